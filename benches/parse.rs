@@ -0,0 +1,119 @@
+#[macro_use]
+extern crate bencher;
+extern crate eno_rust;
+
+use bencher::Bencher;
+use eno_rust::parser::parse;
+
+/// A document with `sections` sections, each holding one of every kind of
+/// element, so the input grows linearly with `sections`.
+fn generate(sections: usize) -> String {
+    let mut input = String::new();
+    for i in 0..sections {
+        input.push_str(&format!("# section {}\n", i));
+        input.push_str("> a comment\n");
+        input.push_str(&format!("name: Value number {}\n", i));
+        input.push_str("| continued on a new line\n");
+        input.push_str("\\ and on the same line\n");
+        input.push_str("colors:\n");
+        input.push_str("primary = #ff0000\n");
+        input.push_str("secondary = #00ff00\n");
+        input.push_str("tags:\n");
+        input.push_str("- first\n");
+        input.push_str("- second\n");
+        input.push_str("-- notes\n");
+        input.push_str("Embedded content\nthat spans lines\n");
+        input.push_str("-- notes\n");
+        input.push_str("`escaped: name`: value\n");
+        input.push('\n');
+    }
+    input
+}
+
+/// A document with a single block of `lines` lines.
+fn generate_block(lines: usize) -> String {
+    let mut input = String::from("-- block\n");
+    for i in 0..lines {
+        input.push_str(&format!(
+            "Line {} of the block, with some text after it\n",
+            i
+        ));
+    }
+    input.push_str("-- block\n");
+    input
+}
+
+/// A document of `length` sections, each deep copying the one before it and
+/// adding a field, so the last one holds every field of the chain.
+fn generate_copy_chain(length: usize) -> String {
+    let mut input = String::from("# section 0\nfield 0: value\n");
+    for i in 1..length {
+        input.push_str(&format!("# section {} << section {}\n", i, i - 1));
+        input.push_str(&format!("field {}: value\n", i));
+    }
+    input
+}
+
+fn bench_input(b: &mut Bencher, input: String) {
+    b.bytes = input.len() as u64;
+    b.iter(|| parse(&input, false).unwrap());
+}
+
+fn bench_parse(b: &mut Bencher, sections: usize) {
+    bench_input(b, generate(sections));
+}
+
+fn parse_1_section(b: &mut Bencher) {
+    bench_parse(b, 1);
+}
+
+fn parse_10_sections(b: &mut Bencher) {
+    bench_parse(b, 10);
+}
+
+fn parse_100_sections(b: &mut Bencher) {
+    bench_parse(b, 100);
+}
+
+fn parse_1000_sections(b: &mut Bencher) {
+    bench_parse(b, 1000);
+}
+
+fn parse_10000_sections(b: &mut Bencher) {
+    bench_parse(b, 10000);
+}
+
+fn parse_1000_line_block(b: &mut Bencher) {
+    bench_input(b, generate_block(1000));
+}
+
+fn parse_100000_line_block(b: &mut Bencher) {
+    bench_input(b, generate_block(100000));
+}
+
+fn parse_10_deep_copies(b: &mut Bencher) {
+    bench_input(b, generate_copy_chain(10));
+}
+
+fn parse_100_deep_copies(b: &mut Bencher) {
+    bench_input(b, generate_copy_chain(100));
+}
+
+fn parse_1000_deep_copies(b: &mut Bencher) {
+    bench_input(b, generate_copy_chain(1000));
+}
+
+benchmark_group!(
+    benches,
+    parse_1_section,
+    parse_10_sections,
+    parse_100_sections,
+    parse_1000_sections,
+    parse_10000_sections,
+    parse_1000_line_block,
+    parse_100000_line_block,
+    parse_10_deep_copies,
+    parse_100_deep_copies,
+    parse_1000_deep_copies
+);
+benchmark_main!(benches);
@@ -0,0 +1,297 @@
+use document::ElementType;
+use error::{ErrorDetails, ParseError};
+use messages::*;
+use parser::ContextValues;
+use std::collections::HashMap;
+use tokenizer::{Instruction, InstructionKind};
+
+/// An element as recognized by the analyzer: the instruction that opens it
+/// together with every instruction that belongs to it.
+#[derive(Clone, Debug)]
+pub struct AnalyzedElement<'i, 'a: 'i> {
+    pub element_type: ElementType,
+    pub instruction: &'i Instruction<'a>,
+    pub continuations: Vec<&'i Instruction<'a>>,
+    pub content: Vec<&'i Instruction<'a>>,
+    pub elements: Vec<AnalyzedElement<'i, 'a>>,
+}
+
+pub struct Analyzer<'i, 'a: 'i> {
+    context: &'i HashMap<&'static str, ContextValues<'a>>,
+    messages: Messages,
+}
+
+impl<'i, 'a> AnalyzedElement<'i, 'a> {
+    fn new(element_type: ElementType, instruction: &'i Instruction<'a>) -> AnalyzedElement<'i, 'a> {
+        AnalyzedElement {
+            element_type,
+            instruction,
+            continuations: Vec::new(),
+            content: Vec::new(),
+            elements: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> Option<&'a str> {
+        self.instruction.name
+    }
+}
+
+impl<'i, 'a> Analyzer<'i, 'a> {
+    pub fn new(context: &'i HashMap<&'static str, ContextValues<'a>>) -> Analyzer<'i, 'a> {
+        Analyzer {
+            context,
+            messages: context
+                .get("Messages")
+                .unwrap()
+                .get_messages()
+                .unwrap()
+                .clone(),
+        }
+    }
+
+    /// Groups the tokenized instructions into elements and nests sections
+    /// by their depth, failing on the first structural error.
+    pub fn analyze(&self) -> Result<Vec<AnalyzedElement<'i, 'a>>, ParseError> {
+        let instructions = self
+            .context
+            .get("Instructions")
+            .unwrap()
+            .get_instruction()
+            .unwrap();
+        let mut elements = Vec::new();
+        let mut sections: Vec<AnalyzedElement<'i, 'a>> = Vec::new();
+        // Whether the last element of the current section still accepts
+        // list items, fieldset entries and continuations.
+        let mut open = false;
+
+        for instruction in instructions {
+            match instruction.kind {
+                InstructionKind::Name | InstructionKind::Copy => {
+                    current(&mut sections, &mut elements)
+                        .push(AnalyzedElement::new(ElementType::Empty, instruction));
+                    open = true;
+                }
+                InstructionKind::Field => {
+                    current(&mut sections, &mut elements)
+                        .push(AnalyzedElement::new(ElementType::Field, instruction));
+                    open = true;
+                }
+                InstructionKind::ListItem => {
+                    let element = match current(&mut sections, &mut elements).last_mut() {
+                        Some(element) if open => element,
+                        _ => return Err(self.error("missing_name_for_list_item", instruction)),
+                    };
+                    match element.element_type {
+                        ElementType::Empty => element.element_type = ElementType::List,
+                        ElementType::List => {}
+                        ElementType::Fieldset => {
+                            return Err(self.error("list_item_in_fieldset", instruction))
+                        }
+                        _ => return Err(self.error("list_item_in_field", instruction)),
+                    }
+                    element
+                        .elements
+                        .push(AnalyzedElement::new(ElementType::ListItem, instruction));
+                }
+                InstructionKind::FieldsetEntry => {
+                    let element = match current(&mut sections, &mut elements).last_mut() {
+                        Some(element) if open => element,
+                        _ => return Err(self.error("missing_name_for_fieldset_entry", instruction)),
+                    };
+                    match element.element_type {
+                        ElementType::Empty => element.element_type = ElementType::Fieldset,
+                        ElementType::Fieldset => {}
+                        ElementType::List => {
+                            return Err(self.error("fieldset_entry_in_list", instruction))
+                        }
+                        _ => return Err(self.error("fieldset_entry_in_field", instruction)),
+                    }
+                    let entry = AnalyzedElement::new(ElementType::FieldsetEntry, instruction);
+                    if element.elements.iter().any(|e| e.name() == entry.name()) {
+                        let message = Analysis::msg_with_two_values(
+                            &self.messages.analysis,
+                            "duplicate_fieldset_entry_name",
+                            element.name().unwrap(),
+                            entry.name().unwrap(),
+                        );
+                        return Err(self.located(message, instruction));
+                    }
+                    element.elements.push(entry);
+                }
+                InstructionKind::Continuation => {
+                    let element = match current(&mut sections, &mut elements).last_mut() {
+                        Some(element) if open => element,
+                        _ => {
+                            return Err(self.error("missing_element_for_continuation", instruction))
+                        }
+                    };
+                    if element.element_type == ElementType::Empty {
+                        element.element_type = ElementType::Field;
+                    }
+                    match element.elements.last_mut() {
+                        Some(child) => child.continuations.push(instruction),
+                        None => element.continuations.push(instruction),
+                    }
+                }
+                InstructionKind::Section => {
+                    let depth = instruction.depth;
+                    if depth > sections.len() + 1 {
+                        return Err(self.error("section_hierarchy_layer_skip", instruction));
+                    }
+                    while sections.len() >= depth {
+                        close_section(&mut sections, &mut elements);
+                    }
+                    sections.push(AnalyzedElement::new(ElementType::Section, instruction));
+                    open = false;
+                }
+                InstructionKind::Block => {
+                    current(&mut sections, &mut elements)
+                        .push(AnalyzedElement::new(ElementType::Block, instruction));
+                    open = false;
+                }
+                InstructionKind::BlockContent => {
+                    if let Some(block) = current(&mut sections, &mut elements).last_mut() {
+                        block.content.push(instruction);
+                    }
+                }
+                _ => {}
+            }
+        }
+        while !sections.is_empty() {
+            close_section(&mut sections, &mut elements);
+        }
+
+        Ok(elements)
+    }
+
+    fn error(&self, el_name: &str, instruction: &Instruction<'a>) -> ParseError {
+        let line = instruction.line + self.indexing();
+        let message = Analysis::msg_with_one_value(&self.messages.analysis, el_name, line);
+        self.located(message, instruction)
+    }
+
+    fn located(&self, message: String, instruction: &Instruction<'a>) -> ParseError {
+        ParseError::Analysis(ErrorDetails::from_instruction(
+            message,
+            instruction,
+            self.indexing(),
+        ))
+    }
+
+    fn indexing(&self) -> usize {
+        *self
+            .context
+            .get("Indexing")
+            .unwrap()
+            .get_indexing()
+            .unwrap()
+    }
+}
+
+fn current<'s, 'i, 'a>(
+    sections: &'s mut Vec<AnalyzedElement<'i, 'a>>,
+    elements: &'s mut Vec<AnalyzedElement<'i, 'a>>,
+) -> &'s mut Vec<AnalyzedElement<'i, 'a>> {
+    match sections.last_mut() {
+        Some(section) => &mut section.elements,
+        None => elements,
+    }
+}
+
+fn close_section<'i, 'a>(
+    sections: &mut Vec<AnalyzedElement<'i, 'a>>,
+    elements: &mut Vec<AnalyzedElement<'i, 'a>>,
+) {
+    let section = sections.pop().unwrap();
+    current(sections, elements).push(section);
+}
+
+#[cfg(test)]
+mod tests {
+    use document::Element;
+    use error::ParseError;
+    use parser::parse;
+
+    fn analysis_error(input: &str) -> (String, usize) {
+        match parse(input, false) {
+            Err(ParseError::Analysis(details)) => (details.message, details.line),
+            result => panic!("{:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn reports_each_analysis_error() {
+        let cases = [
+            (
+                "f:\na = 1\na = 2\n",
+                "The fieldset 'f' contains two entries named 'a'.",
+                3,
+            ),
+            (
+                "f: v\na = 1\n",
+                "Line 2 contains a fieldset entry inside a field.",
+                2,
+            ),
+            (
+                "l:\n- x\na = 1\n",
+                "Line 3 contains a fieldset entry inside a list.",
+                3,
+            ),
+            (
+                "f: v\n- x\n",
+                "Line 2 contains a list item inside a field.",
+                2,
+            ),
+            (
+                "f:\na = 1\n- x\n",
+                "Line 3 contains a list item inside a fieldset.",
+                3,
+            ),
+            (
+                "| x\n",
+                "Line 1 contains a continuation without any continuable element being specified before.",
+                1,
+            ),
+            (
+                "a = 1\n",
+                "Line 1 contains a fieldset entry without a name for a fieldset being specified before.",
+                1,
+            ),
+            (
+                "- x\n",
+                "Line 1 contains a list item without a name for a list being specified before.",
+                1,
+            ),
+            (
+                "# a\n### b\n",
+                "Line 2 starts a section that is more than one level deeper than the current one.",
+                2,
+            ),
+        ];
+        for &(input, message, line) in &cases {
+            assert_eq!(
+                analysis_error(input),
+                (message.to_string(), line),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn continuations_extend_fields_and_entries() {
+        let document = parse("f: a\n\\ b\n| c\ns:\nx = 1\n\\ 2\n", false).unwrap();
+        let elements = document.elements();
+        match elements[0] {
+            Element::Field(field) => assert_eq!(field.value(), Some("a b\nc")),
+            _ => unreachable!(),
+        }
+        match elements[1] {
+            Element::Fieldset(fieldset) => {
+                assert_eq!(fieldset.entries()[0].value(), Some("1 2"))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use document::Element;
+use error::CatalogError;
+use messages::{placeholders, value_names, Messages};
+use parser::parse;
+use serde_json;
+use std::borrow::Cow;
+
+/// Loads a catalog written in eno, with a section per category of `Messages`
+/// and a field or block per message it overrides:
+///
+/// ```text
+/// # validation
+/// missing_field: We need to know '{}' - please add it.
+/// min_count_not_met: '{name}' needs {expected} items, not {actual}.
+/// ```
+///
+/// A message has as many placeholders as the English one. `{}` takes the
+/// value at its position in the English message, and a named placeholder the
+/// value of its name: `line`, `name`, `actual`, `expected`, `error`, or for
+/// `duplicate_fieldset_entry_name` `fieldset` and `entry`. Messages the
+/// catalog leaves out are the English ones.
+pub fn from_eno(input: &str) -> Result<Messages, CatalogError> {
+    let document = parse(input, false).map_err(CatalogError::Parse)?;
+    let mut overrides = Vec::new();
+    for element in document.elements() {
+        let category = match element {
+            Element::Section(section) => section,
+            other => return Err(CatalogError::InvalidEntry(other.name().to_string())),
+        };
+        for message in category.elements() {
+            let template = match message {
+                Element::Field(field) => field.value(),
+                Element::Block(block) => block.value(),
+                _ => None,
+            };
+            match template {
+                Some(template) => overrides.push((
+                    category.name().to_string(),
+                    message.name().to_string(),
+                    template.to_string(),
+                )),
+                None => return Err(CatalogError::InvalidEntry(message.name().to_string())),
+            }
+        }
+    }
+    apply(overrides)
+}
+
+/// Loads a catalog written in JSON, as an object of categories holding the
+/// messages they override, like `{"validation": {"missing_field": "..."}}`.
+pub fn from_json(input: &str) -> Result<Messages, CatalogError> {
+    let catalog: serde_json::Value =
+        serde_json::from_str(input).map_err(|error| CatalogError::Json(error.to_string()))?;
+    let categories = catalog
+        .as_object()
+        .ok_or_else(|| CatalogError::InvalidEntry(catalog.to_string()))?;
+    let mut overrides = Vec::new();
+    for (category, messages) in categories {
+        let messages = messages
+            .as_object()
+            .ok_or_else(|| CatalogError::InvalidEntry(category.clone()))?;
+        for (key, template) in messages {
+            match template.as_str() {
+                Some(template) => {
+                    overrides.push((category.clone(), key.clone(), template.to_string()))
+                }
+                None => return Err(CatalogError::InvalidEntry(key.clone())),
+            }
+        }
+    }
+    apply(overrides)
+}
+
+/// Checks every override against the English template of the same name
+/// before applying any.
+fn apply(overrides: Vec<(String, String, String)>) -> Result<Messages, CatalogError> {
+    let mut messages = Messages::get_messages("en").unwrap();
+    for (category, key, template) in &overrides {
+        let expected = match messages.template_mut(category, key) {
+            Some(english) => placeholders(english).len(),
+            None => return Err(CatalogError::UnknownKey(format!("{}.{}", category, key))),
+        };
+        let found = placeholders(template);
+        if found.len() != expected {
+            return Err(CatalogError::PlaceholderMismatch {
+                key: format!("{}.{}", category, key),
+                expected,
+                found: found.len(),
+            });
+        }
+        let names = value_names(category, key);
+        if let Some(name) = found
+            .into_iter()
+            .find(|name| !name.is_empty() && !names.contains(name))
+        {
+            return Err(CatalogError::UnknownPlaceholder {
+                key: format!("{}.{}", category, key),
+                name: name.to_string(),
+            });
+        }
+    }
+    for (category, key, template) in overrides {
+        *messages.template_mut(&category, &key).unwrap() = Cow::Owned(template);
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_with_messages;
+    use query::ItemCount;
+
+    #[test]
+    fn overrides_replace_only_their_templates() {
+        let english = Messages::get_messages("en").unwrap();
+        let catalog = "# validation\nmissing_field: We need to know '{}'.\n";
+        let messages = from_eno(catalog).unwrap();
+        assert_eq!(messages.validation.missing_field, "We need to know '{}'.");
+        assert!(matches!(messages.validation.missing_field, Cow::Owned(_)));
+        assert_eq!(
+            messages.validation.missing_list,
+            english.validation.missing_list
+        );
+        let json = r#"{"loaders": {"invalid_url": "'{}' is no URL."}}"#;
+        let messages = from_json(json).unwrap();
+        assert_eq!(messages.loaders.invalid_url, "'{}' is no URL.");
+    }
+
+    #[test]
+    fn overrides_keep_the_placeholders() {
+        match from_json(r#"{"validation": {"missing_field": "Missing."}}"#) {
+            Err(CatalogError::PlaceholderMismatch {
+                expected, found, ..
+            }) => {
+                assert_eq!((expected, found), (1, 0))
+            }
+            result => panic!("{:?}", result),
+        }
+        match from_eno("# validation\nmissing_field: '{field}'\n") {
+            Err(CatalogError::UnknownPlaceholder { key, name }) => {
+                assert_eq!(
+                    (key.as_str(), name.as_str()),
+                    ("validation.missing_field", "field")
+                )
+            }
+            result => panic!("{:?}", result),
+        }
+        match from_eno("# validation\nmissing_thing: '{}'\n") {
+            Err(CatalogError::UnknownKey(key)) => assert_eq!(key, "validation.missing_thing"),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn fills_named_placeholders_by_name() {
+        let catalog =
+            "# validation\nmin_count_not_met: '{name}' needs {expected} items, not {actual}.\n";
+        let messages = from_eno(catalog).unwrap();
+        let document = parse_with_messages("l:\n- a\n", false, messages).unwrap();
+        let error = document
+            .list("l")
+            .unwrap()
+            .items_with(ItemCount::new().min_count(3))
+            .unwrap_err();
+        assert_eq!(error.message(), "'l' needs 3 items, not 1.");
+    }
+
+    #[test]
+    fn parses_with_the_loaded_catalog() {
+        let messages = from_eno("# validation\nmissing_field: We need to know '{}'.\n").unwrap();
+        let document = parse_with_messages("", false, messages).unwrap();
+        assert_eq!(
+            document.field("x").unwrap_err().message(),
+            "We need to know 'x'."
+        );
+    }
+}
@@ -0,0 +1,544 @@
+use analyzer::{AnalyzedElement, Analyzer};
+use document::{Document, ElementType};
+use error::{EditError, ParseError};
+use parser::parse;
+use serializer::{block_dashes, fits_field, Writer};
+use std::cmp;
+use std::fmt;
+use std::ops::Range;
+use tokenizer::{Instruction, InstructionKind, Span, Tokenizer};
+
+/// A document kept exactly as written, with its comments, empty lines and
+/// whitespace, for editing files that people maintain by hand. Writing it
+/// out unedited gives back the input byte for byte, and every edit rewrites
+/// only the lines of the element it changes. An edit that would leave a
+/// document which does not parse is refused, so a `Cst` always reads as a
+/// valid document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cst {
+    input: String,
+    zero_indexing: bool,
+}
+
+/// A replacement of the bytes `range` of the input by `text`.
+struct Splice {
+    range: Range<usize>,
+    text: String,
+}
+
+/// The lines of the input, by the byte each of them starts at.
+struct Lines<'a> {
+    input: &'a str,
+    starts: Vec<usize>,
+}
+
+impl Cst {
+    /// Reads `input`, failing like `parse` if it is not a valid document.
+    pub fn parse(input: &str, zero_indexing: bool) -> Result<Cst, ParseError> {
+        parse(input, zero_indexing)?;
+        Ok(Cst {
+            input: input.to_string(),
+            zero_indexing,
+        })
+    }
+
+    /// The document as it currently reads, which parses since every edit is
+    /// checked before it is applied.
+    pub fn document(&self) -> Document<'_> {
+        parse(&self.input, self.zero_indexing).expect("edits keep the document valid")
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.input
+    }
+
+    /// Sets the value of the field, empty element, fieldset entry or block at
+    /// `path`, like `["section", "fieldset", "entry"]`. The line with the name
+    /// keeps its layout; a field whose value has lines with surrounding
+    /// whitespace becomes a block, which holds them unchanged. Fieldset entries
+    /// cannot become blocks, so such a value is refused for them, and elements
+    /// copying a template have no value of their own to set.
+    pub fn set_value(&mut self, path: &[&str], value: &str) -> Result<(), EditError> {
+        self.edit(path, |lines, elements| {
+            let element = find(elements, path)?;
+            let splices = match element.element_type {
+                ElementType::Field | ElementType::Empty | ElementType::FieldsetEntry
+                    if element.instruction.template.is_some() =>
+                {
+                    return Err(EditError::Copied(path.join("/")));
+                }
+                ElementType::Block => set_block(lines, element, value),
+                ElementType::Field | ElementType::Empty if !fits_field(value) => {
+                    let mut writer = Writer::new();
+                    writer.block(element.name().unwrap(), Some(value));
+                    let block = writer.finish();
+                    let block = block.trim_end_matches('\n');
+                    let mut splices = vec![Splice {
+                        range: lines.range(element.instruction.line),
+                        text: lines.terminated(block),
+                    }];
+                    splices.extend(remove_continuations(lines, element));
+                    splices
+                }
+                ElementType::FieldsetEntry if !fits_field(value) => {
+                    return Err(EditError::Unrepresentable(path.join("/")));
+                }
+                ElementType::Field | ElementType::Empty | ElementType::FieldsetEntry => {
+                    let instruction = element.instruction;
+                    let line = lines.text(instruction.line);
+                    let prefix = match instruction.ranges.value {
+                        Some(span) => line[..span.start.byte - instruction.index].to_string(),
+                        None => {
+                            let operator = instruction
+                                .ranges
+                                .name_operator
+                                .or(instruction.ranges.entry_operator)
+                                .ok_or_else(|| {
+                                    EditError::WrongType(path.join("/"), element.element_type)
+                                })?;
+                            format!("{} ", &line[..operator.end.byte - instruction.index])
+                        }
+                    };
+                    let mut splices = vec![Splice {
+                        range: lines.range(instruction.line),
+                        text: lines.terminated(&continued(&prefix, indent(line), value)),
+                    }];
+                    splices.extend(remove_continuations(lines, element));
+                    splices
+                }
+                element_type => {
+                    return Err(EditError::WrongType(path.join("/"), element_type));
+                }
+            };
+            Ok(splices)
+        })
+    }
+
+    /// Appends an item to the list at `path`, laid out like its last item. An
+    /// empty element becomes a list of this one item.
+    pub fn append_item(&mut self, path: &[&str], value: &str) -> Result<(), EditError> {
+        self.edit(path, |lines, elements| {
+            let element = find(elements, path)?;
+            let (after, prefix) = match element.element_type {
+                ElementType::List => {
+                    let item = element.elements.last().unwrap();
+                    let instruction = item.instruction;
+                    let line = lines.text(instruction.line);
+                    let prefix = match instruction.ranges.value {
+                        Some(span) => line[..span.start.byte - instruction.index].to_string(),
+                        None => format!("{} ", line.trim_end()),
+                    };
+                    (last_line(item), prefix)
+                }
+                ElementType::Empty => {
+                    let line = element.instruction.line;
+                    (line, format!("{}- ", indent(lines.text(line))))
+                }
+                element_type => {
+                    return Err(EditError::WrongType(path.join("/"), element_type));
+                }
+            };
+            let end = lines.range(after).end;
+            let item = continued(&prefix, indent(&prefix), value);
+            Ok(vec![Splice {
+                range: end..end,
+                text: format!("\n{}", lines.terminated(&item)),
+            }])
+        })
+    }
+
+    /// Lays out every line the same way: without indentation or trailing
+    /// whitespace, and with one space between operators, names and values.
+    /// Comments, copy operators and the content of blocks stay as written,
+    /// so the document reads the same afterwards.
+    pub fn format(&mut self) {
+        let lines = Lines::new(&self.input);
+        let mut formatted = String::with_capacity(self.input.len());
+        for instruction in Tokenizer::from_input(&self.input, self.zero_indexing) {
+            let instruction = instruction.expect("edits keep the document valid");
+            let line = &self.input[lines.range(instruction.line)];
+            if instruction.line > 0 {
+                formatted.push('\n');
+            }
+            match formatted_line(&self.input, &instruction) {
+                Some(text) => {
+                    formatted.push_str(&text);
+                    if line.ends_with('\r') {
+                        formatted.push('\r');
+                    }
+                }
+                None => formatted.push_str(line),
+            }
+        }
+        self.input = formatted;
+    }
+
+    /// Analyzes the input for `splices` to find the lines to change, then
+    /// applies them from the last one up so the ranges of the others stay
+    /// valid. The input is only replaced if the edited one parses, since an
+    /// edit may still break the document around it, like an item appended to
+    /// an element copying a field.
+    fn edit<F>(&mut self, path: &[&str], splices: F) -> Result<(), EditError>
+    where
+        F: FnOnce(&Lines<'_>, &[AnalyzedElement<'_, '_>]) -> Result<Vec<Splice>, EditError>,
+    {
+        let mut splices = {
+            let lines = Lines::new(&self.input);
+            let mut tokenizer = Tokenizer::from_input(&self.input, self.zero_indexing);
+            tokenizer.tokenize().expect("edits keep the document valid");
+            let elements = Analyzer::new(&tokenizer.context)
+                .analyze()
+                .expect("edits keep the document valid");
+            splices(&lines, &elements)?
+        };
+        splices.sort_by_key(|splice| cmp::Reverse(splice.range.start));
+        let mut input = self.input.clone();
+        for splice in splices {
+            input.replace_range(splice.range, &splice.text);
+        }
+        if let Err(error) = parse(&input, self.zero_indexing) {
+            return Err(EditError::Invalid(path.join("/"), error));
+        }
+        self.input = input;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.input)
+    }
+}
+
+impl<'a> Lines<'a> {
+    fn new(input: &'a str) -> Lines<'a> {
+        let mut starts = vec![0];
+        starts.extend(input.match_indices('\n').map(|(index, _)| index + 1));
+        Lines { input, starts }
+    }
+
+    /// The bytes of line `number`, without its line break.
+    fn range(&self, number: usize) -> Range<usize> {
+        let end = match self.starts.get(number + 1) {
+            Some(next) => next - 1,
+            None => self.input.len(),
+        };
+        self.starts[number]..end
+    }
+
+    /// Line `number` without a trailing carriage return.
+    fn text(&self, number: usize) -> &'a str {
+        let line = &self.input[self.range(number)];
+        line.trim_end_matches('\r')
+    }
+
+    /// `text` with the line endings of the input, which keep a carriage
+    /// return before the line break if the first line does.
+    fn terminated(&self, text: &str) -> String {
+        if self.input[self.range(0)].ends_with('\r') {
+            text.split('\n')
+                .map(|line| format!("{}\r", line))
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// The canonical layout of the line of `instruction`, or `None` for a line
+/// to keep as it is.
+fn formatted_line(input: &str, instruction: &Instruction<'_>) -> Option<String> {
+    let ranges = &instruction.ranges;
+    let text = |span: Option<Span>| span.map_or("", |span| &input[span.start.byte..span.end.byte]);
+    let name = match (ranges.escape_begin_operator, ranges.escape_end_operator) {
+        (Some(begin), Some(end)) => &input[begin.start.byte..end.end.byte],
+        _ => text(ranges.name),
+    };
+    let with_value = |prefix: &str| match instruction.value {
+        Some(value) if !value.is_empty() => format!("{} {}", prefix, value),
+        _ => prefix.to_string(),
+    };
+    let line = match instruction.kind {
+        InstructionKind::EmptyLine => String::new(),
+        InstructionKind::Field => with_value(&format!("{}:", name)),
+        InstructionKind::Name => format!("{}:", name),
+        InstructionKind::ListItem => with_value("-"),
+        InstructionKind::FieldsetEntry => with_value(&format!("{} =", name)),
+        InstructionKind::Continuation => with_value(text(
+            ranges
+                .newline_continuation_operator
+                .or(ranges.line_continuation_operator),
+        )),
+        InstructionKind::Section => {
+            let heading = format!("{} {}", text(ranges.section_operator), name);
+            match instruction.template {
+                Some(template) => {
+                    let operator = text(ranges.copy_operator.or(ranges.deep_copy_operator));
+                    format!("{} {} {}", heading, operator, template)
+                }
+                None => heading,
+            }
+        }
+        InstructionKind::Copy => format!(
+            "{} {} {}",
+            name,
+            text(ranges.copy_operator),
+            instruction.template.unwrap()
+        ),
+        InstructionKind::Block | InstructionKind::BlockTerminator => {
+            format!("{} {}", text(ranges.block_operator), name)
+        }
+        InstructionKind::Comment => {
+            let operator = ranges.comment_operator.unwrap();
+            let end = instruction.index + instruction.length;
+            format!(">{}", input[operator.end.byte..end].trim_end())
+        }
+        InstructionKind::BlockContent | InstructionKind::Unparsed => return None,
+    };
+    Some(line)
+}
+
+/// The element at `path`, looked up by name from the top of the document.
+fn find<'e, 'i, 'a>(
+    elements: &'e [AnalyzedElement<'i, 'a>],
+    path: &[&str],
+) -> Result<&'e AnalyzedElement<'i, 'a>, EditError> {
+    let mut elements = elements;
+    let mut found = None;
+    for name in path {
+        let mut matches = elements.iter().filter(|e| e.name() == Some(*name));
+        let element = match (matches.next(), matches.next()) {
+            (Some(element), None) => element,
+            (None, _) => return Err(EditError::NotFound(path.join("/"))),
+            (Some(_), Some(_)) => return Err(EditError::Ambiguous(path.join("/"))),
+        };
+        elements = &element.elements;
+        found = Some(element);
+    }
+    found.ok_or_else(|| EditError::NotFound(String::new()))
+}
+
+/// Replaces the content of a block, changing the number of dashes on both
+/// of its operator lines only if the new content would end the block early.
+fn set_block(lines: &Lines<'_>, element: &AnalyzedElement<'_, '_>, value: &str) -> Vec<Splice> {
+    let instruction = element.instruction;
+    let name = instruction.name.unwrap();
+    let dashes = instruction.ranges.block_operator.unwrap().len();
+    let first = instruction.line;
+    let terminator = first + element.content.len() + 1;
+    let content = match element.content.len() {
+        0 => lines.range(first).end..lines.range(first).end,
+        _ => lines.starts[first + 1] - 1..lines.range(terminator - 1).end,
+    };
+    let mut splices = vec![Splice {
+        range: content,
+        text: format!("\n{}", lines.terminated(value)),
+    }];
+    let needed = block_dashes(name, value, dashes);
+    if needed != dashes {
+        for &line in &[first, terminator] {
+            let text = lines.text(line);
+            let start = lines.starts[line] + indent(text).len();
+            splices.push(Splice {
+                range: start..start + dashes,
+                text: "-".repeat(needed),
+            });
+        }
+    }
+    splices
+}
+
+fn remove_continuations(lines: &Lines<'_>, element: &AnalyzedElement<'_, '_>) -> Vec<Splice> {
+    element
+        .continuations
+        .iter()
+        .map(|continuation| {
+            let range = lines.range(continuation.line);
+            Splice {
+                range: range.start - 1..range.end,
+                text: String::new(),
+            }
+        })
+        .collect()
+}
+
+/// `value` after `prefix`, with its further lines continued by `|` at
+/// `indent`. An empty value leaves no whitespace after the prefix.
+fn continued(prefix: &str, indent: &str, value: &str) -> String {
+    let mut lines = value.split('\n');
+    let mut text = format!("{}{}", prefix, lines.next().unwrap());
+    if value.is_empty() {
+        text.truncate(prefix.trim_end().len());
+    }
+    for line in lines {
+        text.push('\n');
+        text.push_str(indent);
+        text.push('|');
+        if !line.is_empty() {
+            text.push(' ');
+            text.push_str(line);
+        }
+    }
+    text
+}
+
+fn last_line(element: &AnalyzedElement<'_, '_>) -> usize {
+    element
+        .continuations
+        .iter()
+        .map(|continuation| continuation.line)
+        .fold(element.instruction.line, cmp::max)
+}
+
+fn indent(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_refuse_values_they_cannot_hold() {
+        let mut cst = Cst::parse("fieldset:\nentry = value\n", false).unwrap();
+        for value in &[" padded", "trailing ", "two\n lines", "\nleading"] {
+            match cst.set_value(&["fieldset", "entry"], value) {
+                Err(EditError::Unrepresentable(path)) => assert_eq!(path, "fieldset/entry"),
+                result => panic!("{:?} for {:?}", result, value),
+            }
+        }
+        assert_eq!(cst.as_str(), "fieldset:\nentry = value\n");
+        cst.set_value(&["fieldset", "entry"], "two\nlines").unwrap();
+        let document = cst.document();
+        let fieldset = document.fieldset("fieldset").unwrap();
+        assert_eq!(fieldset.entry("entry").unwrap().value(), Some("two\nlines"));
+    }
+
+    const INPUT: &str = "\
+> A comment
+title:   Notes  
+  list:
+  - a
+  -   b
+
+fieldset:
+x = 1
+  y   =   2
+-- block
+  kept   as is
+-- block
+# section < template
+# template
+name: value
+";
+
+    #[test]
+    fn writes_the_input_back_byte_for_byte() {
+        for input in &[
+            INPUT,
+            "",
+            "\n\n",
+            "a: 1\r\nb: 2\r\n",
+            "a: 1",
+            "  # s\n\tf:x",
+        ] {
+            let cst = Cst::parse(input, false).unwrap();
+            assert_eq!(cst.as_str(), *input);
+            assert_eq!(cst.to_string(), *input);
+        }
+    }
+
+    #[test]
+    fn edits_only_the_lines_of_the_element() {
+        let mut cst = Cst::parse(INPUT, false).unwrap();
+        cst.set_value(&["title"], "Plans").unwrap();
+        cst.set_value(&["fieldset", "y"], "3").unwrap();
+        cst.set_value(&["block"], "new\ncontent").unwrap();
+        cst.append_item(&["list"], "c").unwrap();
+        let expected = INPUT
+            .replace("title:   Notes  ", "title:   Plans")
+            .replace("y   =   2", "y   =   3")
+            .replace("  kept   as is", "new\ncontent")
+            .replace("  -   b\n", "  -   b\n  -   c\n");
+        assert_eq!(cst.as_str(), expected);
+    }
+
+    #[test]
+    fn turns_fields_into_blocks_and_empty_elements_into_lists() {
+        let mut cst = Cst::parse("f: v\n| more\ne:\n", false).unwrap();
+        cst.set_value(&["f"], " padded").unwrap();
+        cst.append_item(&["e"], "first").unwrap();
+        assert_eq!(cst.as_str(), "-- f\n padded\n-- f\ne:\n- first\n");
+        cst.set_value(&["f"], "one\ntwo").unwrap();
+        assert_eq!(cst.document().field("f").unwrap().value(), Some("one\ntwo"));
+    }
+
+    #[test]
+    fn reports_edits_that_do_not_apply() {
+        let mut cst = Cst::parse(INPUT, false).unwrap();
+        match cst.set_value(&["missing"], "x") {
+            Err(EditError::NotFound(path)) => assert_eq!(path, "missing"),
+            result => panic!("{:?}", result),
+        }
+        match cst.set_value(&["list"], "x") {
+            Err(EditError::WrongType(path, ElementType::List)) => assert_eq!(path, "list"),
+            result => panic!("{:?}", result),
+        }
+        match cst.append_item(&["title"], "x") {
+            Err(EditError::WrongType(_, ElementType::Field)) => {}
+            result => panic!("{:?}", result),
+        }
+        let mut cst = Cst::parse("a: 1\na: 2\n", false).unwrap();
+        match cst.set_value(&["a"], "3") {
+            Err(EditError::Ambiguous(path)) => assert_eq!(path, "a"),
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(cst.as_str(), "a: 1\na: 2\n");
+    }
+
+    #[test]
+    fn formats_lines_but_keeps_comments_copies_and_blocks() {
+        let mut cst = Cst::parse(INPUT, false).unwrap();
+        cst.format();
+        assert_eq!(
+            cst.as_str(),
+            "> A comment\ntitle: Notes\nlist:\n- a\n- b\n\nfieldset:\nx = 1\ny = 2\n\
+             -- block\n  kept   as is\n-- block\n# section < template\n# template\nname: value\n"
+        );
+        let formatted = cst.as_str().to_string();
+        cst.format();
+        assert_eq!(cst.as_str(), formatted);
+
+        let mut cst = Cst::parse("a:  1\r\n", false).unwrap();
+        cst.format();
+        assert_eq!(cst.as_str(), "a: 1\r\n");
+    }
+
+    #[test]
+    fn copies_have_no_value_to_set() {
+        let input = "f: v\nc < f\n\\ more\ne < f\n";
+        let mut cst = Cst::parse(input, false).unwrap();
+        for name in &["c", "e"] {
+            match cst.set_value(&[name], "x") {
+                Err(EditError::Copied(path)) => assert_eq!(path, *name),
+                result => panic!("{:?}", result),
+            }
+        }
+        assert_eq!(cst.as_str(), input);
+    }
+
+    #[test]
+    fn refuses_edits_that_leave_an_invalid_document() {
+        let mut cst = Cst::parse("f: v\nl < f\n", false).unwrap();
+        match cst.append_item(&["l"], "x") {
+            Err(EditError::Invalid(path, ParseError::Resolution(details))) => {
+                assert_eq!(path, "l");
+                assert_eq!(details.message, "In line 2 a field is copied into a list.");
+            }
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(cst.as_str(), "f: v\nl < f\n");
+        assert_eq!(cst.document().field("l").unwrap().value(), Some("v"));
+    }
+}
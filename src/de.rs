@@ -0,0 +1,676 @@
+use document::{Document, ElementType, DOCUMENT};
+use error::{DeserializeError, ValidationError};
+use loaders::{self, LoaderError};
+use messages::Validation;
+use parser::parse;
+use query::found_key;
+use serde::de::value::{BorrowedStrDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, IntoDeserializer, Visitor};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::vec;
+
+/// Reads `input` into a `T`. The document and its sections map to structs or
+/// maps of their elements by name, fieldsets to structs or maps of their
+/// entries, lists to sequences of their items, and fields and blocks to their
+/// value, which is converted with the loaders of `loaders` where `T` asks for
+/// a number or a boolean. Elements that occur several times under one name
+/// map to a sequence of them. Elements without a value map to `None`, `()` or,
+/// where `T` asks for a string, the empty string.
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T, DeserializeError> {
+    let document = parse(input, false)?;
+    from_document(&document)
+}
+
+/// Like `from_str`, for a document parsed with other options or messages.
+/// `T` may borrow the values of the document.
+pub fn from_document<'d, T: Deserialize<'d>>(
+    document: &'d Document<'d>,
+) -> Result<T, DeserializeError> {
+    T::deserialize(Elements {
+        document,
+        ids: vec![DOCUMENT],
+    })
+}
+
+/// The elements of one name inside a section, or the document itself.
+struct Elements<'d> {
+    document: &'d Document<'d>,
+    ids: Vec<usize>,
+}
+
+/// The value of a field, block, fieldset entry or list item. Items are
+/// reported by the name of their list, and `missing` is the message for a
+/// value that is required but not there.
+struct Value<'d> {
+    document: &'d Document<'d>,
+    id: usize,
+    name: &'d str,
+    missing: &'static str,
+}
+
+/// The elements or entries of a section or fieldset, keeping track of the one
+/// being read so that errors raised by serde can be located at it.
+struct Entries<'c, 'd, V> {
+    entries: vec::IntoIter<(&'d str, usize, V)>,
+    value: Option<V>,
+    current: &'c Cell<usize>,
+}
+
+impl<'d> Elements<'d> {
+    fn name(&self) -> &'d str {
+        name(self.document, self.ids[0])
+    }
+
+    /// The one element of the name, or `el_name` at the second one.
+    fn single(&self, el_name: &str) -> Result<usize, DeserializeError> {
+        match self.ids[..] {
+            [id] => Ok(id),
+            _ => Err(validation(self.document, el_name, self.name(), self.ids[1])),
+        }
+    }
+
+    fn value(&self) -> Result<Value<'d>, DeserializeError> {
+        let id = self.single("expected_field_got_fields")?;
+        match self.document.nodes[id].element_type {
+            ElementType::Field | ElementType::Block | ElementType::Empty => Ok(Value {
+                document: self.document,
+                id,
+                name: self.name(),
+                missing: "missing_field_value",
+            }),
+            element_type => {
+                let el_name = format!("expected_field_got_{}", found_key(element_type));
+                Err(validation(self.document, &el_name, self.name(), id))
+            }
+        }
+    }
+
+    fn deserialize_items<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let document = self.document;
+        if self.ids.len() > 1 {
+            let elements = self.ids.into_iter().map(|id| Elements {
+                document,
+                ids: vec![id],
+            });
+            return SeqDeserializer::new(elements).deserialize_any(visitor);
+        }
+        let id = self.ids[0];
+        match document.nodes[id].element_type {
+            ElementType::List | ElementType::Empty => {
+                let name = self.name();
+                let items = document.nodes[id].children.iter().map(|item| Value {
+                    document,
+                    id: *item,
+                    name,
+                    missing: "missing_list_item_value",
+                });
+                SeqDeserializer::new(items).deserialize_any(visitor)
+            }
+            _ => SeqDeserializer::new(Some(self).into_iter()).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_entries<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let el_name = match self.document.nodes[self.ids[0]].element_type {
+            ElementType::Fieldset => "expected_fieldset_got_fieldsets",
+            _ => "expected_section_got_sections",
+        };
+        let id = self.single(el_name)?;
+        let document = self.document;
+        let node = &document.nodes[id];
+        match node.element_type {
+            ElementType::Section | ElementType::Empty => {
+                let mut names: Vec<(&'d str, usize, Elements<'d>)> = Vec::new();
+                for child in &node.children {
+                    let name = name(document, *child);
+                    match names.iter_mut().find(|entry| entry.0 == name) {
+                        Some(entry) => entry.2.ids.push(*child),
+                        None => names.push((
+                            name,
+                            *child,
+                            Elements {
+                                document,
+                                ids: vec![*child],
+                            },
+                        )),
+                    }
+                }
+                visit_entries(document, id, names, visitor)
+            }
+            ElementType::Fieldset => {
+                let entries = node
+                    .children
+                    .iter()
+                    .map(|entry| {
+                        let name = name(document, *entry);
+                        let value = Value {
+                            document,
+                            id: *entry,
+                            name,
+                            missing: "missing_fieldset_entry_value",
+                        };
+                        (name, *entry, value)
+                    })
+                    .collect();
+                visit_entries(document, id, entries, visitor)
+            }
+            element_type => {
+                let el_name = format!("expected_section_got_{}", found_key(element_type));
+                Err(validation(document, &el_name, self.name(), id))
+            }
+        }
+    }
+}
+
+impl<'d> Value<'d> {
+    fn value(&self) -> Result<&'d str, DeserializeError> {
+        self.document.nodes[self.id]
+            .value
+            .as_deref()
+            .ok_or_else(|| validation(self.document, self.missing, self.name, self.id))
+    }
+
+    fn load<T>(&self, loader: fn(&str) -> Result<T, LoaderError>) -> Result<T, DeserializeError> {
+        loader(self.value()?).map_err(|error| self.loader_error(error))
+    }
+
+    fn loader_error(&self, error: LoaderError) -> DeserializeError {
+        let message = error.message(&self.document.messages.loaders, self.name);
+        DeserializeError::Validation(ValidationError::Loaders(
+            self.document.details(message, self.id),
+        ))
+    }
+
+    /// Reports errors that the visitor raises without a location, like an
+    /// unknown enum variant, as a problem with this value.
+    fn locate(&self, error: DeserializeError) -> DeserializeError {
+        match error {
+            DeserializeError::Custom(_) => {
+                validation(self.document, "generic_error", self.name, self.id)
+            }
+            error => error,
+        }
+    }
+
+    fn unsupported(&self) -> DeserializeError {
+        validation(self.document, "generic_error", self.name, self.id)
+    }
+}
+
+fn visit_entries<'d, V, X>(
+    document: &'d Document<'d>,
+    id: usize,
+    entries: Vec<(&'d str, usize, X)>,
+    visitor: V,
+) -> Result<V::Value, DeserializeError>
+where
+    V: Visitor<'d>,
+    X: Deserializer<'d, Error = DeserializeError>,
+{
+    let current = Cell::new(id);
+    let result = visitor.visit_map(Entries {
+        entries: entries.into_iter(),
+        value: None,
+        current: &current,
+    });
+    result.map_err(|error| match error {
+        // serde reports missing struct fields without their type, so a
+        // missing list or fieldset is reported as a missing field too.
+        DeserializeError::MissingField(field) => validation(document, "missing_field", field, id),
+        DeserializeError::UnknownField(field) => {
+            validation(document, "excess_name", &field, current.get())
+        }
+        DeserializeError::Custom(_) => validation(
+            document,
+            "generic_error",
+            name(document, current.get()),
+            current.get(),
+        ),
+        error => error,
+    })
+}
+
+fn name<'d>(document: &'d Document<'d>, id: usize) -> &'d str {
+    document.nodes[id].name.as_deref().unwrap_or("")
+}
+
+fn validation(document: &Document, el_name: &str, name: &str, id: usize) -> DeserializeError {
+    let message = Validation::msg_with_one_value(&document.messages.validation, el_name, name);
+    DeserializeError::Validation(ValidationError::Validation(document.details(message, id)))
+}
+
+impl<'c, 'd, V> de::MapAccess<'d> for Entries<'c, 'd, V>
+where
+    V: Deserializer<'d, Error = DeserializeError>,
+{
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'d>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeserializeError> {
+        match self.entries.next() {
+            Some((name, id, value)) => {
+                self.current.set(id);
+                self.value = Some(value);
+                seed.deserialize(BorrowedStrDeserializer::new(name))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'d>>(
+        &mut self,
+        seed: S,
+    ) -> Result<S::Value, DeserializeError> {
+        seed.deserialize(self.value.take().unwrap())
+    }
+}
+
+impl<'d> IntoDeserializer<'d, DeserializeError> for Elements<'d> {
+    type Deserializer = Elements<'d>;
+
+    fn into_deserializer(self) -> Elements<'d> {
+        self
+    }
+}
+
+impl<'d> IntoDeserializer<'d, DeserializeError> for Value<'d> {
+    type Deserializer = Value<'d>;
+
+    fn into_deserializer(self) -> Value<'d> {
+        self
+    }
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+            self.value()?.$method(visitor)
+        }
+    )*};
+}
+
+impl<'d> Deserializer<'d> for Elements<'d> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        if self.ids.len() > 1 {
+            return self.deserialize_items(visitor);
+        }
+        match self.document.nodes[self.ids[0]].element_type {
+            ElementType::Section | ElementType::Fieldset => self.deserialize_entries(visitor),
+            ElementType::List => self.deserialize_items(visitor),
+            ElementType::Empty => visitor.visit_unit(),
+            _ => self.value()?.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_value! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string deserialize_bytes
+        deserialize_byte_buf deserialize_unit deserialize_identifier
+    }
+
+    fn deserialize_option<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        if let [id] = self.ids[..] {
+            let node = &self.document.nodes[id];
+            let valueless = match node.element_type {
+                ElementType::Empty => true,
+                ElementType::Field => node.value.is_none(),
+                _ => false,
+            };
+            if valueless {
+                return visitor.visit_none();
+            }
+        }
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'d>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.value()?.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'d>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.deserialize_items(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'d>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_items(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'d>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_items(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.deserialize_entries(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'d>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_entries(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'d>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.value()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'d>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_unit()
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident($ty:ty))*) => {$(
+        fn $method<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+            let value = <$ty>::try_from(self.load(loaders::integer)?)
+                .map_err(|_| self.loader_error(LoaderError::InvalidInteger))?;
+            visitor.$visit(value).map_err(|error| self.locate(error))
+        }
+    )*};
+}
+
+macro_rules! deserialize_unsupported {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {$(
+        fn $method<V: Visitor<'d>>(
+            self,
+            $(_: $ty,)*
+            _visitor: V,
+        ) -> Result<V::Value, DeserializeError> {
+            Err(self.unsupported())
+        }
+    )*};
+}
+
+impl<'d> Deserializer<'d> for Value<'d> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self.document.nodes[self.id].value.as_deref() {
+            Some(value) => visitor.visit_borrowed_str(value),
+            None => visitor.visit_unit(),
+        }
+        .map_err(|error| self.locate(error))
+    }
+
+    fn deserialize_bool<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let value = self.load(loaders::boolean)?;
+        visitor
+            .visit_bool(value)
+            .map_err(|error| self.locate(error))
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8(i8)
+        deserialize_i16 => visit_i16(i16)
+        deserialize_i32 => visit_i32(i32)
+        deserialize_i64 => visit_i64(i64)
+        deserialize_u8 => visit_u8(u8)
+        deserialize_u16 => visit_u16(u16)
+        deserialize_u32 => visit_u32(u32)
+        deserialize_u64 => visit_u64(u64)
+    }
+
+    fn deserialize_f32<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let value = self.load(loaders::float)?;
+        visitor
+            .visit_f32(value as f32)
+            .map_err(|error| self.locate(error))
+    }
+
+    fn deserialize_f64<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let value = self.load(loaders::float)?;
+        visitor.visit_f64(value).map_err(|error| self.locate(error))
+    }
+
+    fn deserialize_char<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let mut chars = self.value()?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c).map_err(|error| self.locate(error)),
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// An element without a value reads as the empty string, which is how
+    /// `to_string` writes one.
+    fn deserialize_str<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let value = self.document.nodes[self.id].value.as_deref().unwrap_or("");
+        visitor
+            .visit_borrowed_str(value)
+            .map_err(|error| self.locate(error))
+    }
+
+    fn deserialize_string<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let value = self.value()?;
+        visitor
+            .visit_borrowed_bytes(value.as_bytes())
+            .map_err(|error| self.locate(error))
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'d>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self.document.nodes[self.id].value {
+            Some(_) => visitor.visit_some(self),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'d>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'d>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'d>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    deserialize_unsupported! {
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+    }
+
+    fn deserialize_enum<V: Visitor<'d>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        let value = self.value()?;
+        visitor
+            .visit_enum(BorrowedStrDeserializer::new(value))
+            .map_err(|error| self.locate(error))
+    }
+
+    fn deserialize_identifier<V: Visitor<'d>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'d>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_unit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::fmt;
+
+    fn message<T: DeserializeOwned + ::std::fmt::Debug>(input: &str) -> String {
+        match from_str::<T>(input) {
+            Err(DeserializeError::Validation(error)) => error.message().to_string(),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn reads_sections_fieldsets_lists_and_repeated_elements() {
+        type Map = BTreeMap<String, BTreeMap<String, Vec<String>>>;
+        let input = "# a\nl:\n- x\n- y\nf: one\nf: two\n";
+        let read: Map = from_str(input).unwrap();
+        assert_eq!(read["a"]["l"], vec!["x", "y"]);
+        assert_eq!(read["a"]["f"], vec!["one", "two"]);
+
+        let read: BTreeMap<String, BTreeMap<String, u8>> = from_str("fs:\na = 1\nb = 2\n").unwrap();
+        assert_eq!(read["fs"]["b"], 2);
+
+        let read: BTreeMap<String, (i32, bool)> = from_str("pair:\n- -4\n- yes\n").unwrap();
+        assert_eq!(read["pair"], (-4, true));
+    }
+
+    /// A struct with a required field, read the way serde derives it.
+    #[derive(Debug)]
+    struct Named {
+        name: String,
+    }
+
+    impl<'d> Deserialize<'d> for Named {
+        fn deserialize<D: Deserializer<'d>>(deserializer: D) -> Result<Named, D::Error> {
+            struct NamedVisitor;
+
+            impl<'d> Visitor<'d> for NamedVisitor {
+                type Value = Named;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a struct with a name")
+                }
+
+                fn visit_map<A: de::MapAccess<'d>>(self, mut map: A) -> Result<Named, A::Error> {
+                    let mut name = None;
+                    while let Some(key) = map.next_key::<String>()? {
+                        if key == "name" {
+                            name = Some(map.next_value()?);
+                        } else {
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                    let name = name.ok_or_else(|| de::Error::missing_field("name"))?;
+                    Ok(Named { name })
+                }
+            }
+
+            deserializer.deserialize_struct("Named", &["name"], NamedVisitor)
+        }
+    }
+
+    #[test]
+    fn borrows_values_from_the_document() {
+        let document = parse("name: jane\n", false).unwrap();
+        let read: BTreeMap<&str, &str> = from_document(&document).unwrap();
+        assert_eq!(read["name"], "jane");
+    }
+
+    #[test]
+    fn reports_errors_at_the_element() {
+        assert_eq!(
+            message::<BTreeMap<String, i64>>("n: forty\n"),
+            "'n' must contain an integer, for instance '42' or '-21'."
+        );
+        assert_eq!(
+            message::<BTreeMap<String, String>>("l:\n- x\n"),
+            "Instead of the expected field 'l' a list with this name was found."
+        );
+        assert_eq!(
+            message::<BTreeMap<String, String>>("f: 1\nf: 2\n"),
+            "Instead of the expected single field 'f' several fields with this name were found."
+        );
+        assert_eq!(
+            message::<BTreeMap<String, BTreeMap<String, String>>>("# s\n# s\n"),
+            "Instead of the expected single section 's' several sections with this name were found."
+        );
+        assert_eq!(
+            message::<BTreeMap<String, BTreeMap<String, String>>>("fs:\na = 1\nfs:\nb = 2\n"),
+            "Instead of the expected single fieldset 'fs' several fieldsets with this name were found."
+        );
+        assert_eq!(
+            message::<Named>("other: 1\n"),
+            "The field 'name' is missing - in case it has been specified look for typos and also check for correct capitalization."
+        );
+        assert_eq!(from_str::<Named>("name: jane\n").unwrap().name, "jane");
+        assert_eq!(
+            message::<BTreeMap<String, Vec<u8>>>("l:\n- 1\n- x\n"),
+            "'l' must contain an integer, for instance '42' or '-21'."
+        );
+        match from_str::<BTreeMap<String, i64>>("a: 1\nb: x\n") {
+            Err(DeserializeError::Validation(error)) => assert_eq!(error.line(), 2),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn reads_elements_without_a_value_as_none_or_empty() {
+        let read: BTreeMap<String, Option<String>> = from_str("a:\nb: 1\n").unwrap();
+        assert_eq!(read["a"], None);
+        assert_eq!(read["b"], Some("1".to_string()));
+        let read: BTreeMap<String, String> = from_str("a:\n").unwrap();
+        assert_eq!(read["a"], "");
+    }
+}
@@ -0,0 +1,694 @@
+use analyzer::AnalyzedElement;
+use error::{instruction_span, ErrorDetails};
+use messages::Messages;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fmt;
+use std::ops::Range;
+use tokenizer::Instruction;
+
+pub(crate) const DOCUMENT: usize = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElementType {
+    Section,
+    Field,
+    Fieldset,
+    FieldsetEntry,
+    List,
+    ListItem,
+    Block,
+    Empty,
+}
+
+#[derive(Clone)]
+pub(crate) struct Node<'a> {
+    pub(crate) element_type: ElementType,
+    pub(crate) name: Option<Cow<'a, str>>,
+    pub(crate) value: Option<String>,
+    pub(crate) template: Option<Cow<'a, str>>,
+    pub(crate) deep_copy: bool,
+    pub(crate) depth: usize,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) range: Range<usize>,
+    pub(crate) parent: Option<usize>,
+    pub(crate) children: Vec<usize>,
+    /// Whether application code has queried the element.
+    pub(crate) touched: Cell<bool>,
+}
+
+/// The parsed document. Owns every element; the typed element handles
+/// (`Section`, `Field`, ...) borrow from it and navigate by id. Names
+/// borrow from the input unless the document was made owned.
+#[derive(Clone)]
+pub struct Document<'a> {
+    pub(crate) nodes: Vec<Node<'a>>,
+    pub(crate) indexing: usize,
+    pub(crate) messages: Messages,
+}
+
+#[derive(Clone, Copy)]
+pub struct Section<'d> {
+    pub(crate) document: &'d Document<'d>,
+    pub(crate) id: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct Field<'d> {
+    pub(crate) document: &'d Document<'d>,
+    pub(crate) id: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct Fieldset<'d> {
+    pub(crate) document: &'d Document<'d>,
+    pub(crate) id: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct FieldsetEntry<'d> {
+    pub(crate) document: &'d Document<'d>,
+    pub(crate) id: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct List<'d> {
+    pub(crate) document: &'d Document<'d>,
+    pub(crate) id: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct ListItem<'d> {
+    pub(crate) document: &'d Document<'d>,
+    pub(crate) id: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct Block<'d> {
+    pub(crate) document: &'d Document<'d>,
+    pub(crate) id: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct Empty<'d> {
+    pub(crate) document: &'d Document<'d>,
+    pub(crate) id: usize,
+}
+
+/// Any element that can appear directly inside the document or a section.
+#[derive(Clone, Copy, Debug)]
+pub enum Element<'d> {
+    Section(Section<'d>),
+    Field(Field<'d>),
+    Fieldset(Fieldset<'d>),
+    List(List<'d>),
+    Block(Block<'d>),
+    Empty(Empty<'d>),
+}
+
+impl<'a> Document<'a> {
+    pub fn from_analysis(
+        elements: &[AnalyzedElement<'_, 'a>],
+        input: &'a str,
+        indexing: usize,
+        messages: Messages,
+    ) -> Document<'a> {
+        let mut document = Document {
+            nodes: vec![Node {
+                element_type: ElementType::Section,
+                name: None,
+                value: None,
+                template: None,
+                deep_copy: false,
+                depth: 0,
+                line: 0,
+                column: 0,
+                range: 0..0,
+                parent: None,
+                children: Vec::new(),
+                touched: Cell::new(false),
+            }],
+            indexing,
+            messages,
+        };
+        for element in elements {
+            document.append(DOCUMENT, element, input);
+        }
+        document
+    }
+
+    fn append(&mut self, parent: usize, element: &AnalyzedElement<'_, 'a>, input: &'a str) {
+        let instruction = element.instruction;
+        let value = match element.element_type {
+            ElementType::Block if !element.content.is_empty() => Some(
+                element
+                    .content
+                    .iter()
+                    .map(|line| &input[line.index..line.index + line.length])
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            _ => element.continuations.iter().fold(
+                element_value(instruction),
+                |value, continuation| {
+                    let separator = continuation.separator.unwrap();
+                    match (value, element_value(continuation)) {
+                        (None, continued) => continued,
+                        (Some(value), None) if separator == " " => Some(value),
+                        (Some(value), continued) => Some(format!(
+                            "{}{}{}",
+                            value,
+                            separator,
+                            continued.unwrap_or_default()
+                        )),
+                    }
+                },
+            ),
+        };
+        let (column, range) = instruction_span(instruction);
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            element_type: element.element_type,
+            name: element.name().map(Cow::Borrowed),
+            value,
+            template: instruction.template.map(Cow::Borrowed),
+            deep_copy: instruction.deep_copy,
+            depth: instruction.depth,
+            line: instruction.line,
+            column,
+            range,
+            parent: Some(parent),
+            children: Vec::new(),
+            touched: Cell::new(false),
+        });
+        self.nodes[parent].children.push(id);
+        for child in &element.elements {
+            self.append(id, child, input);
+        }
+    }
+
+    pub(crate) fn element_at(&self, id: usize) -> Element<'_> {
+        match self.nodes[id].element_type {
+            ElementType::Section => Element::Section(Section { document: self, id }),
+            ElementType::Field => Element::Field(Field { document: self, id }),
+            ElementType::Fieldset => Element::Fieldset(Fieldset { document: self, id }),
+            ElementType::List => Element::List(List { document: self, id }),
+            ElementType::Block => Element::Block(Block { document: self, id }),
+            ElementType::Empty => Element::Empty(Empty { document: self, id }),
+            ElementType::FieldsetEntry | ElementType::ListItem => {
+                unreachable!("entries and items only occur inside fieldsets and lists")
+            }
+        }
+    }
+
+    fn parent_section(&self, id: usize) -> Option<Section<'_>> {
+        match self.nodes[id].parent {
+            Some(DOCUMENT) | None => None,
+            Some(parent) => Some(Section {
+                document: self,
+                id: parent,
+            }),
+        }
+    }
+
+    pub(crate) fn line(&self, id: usize) -> usize {
+        self.nodes[id].line + self.indexing
+    }
+
+    /// Locates an error message at the given element.
+    pub(crate) fn details(&self, message: String, id: usize) -> ErrorDetails {
+        let node = &self.nodes[id];
+        ErrorDetails::new(
+            message,
+            node.line,
+            node.column,
+            node.range.clone(),
+            self.indexing,
+        )
+    }
+
+    /// Detaches the document from its input by copying every borrowed name.
+    pub fn into_owned(self) -> Document<'static> {
+        Document {
+            nodes: self.nodes.into_iter().map(Node::into_owned).collect(),
+            indexing: self.indexing,
+            messages: self.messages,
+        }
+    }
+
+    /// The elements at the top level of the document, in document order.
+    pub fn elements(&self) -> Vec<Element<'_>> {
+        self.nodes[DOCUMENT]
+            .children
+            .iter()
+            .map(|id| self.element_at(*id))
+            .collect()
+    }
+}
+
+impl<'a> Node<'a> {
+    fn into_owned(self) -> Node<'static> {
+        Node {
+            element_type: self.element_type,
+            name: self.name.map(|name| Cow::Owned(name.into_owned())),
+            value: self.value,
+            template: self
+                .template
+                .map(|template| Cow::Owned(template.into_owned())),
+            deep_copy: self.deep_copy,
+            depth: self.depth,
+            line: self.line,
+            column: self.column,
+            range: self.range,
+            parent: self.parent,
+            children: self.children,
+            touched: self.touched,
+        }
+    }
+}
+
+fn element_value(instruction: &Instruction<'_>) -> Option<String> {
+    instruction.value.map(|v| v.to_string())
+}
+
+impl<'d> Element<'d> {
+    pub fn element_type(&self) -> ElementType {
+        match self {
+            Element::Section(_) => ElementType::Section,
+            Element::Field(_) => ElementType::Field,
+            Element::Fieldset(_) => ElementType::Fieldset,
+            Element::List(_) => ElementType::List,
+            Element::Block(_) => ElementType::Block,
+            Element::Empty(_) => ElementType::Empty,
+        }
+    }
+
+    pub fn name(&self) -> &'d str {
+        match self {
+            Element::Section(e) => e.name(),
+            Element::Field(e) => e.name(),
+            Element::Fieldset(e) => e.name(),
+            Element::List(e) => e.name(),
+            Element::Block(e) => e.name(),
+            Element::Empty(e) => e.name(),
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        match self {
+            Element::Section(e) => e.line(),
+            Element::Field(e) => e.line(),
+            Element::Fieldset(e) => e.line(),
+            Element::List(e) => e.line(),
+            Element::Block(e) => e.line(),
+            Element::Empty(e) => e.line(),
+        }
+    }
+
+    /// Marks the element as read, so `assert_all_touched` accepts it.
+    pub fn touch(&self) {
+        match self {
+            Element::Section(e) => e.touch(),
+            Element::Field(e) => e.touch(),
+            Element::Fieldset(e) => e.touch(),
+            Element::List(e) => e.touch(),
+            Element::Block(e) => e.touch(),
+            Element::Empty(e) => e.touch(),
+        }
+    }
+
+    /// The enclosing section, or `None` for elements at the document level.
+    pub fn parent(&self) -> Option<Section<'d>> {
+        match self {
+            Element::Section(e) => e.parent(),
+            Element::Field(e) => e.parent(),
+            Element::Fieldset(e) => e.parent(),
+            Element::List(e) => e.parent(),
+            Element::Block(e) => e.parent(),
+            Element::Empty(e) => e.parent(),
+        }
+    }
+}
+
+impl<'d> Section<'d> {
+    pub fn name(&self) -> &'d str {
+        self.document.nodes[self.id].name.as_deref().unwrap()
+    }
+    pub fn line(&self) -> usize {
+        self.document.line(self.id)
+    }
+    pub fn touch(&self) {
+        self.document.nodes[self.id].touched.set(true);
+    }
+    pub fn depth(&self) -> usize {
+        self.document.nodes[self.id].depth
+    }
+    pub fn template(&self) -> Option<&'d str> {
+        self.document.nodes[self.id].template.as_deref()
+    }
+    pub fn deep_copy(&self) -> bool {
+        self.document.nodes[self.id].deep_copy
+    }
+    pub fn parent(&self) -> Option<Section<'d>> {
+        self.document.parent_section(self.id)
+    }
+    pub fn elements(&self) -> Vec<Element<'d>> {
+        let document = self.document;
+        document.nodes[self.id]
+            .children
+            .iter()
+            .map(|id| document.element_at(*id))
+            .collect()
+    }
+}
+
+impl<'d> Field<'d> {
+    pub fn name(&self) -> &'d str {
+        self.document.nodes[self.id].name.as_deref().unwrap()
+    }
+    pub fn line(&self) -> usize {
+        self.document.line(self.id)
+    }
+    pub fn touch(&self) {
+        self.document.nodes[self.id].touched.set(true);
+    }
+    pub fn value(&self) -> Option<&'d str> {
+        self.document.nodes[self.id].value.as_deref()
+    }
+    pub fn parent(&self) -> Option<Section<'d>> {
+        self.document.parent_section(self.id)
+    }
+}
+
+impl<'d> Fieldset<'d> {
+    pub fn name(&self) -> &'d str {
+        self.document.nodes[self.id].name.as_deref().unwrap()
+    }
+    pub fn line(&self) -> usize {
+        self.document.line(self.id)
+    }
+    pub fn touch(&self) {
+        self.document.nodes[self.id].touched.set(true);
+    }
+    pub fn template(&self) -> Option<&'d str> {
+        self.document.nodes[self.id].template.as_deref()
+    }
+    pub fn parent(&self) -> Option<Section<'d>> {
+        self.document.parent_section(self.id)
+    }
+    pub fn entries(&self) -> Vec<FieldsetEntry<'d>> {
+        let document = self.document;
+        document.nodes[self.id]
+            .children
+            .iter()
+            .map(|id| FieldsetEntry { document, id: *id })
+            .collect()
+    }
+}
+
+impl<'d> FieldsetEntry<'d> {
+    pub fn name(&self) -> &'d str {
+        self.document.nodes[self.id].name.as_deref().unwrap()
+    }
+    pub fn line(&self) -> usize {
+        self.document.line(self.id)
+    }
+    pub fn touch(&self) {
+        self.document.nodes[self.id].touched.set(true);
+    }
+    pub fn value(&self) -> Option<&'d str> {
+        self.document.nodes[self.id].value.as_deref()
+    }
+    pub fn parent(&self) -> Fieldset<'d> {
+        Fieldset {
+            document: self.document,
+            id: self.document.nodes[self.id].parent.unwrap(),
+        }
+    }
+}
+
+impl<'d> List<'d> {
+    pub fn name(&self) -> &'d str {
+        self.document.nodes[self.id].name.as_deref().unwrap()
+    }
+    pub fn line(&self) -> usize {
+        self.document.line(self.id)
+    }
+    pub fn touch(&self) {
+        self.document.nodes[self.id].touched.set(true);
+    }
+    pub fn template(&self) -> Option<&'d str> {
+        self.document.nodes[self.id].template.as_deref()
+    }
+    pub fn parent(&self) -> Option<Section<'d>> {
+        self.document.parent_section(self.id)
+    }
+    pub fn items(&self) -> Vec<ListItem<'d>> {
+        let document = self.document;
+        document.nodes[self.id]
+            .children
+            .iter()
+            .map(|id| ListItem { document, id: *id })
+            .collect()
+    }
+}
+
+impl<'d> ListItem<'d> {
+    pub fn line(&self) -> usize {
+        self.document.line(self.id)
+    }
+    pub fn value(&self) -> Option<&'d str> {
+        self.document.nodes[self.id].value.as_deref()
+    }
+    pub fn parent(&self) -> List<'d> {
+        List {
+            document: self.document,
+            id: self.document.nodes[self.id].parent.unwrap(),
+        }
+    }
+}
+
+impl<'d> Block<'d> {
+    pub fn name(&self) -> &'d str {
+        self.document.nodes[self.id].name.as_deref().unwrap()
+    }
+    pub fn line(&self) -> usize {
+        self.document.line(self.id)
+    }
+    pub fn touch(&self) {
+        self.document.nodes[self.id].touched.set(true);
+    }
+    pub fn value(&self) -> Option<&'d str> {
+        self.document.nodes[self.id].value.as_deref()
+    }
+    pub fn parent(&self) -> Option<Section<'d>> {
+        self.document.parent_section(self.id)
+    }
+}
+
+impl<'d> Empty<'d> {
+    pub fn name(&self) -> &'d str {
+        self.document.nodes[self.id].name.as_deref().unwrap()
+    }
+    pub fn line(&self) -> usize {
+        self.document.line(self.id)
+    }
+    pub fn touch(&self) {
+        self.document.nodes[self.id].touched.set(true);
+    }
+    pub fn template(&self) -> Option<&'d str> {
+        self.document.nodes[self.id].template.as_deref()
+    }
+    pub fn parent(&self) -> Option<Section<'d>> {
+        self.document.parent_section(self.id)
+    }
+}
+
+impl<'a> fmt::Debug for Document<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Document")
+            .field("elements", &self.elements())
+            .finish()
+    }
+}
+
+impl<'d> fmt::Debug for Section<'d> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Section")
+            .field("name", &self.name())
+            .field("line", &self.line())
+            .field("elements", &self.elements())
+            .finish()
+    }
+}
+
+impl<'d> fmt::Debug for Field<'d> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Field")
+            .field("name", &self.name())
+            .field("line", &self.line())
+            .field("value", &self.value())
+            .finish()
+    }
+}
+
+impl<'d> fmt::Debug for Fieldset<'d> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Fieldset")
+            .field("name", &self.name())
+            .field("line", &self.line())
+            .field("entries", &self.entries())
+            .finish()
+    }
+}
+
+impl<'d> fmt::Debug for FieldsetEntry<'d> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FieldsetEntry")
+            .field("name", &self.name())
+            .field("line", &self.line())
+            .field("value", &self.value())
+            .finish()
+    }
+}
+
+impl<'d> fmt::Debug for List<'d> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("List")
+            .field("name", &self.name())
+            .field("line", &self.line())
+            .field("items", &self.items())
+            .finish()
+    }
+}
+
+impl<'d> fmt::Debug for ListItem<'d> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ListItem")
+            .field("line", &self.line())
+            .field("value", &self.value())
+            .finish()
+    }
+}
+
+impl<'d> fmt::Debug for Block<'d> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Block")
+            .field("name", &self.name())
+            .field("line", &self.line())
+            .field("value", &self.value())
+            .finish()
+    }
+}
+
+impl<'d> fmt::Debug for Empty<'d> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Empty")
+            .field("name", &self.name())
+            .field("line", &self.line())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse;
+
+    const INPUT: &str = "\
+author: Jane Doe
+empty:
+-- notes
+Some notes
+-- notes
+colors:
+active = #fff
+hover = #b6b6b6
+tags:
+- a
+- b
+# cities
+Wien: 48.2
+## districts
+Leopoldstadt: 2
+# towns
+";
+
+    #[test]
+    fn builds_the_elements_of_each_kind() {
+        let document = parse(INPUT, false).unwrap();
+        let elements = document.elements();
+        let types: Vec<ElementType> = elements.iter().map(Element::element_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                ElementType::Field,
+                ElementType::Empty,
+                ElementType::Block,
+                ElementType::Fieldset,
+                ElementType::List,
+                ElementType::Section,
+                ElementType::Section,
+            ]
+        );
+        match elements[0] {
+            Element::Field(field) => assert_eq!(field.value(), Some("Jane Doe")),
+            _ => unreachable!(),
+        }
+        match elements[2] {
+            Element::Block(block) => assert_eq!(block.value(), Some("Some notes")),
+            _ => unreachable!(),
+        }
+        match elements[3] {
+            Element::Fieldset(fieldset) => {
+                let entries = fieldset.entries();
+                assert_eq!(entries.len(), 2);
+                assert_eq!(entries[1].name(), "hover");
+                assert_eq!(entries[1].value(), Some("#b6b6b6"));
+                assert_eq!(entries[1].parent().name(), "colors");
+            }
+            _ => unreachable!(),
+        }
+        match elements[4] {
+            Element::List(list) => {
+                let items: Vec<_> = list.items().iter().map(|item| item.value()).collect();
+                assert_eq!(items, vec![Some("a"), Some("b")]);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn nests_sections_by_depth() {
+        let document = parse(INPUT, false).unwrap();
+        let elements = document.elements();
+        let cities = match elements[5] {
+            Element::Section(section) => section,
+            _ => unreachable!(),
+        };
+        assert_eq!((cities.name(), cities.depth()), ("cities", 1));
+        let children = cities.elements();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].name(), "Wien");
+        assert_eq!(children[0].parent().unwrap().name(), "cities");
+        match children[1] {
+            Element::Section(districts) => {
+                assert_eq!(districts.depth(), 2);
+                assert_eq!(districts.elements()[0].name(), "Leopoldstadt");
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(elements[6].name(), "towns");
+        assert!(elements[6].parent().is_none());
+    }
+
+    #[test]
+    fn numbers_lines_by_the_indexing() {
+        let document = parse(INPUT, false).unwrap();
+        let lines: Vec<usize> = document.elements().iter().map(Element::line).collect();
+        assert_eq!(lines, vec![1, 2, 3, 6, 9, 12, 16]);
+        let document = parse(INPUT, true).unwrap();
+        assert_eq!(document.elements()[5].line(), 11);
+    }
+}
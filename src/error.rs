@@ -0,0 +1,515 @@
+use document::ElementType;
+use serde::{de, ser};
+use std::error;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use tokenizer::Instruction;
+
+/// The localized message of an error together with where it occurred.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorDetails {
+    pub message: String,
+    /// Line of the error, indexed the same way as the parsed document.
+    pub line: usize,
+    /// Column of the error within its line, indexed like `line`.
+    pub column: usize,
+    /// Byte range of the offending input.
+    pub range: Range<usize>,
+}
+
+/// Raised by `parse` when the input is not a valid eno document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    Tokenization(ErrorDetails),
+    Analysis(ErrorDetails),
+    Resolution(ErrorDetails),
+}
+
+/// Raised by `parse_reader` when the input cannot be read or parsed.
+#[derive(Debug)]
+pub enum ReadError {
+    /// Reading failed, or a line of the input is not valid UTF-8.
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+/// Raised by the tokenizer on a line it cannot read, or on a block that is
+/// never terminated. `parse` reports it as `ParseError::Tokenization`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenizeError(pub ErrorDetails);
+
+/// Raised when a parsed document does not hold what the application asks of it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    Validation(ErrorDetails),
+    Loaders(ErrorDetails),
+}
+
+/// Raised when a custom message catalog cannot be loaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CatalogError {
+    /// The eno catalog is not a valid document.
+    Parse(ParseError),
+    /// The JSON catalog is not valid JSON; carries the message of the parser.
+    Json(String),
+    /// An element of the catalog is not a message inside a category, or a
+    /// message is not text; carries the name of the element.
+    InvalidEntry(String),
+    /// There is no message of this name, given as `category.key`.
+    UnknownKey(String),
+    /// A message has another number of placeholders than the built-in one.
+    PlaceholderMismatch {
+        key: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A message has a named placeholder it has no value for, like `{count}`
+    /// in a message filled with `{name}` and `{line}`.
+    UnknownPlaceholder { key: String, name: String },
+}
+
+/// Raised by `from_str` when the input is not a valid document or does not
+/// have the shape of the type it is read into.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeserializeError {
+    Parse(ParseError),
+    /// An element is missing, has the wrong type or a value its loader
+    /// rejects, reported with the messages `Document` queries use.
+    Validation(ValidationError),
+    /// The errors below are raised by serde and reported as `Validation`
+    /// once located in the document. They only surface from `Deserialize`
+    /// implementations that raise them outside of any element.
+    MissingField(&'static str),
+    UnknownField(String),
+    Custom(String),
+}
+
+/// Raised by `to_string` when a value has no eno representation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SerializeError {
+    /// The value is not a struct or map, which a document needs at its top.
+    TopLevel,
+    /// A map key or field name is not a string, number, boolean or character,
+    /// or spans several lines.
+    Key,
+    /// A sequence mixes values with structs or holds sequences; carries the
+    /// name of the element.
+    Sequence(String),
+    /// A list item has lines with surrounding whitespace, which it would not
+    /// read back with; carries the name of the list.
+    Unrepresentable(String),
+    /// Raised by a `Serialize` implementation.
+    Custom(String),
+}
+
+/// Raised when a schema cannot be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaError {
+    /// The schema is not a valid document.
+    Parse(ParseError),
+    /// A declaration does not follow the schema language.
+    InvalidDeclaration {
+        name: String,
+        line: usize,
+        problem: String,
+    },
+}
+
+/// Raised by `Selector::parse` on a malformed selector; carries the byte
+/// offset of the problem within it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelectorError {
+    /// A segment has no name, as in `a//b` or `# `.
+    EmptyName(usize),
+    /// A name opened with backticks is never closed.
+    UnterminatedEscape(usize),
+    /// A `[` is not followed by a number and `]`.
+    InvalidIndex(usize),
+    /// A segment is followed by something other than `/`.
+    Unexpected(usize),
+}
+
+/// Raised when an edit of a `Cst` cannot be applied. Paths are given with
+/// their names joined by `/`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditError {
+    /// No element has this path.
+    NotFound(String),
+    /// Several elements have this path.
+    Ambiguous(String),
+    /// The element at the path is of a type the edit does not apply to.
+    WrongType(String, ElementType),
+    /// The value would not read back unchanged from the element at the path.
+    Unrepresentable(String),
+    /// The element at the path copies a template, so its value is not its own.
+    Copied(String),
+    /// The edit would leave a document that does not parse, so it was not
+    /// applied.
+    Invalid(String, ParseError),
+}
+
+impl ErrorDetails {
+    /// Locates an error at the given line, which is taken as 0-based and
+    /// shifted by `indexing` like the column.
+    pub(crate) fn new(
+        message: String,
+        line: usize,
+        column: usize,
+        range: Range<usize>,
+        indexing: usize,
+    ) -> ErrorDetails {
+        ErrorDetails {
+            message,
+            line: line + indexing,
+            column: column + indexing,
+            range,
+        }
+    }
+
+    pub(crate) fn from_instruction(
+        message: String,
+        instruction: &Instruction,
+        indexing: usize,
+    ) -> ErrorDetails {
+        let (column, range) = instruction_span(instruction);
+        ErrorDetails::new(message, instruction.line, column, range, indexing)
+    }
+}
+
+/// The 0-based column of the first token of an instruction and the byte
+/// range from there to the end of its line.
+pub(crate) fn instruction_span(instruction: &Instruction) -> (usize, Range<usize>) {
+    let end = instruction.index + instruction.length;
+    let first = instruction
+        .ranges
+        .spans()
+        .into_iter()
+        .map(|span| span.start)
+        .min_by_key(|start| start.byte);
+    match first {
+        Some(start) => (start.column, start.byte..end),
+        None => (0, instruction.index..end),
+    }
+}
+
+impl ParseError {
+    pub fn details(&self) -> &ErrorDetails {
+        match self {
+            ParseError::Tokenization(details)
+            | ParseError::Analysis(details)
+            | ParseError::Resolution(details) => details,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.details().message
+    }
+
+    pub fn line(&self) -> usize {
+        self.details().line
+    }
+
+    pub fn column(&self) -> usize {
+        self.details().column
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.details().range.clone()
+    }
+}
+
+impl TokenizeError {
+    pub fn details(&self) -> &ErrorDetails {
+        &self.0
+    }
+
+    pub fn message(&self) -> &str {
+        &self.0.message
+    }
+
+    pub fn line(&self) -> usize {
+        self.0.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.0.column
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.0.range.clone()
+    }
+}
+
+impl From<TokenizeError> for ParseError {
+    fn from(error: TokenizeError) -> ParseError {
+        ParseError::Tokenization(error.0)
+    }
+}
+
+impl ValidationError {
+    pub fn details(&self) -> &ErrorDetails {
+        match self {
+            ValidationError::Validation(details) | ValidationError::Loaders(details) => details,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.details().message
+    }
+
+    pub fn line(&self) -> usize {
+        self.details().line
+    }
+
+    pub fn column(&self) -> usize {
+        self.details().column
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.details().range.clone()
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeserializeError::Parse(error) => error.fmt(f),
+            DeserializeError::Validation(error) => error.fmt(f),
+            DeserializeError::MissingField(name) => write!(f, "The field '{}' is missing.", name),
+            DeserializeError::UnknownField(name) => write!(f, "The field '{}' is unknown.", name),
+            DeserializeError::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerializeError::TopLevel => {
+                f.write_str("Only a struct or map can be written as a document.")
+            }
+            SerializeError::Key => f.write_str(
+                "Only strings, numbers, booleans and characters without line breaks can be names.",
+            ),
+            SerializeError::Sequence(name) => write!(
+                f,
+                "The sequence '{}' must hold either only values or only structs and maps.",
+                name
+            ),
+            SerializeError::Unrepresentable(name) => write!(
+                f,
+                "The list '{}' has an item with lines with surrounding whitespace, which it cannot hold.",
+                name
+            ),
+            SerializeError::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaError::Parse(error) => error.fmt(f),
+            SchemaError::InvalidDeclaration {
+                name,
+                line,
+                problem,
+            } => write!(
+                f,
+                "The declaration '{}' in line {} {}.",
+                name, line, problem
+            ),
+        }
+    }
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SelectorError::EmptyName(offset) => {
+                write!(f, "The selector lacks a name at offset {}.", offset)
+            }
+            SelectorError::UnterminatedEscape(offset) => write!(
+                f,
+                "The escaped name at offset {} of the selector is not terminated.",
+                offset
+            ),
+            SelectorError::InvalidIndex(offset) => write!(
+                f,
+                "The selector has no valid index like '[2]' at offset {}.",
+                offset
+            ),
+            SelectorError::Unexpected(offset) => write!(
+                f,
+                "The selector continues without a '/' at offset {}.",
+                offset
+            ),
+        }
+    }
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::NotFound(path) => write!(f, "There is no element '{}'.", path),
+            EditError::Ambiguous(path) => write!(f, "There are several elements '{}'.", path),
+            EditError::WrongType(path, element_type) => write!(
+                f,
+                "The element '{}' is a {:?}, which this edit does not apply to.",
+                path, element_type
+            ),
+            EditError::Unrepresentable(path) => write!(
+                f,
+                "The value for '{}' has lines with surrounding whitespace, which it cannot hold.",
+                path
+            ),
+            EditError::Copied(path) => write!(
+                f,
+                "The element '{}' copies another element, which edits do not apply to.",
+                path
+            ),
+            EditError::Invalid(path, error) => write!(
+                f,
+                "The edit of '{}' was not applied, since the document would not be valid: {}",
+                path, error
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(error) => write!(f, "The input could not be read: {}", error),
+            ReadError::Parse(error) => error.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogError::Parse(error) => {
+                write!(f, "The catalog is not a valid document: {}", error)
+            }
+            CatalogError::Json(error) => write!(f, "The catalog is not valid JSON: {}", error),
+            CatalogError::InvalidEntry(name) => write!(
+                f,
+                "'{}' is not a message inside a category of the catalog.",
+                name
+            ),
+            CatalogError::UnknownKey(key) => write!(f, "There is no message '{}'.", key),
+            CatalogError::PlaceholderMismatch {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "The message '{}' has {} placeholders where the built-in one has {}.",
+                key, found, expected
+            ),
+            CatalogError::UnknownPlaceholder { key, name } => write!(
+                f,
+                "The message '{}' has the placeholder '{{{}}}', which it has no value for.",
+                key, name
+            ),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl error::Error for DeserializeError {}
+
+impl de::Error for DeserializeError {
+    fn custom<T: fmt::Display>(message: T) -> DeserializeError {
+        DeserializeError::Custom(message.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> DeserializeError {
+        DeserializeError::MissingField(field)
+    }
+
+    fn unknown_field(field: &str, _expected: &'static [&'static str]) -> DeserializeError {
+        DeserializeError::UnknownField(field.to_string())
+    }
+}
+
+impl From<ParseError> for DeserializeError {
+    fn from(error: ParseError) -> DeserializeError {
+        DeserializeError::Parse(error)
+    }
+}
+
+impl From<ValidationError> for DeserializeError {
+    fn from(error: ValidationError) -> DeserializeError {
+        DeserializeError::Validation(error)
+    }
+}
+
+impl error::Error for SerializeError {}
+
+impl ser::Error for SerializeError {
+    fn custom<T: fmt::Display>(message: T) -> SerializeError {
+        SerializeError::Custom(message.to_string())
+    }
+}
+
+impl error::Error for SchemaError {}
+
+impl error::Error for SelectorError {}
+
+impl error::Error for EditError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            EditError::Invalid(_, error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ReadError::Io(error) => Some(error),
+            ReadError::Parse(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(error: io::Error) -> ReadError {
+        ReadError::Io(error)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(error: ParseError) -> ReadError {
+        ReadError::Parse(error)
+    }
+}
+
+impl error::Error for TokenizeError {}
+
+impl error::Error for ValidationError {}
+
+impl error::Error for CatalogError {}
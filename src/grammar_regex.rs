@@ -1,9 +1,11 @@
+use onig;
 
-pub const OPTIONAL: &'static str = "([^\\n]+?)?";
-pub const REQUIRED: &'static str = "(\\S[^\\n]*?)";
+
+pub const OPTIONAL: &str = "([^\\n]+?)?";
+pub const REQUIRED: &str = "(\\S[^\\n]*?)";
 
 //
-pub const EMPTY: &'static str = "()";
+pub const EMPTY: &str = "()";
 pub const EMPTY_LINE_INDEX: usize = 1;
 
 // | Value
@@ -27,11 +29,11 @@ pub const BLOCK_DASHES_INDEX: usize = 10;
 pub const BLOCK_NAME_INDEX: usize = 11;
 
 // #
-pub const SECTION_HASHES: &'static str = "(#+)(?!#)";
+pub const SECTION_HASHES: &str = "(#+)(?!#)";
 pub const SECTION_HASHES_INDEX: usize = 12;
 
 // # Name
-pub const SECTION_NAME_UNESCAPED: &'static str = "(?!`)([^\\s<][^<\\n]*?)";
+pub const SECTION_NAME_UNESCAPED: &str = "(?!`)([^\\s<][^<\\n]*?)";
 pub const SECTION_NAME_UNESCAPED_INDEX: usize = 13;
 
 // # `Name`
@@ -47,7 +49,7 @@ pub const SECTION_TEMPLATE_INDEX: usize = 18;
 
 
 // Name
-pub const NAME_UNESCAPED: &'static str = "(?![>#\\-`\\\\|])([^\\s:=<][^:=<]*?)";
+pub const NAME_UNESCAPED: &str = "(?![>#\\-`\\\\|])([^\\s:=<][^\\n:=<]*?)";
 pub const NAME_UNESCAPED_INDEX: usize = 19;
 
 //`Name`
@@ -71,6 +73,13 @@ pub const FIELDSET_ENTRY_VALUE_INDEX: usize = 26;
 pub const COPY_OPERATOR_INDEX: usize = 27;
 pub const TEMPLATE_INDEX: usize = 28;
 
+lazy_static! {
+    /// The grammar, compiled once and shared by all tokenizers. The leading
+    /// `\G` anchors every search at the offset it starts from.
+    pub static ref GRAMMAR: onig::Regex =
+        onig::Regex::new(&format!("\\G{}", get_regex())).unwrap();
+}
+
     pub fn get_regex() -> String{
         // | Value
         let new_line_continuation = format!("(\\|)[^\\S\\n]*{}", OPTIONAL);
@@ -83,7 +92,7 @@ pub const TEMPLATE_INDEX: usize = 28;
         // # `Escaped section name` < Template
         let section_template = format!("(?:(<(?!<)|<<)[^\\S\\n]*{})?", REQUIRED);
         let section_name = format!("(?:{}|{})", SECTION_NAME_UNESCAPED, section_name_escaped);
-        let section = format!("{}\\s*{}[^\\S\\n]*{}", SECTION_HASHES, section_name, section_template);
+        let section = format!("{}[^\\S\\n]*{}[^\\S\\n]*{}", SECTION_HASHES, section_name, section_template);
         // > Comment
         let comment = format!("(>)[^\\S\\n]*{}", OPTIONAL);
         // - Value
@@ -93,7 +102,7 @@ pub const TEMPLATE_INDEX: usize = 28;
         let early_determined = format!("{}|{}|{}|{}|{}", continuation, comment, list_item, block, section);
         //`Name`
         let name_escaped = format!("(`+)[^\\S\\n]*(\\S[^\\n]*?)[^\\S\\n]*(\\{})", NAME_ESCAPE_BEGIN_OPERATOR_INDEX);
-        let name = format!("(?:{}|{})", name_escaped, NAME_ESCAPED_INDEX);
+        let name = format!("(?:{}|{})", NAME_UNESCAPED, name_escaped);
         // :
         // : Value
         let field_or_name = format!("(:)[^\\S\\n]*{}", OPTIONAL);
@@ -102,8 +111,8 @@ pub const TEMPLATE_INDEX: usize = 28;
         let field_set_entry  = format!("(=)[^\\S\\n]*{}", OPTIONAL);
         // < Template
         // << Template
-        let copy = format!("(<(?!<)|<<)\\s*{}", REQUIRED);
-        let late_determined = format!("{}\\s*(?:{}|{}|{})", name, field_or_name,field_set_entry,copy);
+        let copy = format!("(<(?!<)|<<)[^\\S\\n]*{}", REQUIRED);
+        let late_determined = format!("{}[^\\S\\n]*(?:{}|{}|{})", name, field_or_name, field_set_entry, copy);
         let not_empty = format!("(?:{}|{})", early_determined, late_determined);
 
         format!("[^\\S\\n]*(?:{}|{})[^\\S\\n]*(?=\\n|$)", EMPTY, not_empty)
@@ -1,10 +1,29 @@
 #[macro_use]
-extern crate log;
-extern crate  env_logger;
+extern crate lazy_static;
 extern crate onig;
 extern crate regex;
+extern crate serde;
+extern crate serde_json;
+extern crate unicode_width;
 
+pub mod analyzer;
+pub mod catalog;
+pub mod cst;
+pub mod de;
+pub mod document;
+pub mod error;
 pub mod grammar_regex;
+pub mod loaders;
 pub mod messages;
 pub mod parser;
+pub mod query;
+pub mod reporter;
+pub mod resolver;
+pub mod schema;
+pub mod selector;
+pub mod ser;
+pub mod serializer;
 pub mod tokenizer;
+
+pub use de::{from_document, from_str};
+pub use ser::to_string;
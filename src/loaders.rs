@@ -0,0 +1,361 @@
+use messages::Loaders;
+use regex::Regex;
+use serde_json;
+
+/// Why a loader rejected a value. Each variant except `Custom` reports the
+/// `messages::Loaders` message of the same name; `Custom` is for loaders
+/// written by the application and carries its own message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoaderError {
+    InvalidBoolean,
+    InvalidColor,
+    InvalidDate,
+    InvalidDatetime,
+    InvalidEmail,
+    InvalidFloat,
+    InvalidInteger,
+    /// Carries the message of the JSON parser.
+    InvalidJson(String),
+    InvalidLatLng,
+    InvalidUrl,
+    Custom(String),
+}
+
+impl LoaderError {
+    /// The message reporting this error for the element named `name`.
+    pub fn message(&self, el: &Loaders, name: &str) -> String {
+        match self {
+            LoaderError::InvalidBoolean => Loaders::msg_with_one_value(el, "invalid_boolean", name),
+            LoaderError::InvalidColor => Loaders::msg_with_one_value(el, "invalid_color", name),
+            LoaderError::InvalidDate => Loaders::msg_with_one_value(el, "invalid_date", name),
+            LoaderError::InvalidDatetime => {
+                Loaders::msg_with_one_value(el, "invalid_datetime", name)
+            }
+            LoaderError::InvalidEmail => Loaders::msg_with_one_value(el, "invalid_email", name),
+            LoaderError::InvalidFloat => Loaders::msg_with_one_value(el, "invalid_float", name),
+            LoaderError::InvalidInteger => Loaders::msg_with_one_value(el, "invalid_integer", name),
+            LoaderError::InvalidJson(error) => {
+                Loaders::msg_with_two_values(el, "invalid_json", name, error)
+            }
+            LoaderError::InvalidLatLng => Loaders::msg_with_one_value(el, "invalid_lat_lng", name),
+            LoaderError::InvalidUrl => Loaders::msg_with_one_value(el, "invalid_url", name),
+            LoaderError::Custom(message) => message.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+/// A point in time as written in the document. Parts left out of the value
+/// default to the start of the enclosing period, and `utc_offset` is given in
+/// minutes east of UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub nanosecond: u32,
+    pub utc_offset: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LatLng {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+lazy_static! {
+    static ref COLOR: Regex = Regex::new(r"^\s*#(?i:[0-9a-f]{3}(?:[0-9a-f]{3})?)\s*$").unwrap();
+    static ref DATE: Regex = Regex::new(r"^\s*([0-9]{4})-([0-9]{2})-([0-9]{2})\s*$").unwrap();
+    // See https://www.w3.org/TR/NOTE-datetime
+    static ref DATETIME: Regex = Regex::new(
+        r"^\s*([0-9]{4})(?:-([0-9]{2})(?:-([0-9]{2})(?:T([0-9]{2}):([0-9]{2})(?::([0-9]{2})(?:\.([0-9]+))?)?(?:(Z)|([+-])([0-9]{2}):([0-9]{2})))?)?)?\s*$"
+    )
+    .unwrap();
+    static ref EMAIL: Regex = Regex::new(r"^\s*[^@\s]+@[^@\s]+\.[^@\s]+\s*$").unwrap();
+    static ref FLOAT: Regex = Regex::new(r"^\s*-?[0-9]+(?:\.[0-9]+)?\s*$").unwrap();
+    static ref INTEGER: Regex = Regex::new(r"^\s*-?[0-9]+\s*$").unwrap();
+    static ref LAT_LNG: Regex =
+        Regex::new(r"^\s*(-?[0-9]{1,3}(?:\.[0-9]+)?)\s*,\s*(-?[0-9]{1,3}(?:\.[0-9]+)?)\s*$").unwrap();
+    static ref URL: Regex = Regex::new(r"^\s*https?://[^\s.]+\.\S+\s*$").unwrap();
+}
+
+/// Accepts `true`, `false`, `yes` and `no`, regardless of case.
+pub fn boolean(value: &str) -> Result<bool, LoaderError> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" => Ok(true),
+        "false" | "no" => Ok(false),
+        _ => Err(LoaderError::InvalidBoolean),
+    }
+}
+
+/// Accepts hex colors like `#B6D918` or `#fff`.
+pub fn color(value: &str) -> Result<String, LoaderError> {
+    if COLOR.is_match(value) {
+        Ok(value.trim().to_string())
+    } else {
+        Err(LoaderError::InvalidColor)
+    }
+}
+
+/// Accepts dates like `1993-11-18`.
+pub fn date(value: &str) -> Result<Date, LoaderError> {
+    let captures = DATE.captures(value).ok_or(LoaderError::InvalidDate)?;
+    let year = captures[1].parse().map_err(|_| LoaderError::InvalidDate)?;
+    let part = |index: usize| {
+        captures[index]
+            .parse()
+            .map_err(|_| LoaderError::InvalidDate)
+    };
+    let (month, day) = (part(2)?, part(3)?);
+    if !valid_date(year, month, day) {
+        return Err(LoaderError::InvalidDate);
+    }
+    Ok(Date { year, month, day })
+}
+
+/// Accepts the formats of https://www.w3.org/TR/NOTE-datetime, from a bare
+/// year like `1961` up to `1989-11-09T19:17:42.5+01:00`.
+pub fn datetime(value: &str) -> Result<DateTime, LoaderError> {
+    let captures = DATETIME
+        .captures(value)
+        .ok_or(LoaderError::InvalidDatetime)?;
+    let part = |index: usize, default: u32| match captures.get(index) {
+        Some(part) => part
+            .as_str()
+            .parse()
+            .map_err(|_| LoaderError::InvalidDatetime),
+        None => Ok(default),
+    };
+    let year = captures[1]
+        .parse()
+        .map_err(|_| LoaderError::InvalidDatetime)?;
+    let (month, day, hour, minute, second) = (
+        part(2, 1)?,
+        part(3, 1)?,
+        part(4, 0)?,
+        part(5, 0)?,
+        part(6, 0)?,
+    );
+    let nanosecond = match captures.get(7) {
+        Some(fraction) => {
+            let digits: String = fraction
+                .as_str()
+                .chars()
+                .chain("000000000".chars())
+                .take(9)
+                .collect();
+            digits.parse().map_err(|_| LoaderError::InvalidDatetime)?
+        }
+        None => 0,
+    };
+    let utc_offset = match captures.get(9) {
+        Some(sign) => {
+            let offset = (part(10, 0)? * 60 + part(11, 0)?) as i32;
+            if sign.as_str() == "-" {
+                -offset
+            } else {
+                offset
+            }
+        }
+        None => 0,
+    };
+    if !valid_date(year, month, day) || hour > 23 || minute > 59 || second > 59 {
+        return Err(LoaderError::InvalidDatetime);
+    }
+    Ok(DateTime {
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        nanosecond,
+        utc_offset,
+    })
+}
+
+/// Accepts addresses like `jane.doe@eno-lang.org`.
+pub fn email(value: &str) -> Result<String, LoaderError> {
+    if EMAIL.is_match(value) {
+        Ok(value.trim().to_string())
+    } else {
+        Err(LoaderError::InvalidEmail)
+    }
+}
+
+/// Accepts decimal numbers like `13.0`, `-9.159` or `42`.
+pub fn float(value: &str) -> Result<f64, LoaderError> {
+    if !FLOAT.is_match(value) {
+        return Err(LoaderError::InvalidFloat);
+    }
+    value.trim().parse().map_err(|_| LoaderError::InvalidFloat)
+}
+
+/// Accepts integers like `42` or `-21`.
+pub fn integer(value: &str) -> Result<i64, LoaderError> {
+    if !INTEGER.is_match(value) {
+        return Err(LoaderError::InvalidInteger);
+    }
+    value
+        .trim()
+        .parse()
+        .map_err(|_| LoaderError::InvalidInteger)
+}
+
+pub fn json(value: &str) -> Result<serde_json::Value, LoaderError> {
+    serde_json::from_str(value).map_err(|error| LoaderError::InvalidJson(error.to_string()))
+}
+
+/// Accepts coordinate pairs like `48.2093723, 16.356099`.
+pub fn lat_lng(value: &str) -> Result<LatLng, LoaderError> {
+    let captures = LAT_LNG.captures(value).ok_or(LoaderError::InvalidLatLng)?;
+    let part = |index: usize| {
+        captures[index]
+            .parse::<f64>()
+            .map_err(|_| LoaderError::InvalidLatLng)
+    };
+    let (lat, lng) = (part(1)?, part(2)?);
+    if lat.abs() > 90.0 || lng.abs() > 180.0 {
+        return Err(LoaderError::InvalidLatLng);
+    }
+    Ok(LatLng { lat, lng })
+}
+
+/// Accepts http and https URLs like `https://eno-lang.org`.
+pub fn url(value: &str) -> Result<String, LoaderError> {
+    if URL.is_match(value) {
+        Ok(value.trim().to_string())
+    } else {
+        Err(LoaderError::InvalidUrl)
+    }
+}
+
+fn valid_date(year: i32, month: u32, day: u32) -> bool {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    day >= 1 && day <= days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use messages::Messages;
+
+    #[test]
+    fn loads_valid_values() {
+        assert_eq!(boolean(" Yes "), Ok(true));
+        assert_eq!(boolean("FALSE"), Ok(false));
+        assert_eq!(color("#B6D918"), Ok("#B6D918".to_string()));
+        assert_eq!(color(" #fff "), Ok("#fff".to_string()));
+        assert_eq!(
+            date("1993-11-18"),
+            Ok(Date {
+                year: 1993,
+                month: 11,
+                day: 18
+            })
+        );
+        assert_eq!(date("2000-02-29").map(|date| date.day), Ok(29));
+        assert_eq!(
+            email("jane.doe@eno-lang.org"),
+            Ok("jane.doe@eno-lang.org".to_string())
+        );
+        assert_eq!(float("-9.159"), Ok(-9.159));
+        assert_eq!(float("42"), Ok(42.0));
+        assert_eq!(integer(" -21 "), Ok(-21));
+        assert_eq!(json("[1, 2]").unwrap(), serde_json::json!([1, 2]));
+        assert_eq!(
+            lat_lng("48.2093723, 16.356099"),
+            Ok(LatLng {
+                lat: 48.2093723,
+                lng: 16.356099
+            })
+        );
+        assert_eq!(
+            url("https://eno-lang.org"),
+            Ok("https://eno-lang.org".to_string())
+        );
+    }
+
+    #[test]
+    fn loads_each_datetime_format() {
+        let year = datetime("1961").unwrap();
+        assert_eq!((year.year, year.month, year.day), (1961, 1, 1));
+        let full = datetime("1989-11-09T19:17:42.5+01:00").unwrap();
+        assert_eq!(
+            full,
+            DateTime {
+                year: 1989,
+                month: 11,
+                day: 9,
+                hour: 19,
+                minute: 17,
+                second: 42,
+                nanosecond: 500_000_000,
+                utc_offset: 60,
+            }
+        );
+        assert_eq!(datetime("2000-01-01T00:00-05:30").unwrap().utc_offset, -330);
+        assert_eq!(datetime("2000-01-01T12:00Z").unwrap().hour, 12);
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert_eq!(boolean("maybe"), Err(LoaderError::InvalidBoolean));
+        assert_eq!(color("#ffff"), Err(LoaderError::InvalidColor));
+        assert_eq!(date("1993-13-01"), Err(LoaderError::InvalidDate));
+        assert_eq!(date("1900-02-29"), Err(LoaderError::InvalidDate));
+        assert_eq!(
+            datetime("1989-11-09T24:00Z"),
+            Err(LoaderError::InvalidDatetime)
+        );
+        assert_eq!(
+            datetime("1989-11-09T19:17"),
+            Err(LoaderError::InvalidDatetime)
+        );
+        assert_eq!(email("jane.doe"), Err(LoaderError::InvalidEmail));
+        assert_eq!(float("1e3"), Err(LoaderError::InvalidFloat));
+        assert_eq!(integer("4.2"), Err(LoaderError::InvalidInteger));
+        assert_eq!(
+            integer("99999999999999999999"),
+            Err(LoaderError::InvalidInteger)
+        );
+        assert!(matches!(json("{"), Err(LoaderError::InvalidJson(_))));
+        assert_eq!(lat_lng("91, 0"), Err(LoaderError::InvalidLatLng));
+        assert_eq!(lat_lng("48.2"), Err(LoaderError::InvalidLatLng));
+        assert_eq!(url("ftp://eno-lang.org"), Err(LoaderError::InvalidUrl));
+    }
+
+    #[test]
+    fn rejects_digits_outside_ascii() {
+        assert_eq!(date("١٩٩٣-١١-١٨"), Err(LoaderError::InvalidDate));
+        assert_eq!(datetime("١٩٦١"), Err(LoaderError::InvalidDatetime));
+        assert_eq!(integer("٤٢"), Err(LoaderError::InvalidInteger));
+        assert_eq!(float("٤٫٢"), Err(LoaderError::InvalidFloat));
+        assert_eq!(lat_lng("٤٨, ١٦"), Err(LoaderError::InvalidLatLng));
+    }
+
+    #[test]
+    fn reports_errors_with_the_name_of_the_element() {
+        let messages = Messages::get_messages("en").unwrap();
+        let message = LoaderError::InvalidInteger.message(&messages.loaders, "count");
+        assert!(message.contains("'count'"), "{}", message);
+        let custom = LoaderError::Custom("Too large.".to_string());
+        assert_eq!(custom.message(&messages.loaders, "count"), "Too large.");
+    }
+}
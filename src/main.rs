@@ -1,27 +1,364 @@
-
 extern crate eno_rust;
+extern crate serde;
+extern crate serde_json;
 
+use eno_rust::cst::Cst;
+use eno_rust::document::{Document, Element};
+use eno_rust::error::ErrorDetails;
 use eno_rust::parser::parse;
+use eno_rust::reporter::{Reporter, TerminalReporter, TextReporter};
+use eno_rust::schema::Schema;
+use eno_rust::selector::{Selected, Selector};
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::process;
+
+const USAGE: &str = "Usage:
+    eno check [--schema <schema>] [<file>...]
+    eno fmt [--check | --write] [<file>...]
+    eno query <selector> [<file>]
+    eno convert --to json [<file>]
+
+Commands:
+    check     Reports the errors of each document, and with --schema the
+              elements that differ from what the schema declares
+    fmt       Writes each document with its lines laid out canonically,
+              keeping comments, copies and block content as they are;
+              --check only lists the documents that are not, --write
+              rewrites them in place
+    query     Prints the values a selector like `section/field`, `*/email`
+              or `# section/list[2]` picks, the items of a list one per
+              line, or the entries of a fieldset
+    convert   Prints the document as JSON
+
+Documents are read from standard input where no file is given. The exit
+status is 1 if a document is invalid, unformatted or has nothing that
+the selector picks, and 2 on bad arguments or unreadable files.";
+
+/// A document and the name it is reported under.
+struct Input {
+    name: String,
+    text: String,
+}
+
+/// A document read for `convert`, with its elements in the order they are
+/// written in, where `serde_json::Value` would sort them by name.
+enum Json {
+    Null,
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+struct JsonVisitor;
 
 fn main() {
-    let input = "author: Jane Doe
-email: jane@eno-lang.org
-
--- my_content
-Multi-line embedded content (e.g. markdown) here ...
--- my_content
-
-states:
-active = #fff
-hover = #b6b6b6
-
-# cities
-Den Haag: 52.069961, 4.302315
-Málaga: 36.721447, -4.421291
-서울특별시: 37.566984, 126.977041
-
-# cities expanded < cities
-Springfield: 38.790312, -77.186418";
-    let zero_indexing = false;
-    println!("{:?}", parse(input,  zero_indexing));
+    let args: Vec<String> = env::args().skip(1).collect();
+    let code = match run(&args) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(message) => {
+            eprintln!("eno: {}", message);
+            2
+        }
+    };
+    process::exit(code);
+}
+
+/// Runs the command in `args`, returning whether it succeeded, or the message
+/// for arguments or files it could not use.
+fn run(args: &[String]) -> Result<bool, String> {
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => return Err(format!("missing command\n\n{}", USAGE)),
+    };
+    match command {
+        "check" => check(args),
+        "fmt" => fmt(args),
+        "query" => query(args),
+        "convert" => convert(args),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(true)
+        }
+        _ => Err(format!("unknown command '{}'\n\n{}", command, USAGE)),
+    }
+}
+
+fn check(args: &[String]) -> Result<bool, String> {
+    let (schema, args) = match args {
+        [flag, schema, args @ ..] if flag == "--schema" => {
+            let input = read_all(vec![schema.clone()])?.remove(0);
+            let schema =
+                Schema::parse(&input.text).map_err(|error| format!("{}: {}", input.name, error))?;
+            (Some(schema), args)
+        }
+        _ => (None, args),
+    };
+    let mut valid = true;
+    for input in read_all(files(args)?)? {
+        let document = match parse(&input.text, false) {
+            Ok(document) => document,
+            Err(error) => {
+                report(&input, error.details());
+                valid = false;
+                continue;
+            }
+        };
+        if let Some(schema) = &schema {
+            if let Err(errors) = schema.validate(&document) {
+                for error in errors {
+                    report(&input, error.details());
+                }
+                valid = false;
+            }
+        }
+    }
+    Ok(valid)
+}
+
+fn fmt(args: &[String]) -> Result<bool, String> {
+    let only_check = args.iter().any(|arg| arg == "--check");
+    let write = args.iter().any(|arg| arg == "--write");
+    let files = files(
+        args.iter()
+            .filter(|arg| *arg != "--check" && *arg != "--write"),
+    )?;
+    if only_check && write {
+        return Err("--check and --write cannot be combined".to_string());
+    }
+    if write && files.is_empty() {
+        return Err("--write needs files to rewrite".to_string());
+    }
+    let mut formatted = true;
+    for input in read_all(files)? {
+        let canonical = match Cst::parse(&input.text, false) {
+            Ok(mut cst) => {
+                cst.format();
+                cst.to_string()
+            }
+            Err(error) => {
+                report(&input, error.details());
+                formatted = false;
+                continue;
+            }
+        };
+        if only_check {
+            if canonical != input.text {
+                eprintln!("{} is not formatted", input.name);
+                formatted = false;
+            }
+        } else if write {
+            if canonical != input.text {
+                fs::write(&input.name, canonical)
+                    .map_err(|error| format!("cannot write {}: {}", input.name, error))?;
+            }
+        } else {
+            print!("{}", canonical);
+        }
+    }
+    Ok(formatted)
+}
+
+fn query(args: &[String]) -> Result<bool, String> {
+    let (selector, files) = match args.split_first() {
+        Some((selector, rest)) if rest.len() <= 1 => (selector, rest),
+        _ => {
+            return Err(format!(
+                "query takes a selector and at most one file\n\n{}",
+                USAGE
+            ))
+        }
+    };
+    let selector = Selector::parse(selector).map_err(|error| error.to_string())?;
+    let input = read_all(files.to_vec())?.remove(0);
+    let document = match parse(&input.text, false) {
+        Ok(document) => document,
+        Err(error) => {
+            report(&input, error.details());
+            return Ok(false);
+        }
+    };
+    match lookup(&document, &selector) {
+        Ok(lines) => {
+            for line in lines {
+                println!("{}", line);
+            }
+            Ok(true)
+        }
+        Err(message) => {
+            eprintln!("{}: {}", input.name, message);
+            Ok(false)
+        }
+    }
+}
+
+fn convert(args: &[String]) -> Result<bool, String> {
+    let files = match args {
+        [flag, format, files @ ..] if flag == "--to" && files.len() <= 1 => {
+            if format != "json" {
+                return Err(format!("cannot convert to '{}', only to json", format));
+            }
+            files
+        }
+        _ => {
+            return Err(format!(
+                "convert takes --to json and at most one file\n\n{}",
+                USAGE
+            ))
+        }
+    };
+    let input = read_all(files.to_vec())?.remove(0);
+    let document = match parse(&input.text, false) {
+        Ok(document) => document,
+        Err(error) => {
+            report(&input, error.details());
+            return Ok(false);
+        }
+    };
+    let value: Json = eno_rust::from_document(&document).map_err(|error| error.to_string())?;
+    let json = serde_json::to_string_pretty(&value).map_err(|error| error.to_string())?;
+    println!("{}", json);
+    Ok(true)
+}
+
+/// The values of what `selector` picks: those of fields, blocks, entries
+/// and items, the items of lists one per line, and the entries of fieldsets.
+fn lookup(document: &Document<'_>, selector: &Selector) -> Result<Vec<String>, String> {
+    let selected = selector.select(document);
+    if selected.is_empty() {
+        return Err(format!("nothing matches '{}'", selector));
+    }
+    let mut lines = Vec::new();
+    for found in selected {
+        match found {
+            Selected::Element(Element::Section(section)) => {
+                return Err(format!(
+                    "'{}' is a section, query an element inside it",
+                    section.name()
+                ));
+            }
+            Selected::Element(Element::List(list)) => {
+                lines.extend(
+                    list.items()
+                        .iter()
+                        .map(|item| item.value().unwrap_or("").to_string()),
+                );
+            }
+            Selected::Element(Element::Fieldset(fieldset)) => {
+                lines.extend(
+                    fieldset
+                        .entries()
+                        .iter()
+                        .map(|entry| format!("{} = {}", entry.name(), entry.value().unwrap_or(""))),
+                );
+            }
+            found => lines.extend(found.value().map(str::to_string)),
+        }
+    }
+    Ok(lines)
+}
+
+/// The file arguments, rejecting anything that looks like an option.
+fn files<'a, I: IntoIterator<Item = &'a String>>(args: I) -> Result<Vec<String>, String> {
+    args.into_iter()
+        .map(|arg| {
+            if arg.starts_with("--") {
+                Err(format!("unknown option '{}'\n\n{}", arg, USAGE))
+            } else {
+                Ok(arg.clone())
+            }
+        })
+        .collect()
+}
+
+/// Reads each of `files`, or standard input if there are none.
+fn read_all(files: Vec<String>) -> Result<Vec<Input>, String> {
+    if files.is_empty() {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|error| format!("cannot read standard input: {}", error))?;
+        return Ok(vec![Input {
+            name: "<stdin>".to_string(),
+            text,
+        }]);
+    }
+    files
+        .into_iter()
+        .map(|name| match fs::read_to_string(&name) {
+            Ok(text) => Ok(Input { name, text }),
+            Err(error) => Err(format!("cannot read {}: {}", name, error)),
+        })
+        .collect()
+}
+
+/// Prints the error to standard error, in color if that is a terminal.
+fn report(input: &Input, details: &ErrorDetails) {
+    let report = if io::stderr().is_terminal() {
+        TerminalReporter::new(&input.text).report(details)
+    } else {
+        TextReporter::new(&input.text).report(details)
+    };
+    eprintln!("{}:{}:{}", input.name, details.line, details.column);
+    eprintln!("{}", report);
+}
+
+impl<'d> Deserialize<'d> for Json {
+    fn deserialize<D: Deserializer<'d>>(deserializer: D) -> Result<Json, D::Error> {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+impl<'d> Visitor<'d> for JsonVisitor {
+    type Value = Json;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an eno document")
+    }
+
+    fn visit_unit<E>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Json, E> {
+        Ok(Json::String(value.to_string()))
+    }
+
+    fn visit_seq<A: SeqAccess<'d>>(self, mut seq: A) -> Result<Json, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Json::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'d>>(self, mut map: A) -> Result<Json, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Json::Object(entries))
+    }
+}
+
+impl Serialize for Json {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Json::Null => serializer.serialize_unit(),
+            Json::String(value) => serializer.serialize_str(value),
+            Json::Array(items) => serializer.collect_seq(items),
+            Json::Object(entries) => {
+                serializer.collect_map(entries.iter().map(|(name, value)| (name, value)))
+            }
+        }
+    }
 }
@@ -1,147 +1,152 @@
 use parser::ContextValues;
 use onig;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use tokenizer::InstructionValues;
+use std::fmt;
+use tokenizer::Instruction;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Messages {
-    elements: Elements,
-    tokenization: Tokenization,
-    analysis: Analysis,
-    resolution: Resolution,
-    validation: Validation,
-    loaders: Loaders,
+    pub elements: Elements,
+    pub tokenization: Tokenization,
+    pub analysis: Analysis,
+    pub resolution: Resolution,
+    pub validation: Validation,
+    pub loaders: Loaders,
 }
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Elements {
-    document: &'static str,
-    empty: &'static str,
-    field: &'static str,
-    fieldset: &'static str,
-    fieldset_entry: &'static str,
-    list: &'static str,
-    list_item: &'static str,
-    section: &'static str,
-    value: &'static str,
+    pub document: Cow<'static, str>,
+    pub empty: Cow<'static, str>,
+    pub field: Cow<'static, str>,
+    pub fieldset: Cow<'static, str>,
+    pub fieldset_entry: Cow<'static, str>,
+    pub list: Cow<'static, str>,
+    pub list_item: Cow<'static, str>,
+    pub section: Cow<'static, str>,
+    pub value: Cow<'static, str>,
 }
 
-pub const UNTERMINATED_ESCAPED_NAME: &'static str = "^\\s*(`+)(?!`)((?:(?!\\1).)+)$";
-#[derive(Clone, Copy, Debug)]
+pub const UNTERMINATED_ESCAPED_NAME: &str = "^\\s*(`+)(?!`)((?:(?!\\1).)+)$";
+#[derive(Clone, Debug)]
 pub struct Tokenization {
-    invalid_line: &'static str,
-    unterminated_block: &'static str,
-    unterminated_escaped_name: &'static str,
+    pub invalid_line: Cow<'static, str>,
+    pub unterminated_block: Cow<'static, str>,
+    pub unterminated_escaped_name: Cow<'static, str>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Analysis {
-    duplicate_fieldset_entry_name: &'static str,
-    fieldset_entry_in_field: &'static str,
-    fieldset_entry_in_list: &'static str,
-    list_item_in_field: &'static str,
-    list_item_in_fieldset: &'static str,
-    missing_element_for_continuation: &'static str,
-    missing_name_for_fieldset_entry: &'static str,
-    missing_name_for_list_item: &'static str,
-    section_hierarchy_layer_skip: &'static str,
+    pub duplicate_fieldset_entry_name: Cow<'static, str>,
+    pub fieldset_entry_in_field: Cow<'static, str>,
+    pub fieldset_entry_in_list: Cow<'static, str>,
+    pub list_item_in_field: Cow<'static, str>,
+    pub list_item_in_fieldset: Cow<'static, str>,
+    pub missing_element_for_continuation: Cow<'static, str>,
+    pub missing_name_for_fieldset_entry: Cow<'static, str>,
+    pub missing_name_for_list_item: Cow<'static, str>,
+    pub section_hierarchy_layer_skip: Cow<'static, str>,
 }
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Resolution {
-    copying_block_into_fieldset: &'static str,
-    copying_block_into_list: &'static str,
-    copying_block_into_section: &'static str,
-    copying_field_into_fieldset: &'static str,
-    copying_field_into_list: &'static str,
-    copying_field_into_section: &'static str,
-    copying_fieldset_into_field: &'static str,
-    copying_fieldset_into_list: &'static str,
-    copying_fieldset_into_section: &'static str,
-    copying_list_into_field: &'static str,
-    copying_list_into_fieldset: &'static str,
-    copying_list_into_section: &'static str,
-    copying_section_into_empty: &'static str,
-    copying_section_into_field: &'static str,
-    copying_section_into_fieldset: &'static str,
-    copying_section_into_list: &'static str,
-    cyclic_dependency: &'static str,
-    multiple_templates_found: &'static str,
-    template_not_found: &'static str,
+    pub copying_block_into_fieldset: Cow<'static, str>,
+    pub copying_block_into_list: Cow<'static, str>,
+    pub copying_block_into_section: Cow<'static, str>,
+    pub copying_field_into_fieldset: Cow<'static, str>,
+    pub copying_field_into_list: Cow<'static, str>,
+    pub copying_field_into_section: Cow<'static, str>,
+    pub copying_fieldset_into_field: Cow<'static, str>,
+    pub copying_fieldset_into_list: Cow<'static, str>,
+    pub copying_fieldset_into_section: Cow<'static, str>,
+    pub copying_list_into_field: Cow<'static, str>,
+    pub copying_list_into_fieldset: Cow<'static, str>,
+    pub copying_list_into_section: Cow<'static, str>,
+    pub copying_section_into_empty: Cow<'static, str>,
+    pub copying_section_into_field: Cow<'static, str>,
+    pub copying_section_into_fieldset: Cow<'static, str>,
+    pub copying_section_into_list: Cow<'static, str>,
+    pub cyclic_dependency: Cow<'static, str>,
+    pub multiple_templates_found: Cow<'static, str>,
+    pub template_not_found: Cow<'static, str>,
 }
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Validation {
-    exact_count_not_met: &'static str,
-    excess_name: &'static str,
-    expected_element_got_elements: &'static str,
-    expected_field_got_fields: &'static str,
-    expected_field_got_fieldset: &'static str,
-    expected_field_got_list: &'static str,
-    expected_field_got_section: &'static str,
-    expected_fields_got_fieldset: &'static str,
-    expected_fields_got_list: &'static str,
-    expected_fields_got_section: &'static str,
-    expected_fieldset_got_field: &'static str,
-    expected_fieldset_got_fieldsets: &'static str,
-    expected_fieldset_got_list: &'static str,
-    expected_fieldset_got_section: &'static str,
-    expected_fieldsets_got_field: &'static str,
-    expected_fieldsets_got_list: &'static str,
-    expected_fieldsets_got_section: &'static str,
-    expected_list_got_field: &'static str,
-    expected_list_got_fieldset: &'static str,
-    expected_list_got_lists: &'static str,
-    expected_list_got_section: &'static str,
-    expected_lists_got_field: &'static str,
-    expected_lists_got_fieldset: &'static str,
-    expected_lists_got_section: &'static str,
-    expected_section_got_empty: &'static str,
-    expected_section_got_field: &'static str,
-    expected_section_got_fieldset: &'static str,
-    expected_section_got_list: &'static str,
-    expected_section_got_sections: &'static str,
-    expected_sections_got_empty: &'static str,
-    expected_sections_got_field: &'static str,
-    expected_sections_got_fieldset: &'static str,
-    expected_sections_got_list: &'static str,
-    generic_error: &'static str,
-    max_count_not_met: &'static str,
-    min_count_not_met: &'static str,
-    missing_element: &'static str,
-    missing_field: &'static str,
-    missing_field_value: &'static str,
-    missing_fieldset: &'static str,
-    missing_fieldset_entry: &'static str,
-    missing_fieldset_entry_value: &'static str,
-    missing_list: &'static str,
-    missing_list_item_value: &'static str,
-    missing_section: &'static str,
+    pub exact_count_not_met: Cow<'static, str>,
+    pub excess_name: Cow<'static, str>,
+    pub expected_element_got_elements: Cow<'static, str>,
+    pub expected_field_got_fields: Cow<'static, str>,
+    pub expected_field_got_fieldset: Cow<'static, str>,
+    pub expected_field_got_list: Cow<'static, str>,
+    pub expected_field_got_section: Cow<'static, str>,
+    pub expected_fields_got_fieldset: Cow<'static, str>,
+    pub expected_fields_got_list: Cow<'static, str>,
+    pub expected_fields_got_section: Cow<'static, str>,
+    pub expected_fieldset_got_field: Cow<'static, str>,
+    pub expected_fieldset_got_fieldsets: Cow<'static, str>,
+    pub expected_fieldset_got_list: Cow<'static, str>,
+    pub expected_fieldset_got_section: Cow<'static, str>,
+    pub expected_fieldsets_got_field: Cow<'static, str>,
+    pub expected_fieldsets_got_list: Cow<'static, str>,
+    pub expected_fieldsets_got_section: Cow<'static, str>,
+    pub expected_list_got_field: Cow<'static, str>,
+    pub expected_list_got_fieldset: Cow<'static, str>,
+    pub expected_list_got_lists: Cow<'static, str>,
+    pub expected_list_got_section: Cow<'static, str>,
+    pub expected_lists_got_field: Cow<'static, str>,
+    pub expected_lists_got_fieldset: Cow<'static, str>,
+    pub expected_lists_got_section: Cow<'static, str>,
+    pub expected_section_got_empty: Cow<'static, str>,
+    pub expected_section_got_field: Cow<'static, str>,
+    pub expected_section_got_fieldset: Cow<'static, str>,
+    pub expected_section_got_list: Cow<'static, str>,
+    pub expected_section_got_sections: Cow<'static, str>,
+    pub expected_sections_got_empty: Cow<'static, str>,
+    pub expected_sections_got_field: Cow<'static, str>,
+    pub expected_sections_got_fieldset: Cow<'static, str>,
+    pub expected_sections_got_list: Cow<'static, str>,
+    pub generic_error: Cow<'static, str>,
+    pub max_count_not_met: Cow<'static, str>,
+    pub min_count_not_met: Cow<'static, str>,
+    pub missing_element: Cow<'static, str>,
+    pub missing_field: Cow<'static, str>,
+    pub missing_field_value: Cow<'static, str>,
+    pub missing_fieldset: Cow<'static, str>,
+    pub missing_fieldset_entry: Cow<'static, str>,
+    pub missing_fieldset_entry_value: Cow<'static, str>,
+    pub missing_list: Cow<'static, str>,
+    pub missing_list_item_value: Cow<'static, str>,
+    pub missing_section: Cow<'static, str>,
 }
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Loaders {
-    invalid_boolean: &'static str,
-    invalid_color: &'static str,
-    invalid_date: &'static str,
-    invalid_datetime: &'static str,
-    invalid_email: &'static str,
-    invalid_float: &'static str,
-    invalid_integer: &'static str,
-    invalid_json: &'static str,
-    invalid_lat_lng: &'static str,
-    invalid_url: &'static str,
+    pub invalid_boolean: Cow<'static, str>,
+    pub invalid_color: Cow<'static, str>,
+    pub invalid_date: Cow<'static, str>,
+    pub invalid_datetime: Cow<'static, str>,
+    pub invalid_email: Cow<'static, str>,
+    pub invalid_float: Cow<'static, str>,
+    pub invalid_integer: Cow<'static, str>,
+    pub invalid_json: Cow<'static, str>,
+    pub invalid_lat_lng: Cow<'static, str>,
+    pub invalid_url: Cow<'static, str>,
 }
 
 impl Elements {
-    pub fn msg(el: Elements, el_name: &'static str) -> &'static str {
+    pub fn msg<'a>(el: &'a Elements, el_name: &str) -> &'a str {
         match el_name {
-            "document" => el.document,
-            "empty" => el.empty,
-            "field" => el.field,
-            "fieldset" => el.fieldset,
-            "fieldset_entry" => el.fieldset_entry,
-            "list" => el.list,
-            "list_item" => el.list_item,
-            "section" => el.section,
-            "value" => el.value,
-            _ => "Error",
+            "document" => &el.document,
+            "empty" => &el.empty,
+            "field" => &el.field,
+            "fieldset" => &el.fieldset,
+            "fieldset_entry" => &el.fieldset_entry,
+            "list" => &el.list,
+            "list_item" => &el.list_item,
+            "section" => &el.section,
+            "value" => &el.value,
+            _ => {
+                debug_assert!(false, "there is no element '{}'", el_name);
+                ""
+            }
         }
     }
 }
@@ -149,413 +154,858 @@ impl Elements {
 impl Tokenization {
     pub fn error_msg(
         el_name: &'static str,
-        context: &HashMap<&'static str, ContextValues>,
-        instruction: &HashMap<&'static str, InstructionValues>,
+        context: &HashMap<&'static str, ContextValues<'_>>,
+        instruction: &Instruction<'_>,
     ) -> String {
+        let el = &context.get("Messages").unwrap().get_messages().unwrap().tokenization;
+        let line_number =
+            instruction.line + context.get("Indexing").unwrap().get_indexing().unwrap();
         match el_name {
             "invalid_line" => {
                 let line = &context.get("Input").unwrap().get_str().unwrap()
-                    [*instruction.get("Index").unwrap().get_numeric().unwrap()
-                        ..(instruction.get("Index").unwrap().get_numeric().unwrap()
-                            + instruction.get("Length").unwrap().get_numeric().unwrap())];
+                    [instruction.index..(instruction.index + instruction.length)];
                 let reg = onig::Regex::new(UNTERMINATED_ESCAPED_NAME).unwrap().find(line);
 
                 if reg.is_some() {
                     return unterminated_escaped_name(context, instruction);
                 }
-                return format!(
-                    "Line {} does not follow any specified pattern.",
-                    (instruction.get("Line").unwrap().get_numeric().unwrap()
-                        + context.get("Indexing").unwrap().get_indexing().unwrap())
-                );
+                rt_format(&el.invalid_line, &[("line", &line_number)])
             }
             "unterminated_escaped_name" => unterminated_escaped_name(context, instruction),
-            "unterminated_block" => format!(
-                "The block '{}' starting in line {} is not terminated until the end of the document.",
-                instruction.get("Name").unwrap().get_str().unwrap(),
-                (instruction.get("Line").unwrap().get_numeric().unwrap()
-                    + context.get("Indexing").unwrap().get_indexing().unwrap())
+            "unterminated_block" => rt_format(
+                &el.unterminated_block,
+                &[("name", &instruction.name.unwrap()), ("line", &line_number)],
             ),
-            _ => "Error".to_string(),
+            _ => unknown_key(el_name),
         }
     }
 }
 
 pub fn unterminated_escaped_name(
-    context: &HashMap<&'static str, ContextValues>,
-    instruction: &HashMap<&'static str, InstructionValues>,
+    context: &HashMap<&'static str, ContextValues<'_>>,
+    instruction: &Instruction<'_>,
 ) -> String {
-    format!(
-        "In line {} the name of an element is escaped, but the escape sequence is not terminated until the end of the line.",
-        (instruction.get("Line").unwrap().get_numeric().unwrap()
-            + context.get("Indexing").unwrap().get_indexing().unwrap())
-    )
+    let el = &context.get("Messages").unwrap().get_messages().unwrap().tokenization;
+    let line = instruction.line + context.get("Indexing").unwrap().get_indexing().unwrap();
+    rt_format(&el.unterminated_escaped_name, &[("line", &line)])
+}
+
+/// Fills the placeholders of a message template with the named values: `{}`
+/// with the value at its position among the placeholders, and named ones like
+/// `{actual}` with the value of that name. Placeholders without a value stay
+/// as written.
+fn rt_format(template: &str, values: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut position = 0;
+    let mut rest = template;
+    while let Some(begin) = rest.find('{') {
+        let end = match rest[begin..].find('}') {
+            Some(end) => begin + end,
+            None => break,
+        };
+        result.push_str(&rest[..begin]);
+        let name = &rest[begin + 1..end];
+        let value = if name.is_empty() {
+            values.get(position)
+        } else {
+            values.iter().find(|value| value.0 == name)
+        };
+        position += 1;
+        match value {
+            Some((_, value)) => result.push_str(&value.to_string()),
+            None => result.push_str(&rest[begin..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    result
 }
 
-/*impl Analysis {
-    pub fn msg_with_one_value(el: Analysis, el_name: &'static str, line: i32) -> String {
+/// The placeholders `rt_format` fills in `template`, by name, with `""` for
+/// those filled in order.
+pub(crate) fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(begin) = rest.find('{') {
+        match rest[begin..].find('}') {
+            Some(end) => {
+                names.push(&rest[begin + 1..begin + end]);
+                rest = &rest[begin + end + 1..];
+            }
+            None => break,
+        }
+    }
+    names
+}
+
+/// The names of the values the message `key` of `category` is filled with,
+/// in the order of the placeholders of the English message.
+pub(crate) fn value_names(category: &str, key: &str) -> &'static [&'static str] {
+    match (category, key) {
+        ("elements", _) => &[],
+        ("tokenization", "unterminated_block") => &["name", "line"],
+        ("analysis", "duplicate_fieldset_entry_name") => &["fieldset", "entry"],
+        ("resolution", "cyclic_dependency")
+        | ("resolution", "multiple_templates_found")
+        | ("resolution", "template_not_found") => &["line", "name"],
+        ("tokenization", _) | ("analysis", _) | ("resolution", _) => &["line"],
+        ("validation", "exact_count_not_met")
+        | ("validation", "max_count_not_met")
+        | ("validation", "min_count_not_met") => &["name", "actual", "expected"],
+        ("loaders", "invalid_json") => &["name", "error"],
+        _ => &["name"],
+    }
+}
+
+/// The message for a key without one, which is a bug in the caller.
+fn unknown_key(el_name: &str) -> String {
+    debug_assert!(false, "there is no message '{}'", el_name);
+    el_name.to_string()
+}
+
+impl Analysis {
+    pub fn msg_with_one_value(el: &Analysis, el_name: &str, line: usize) -> String {
         match el_name {
-            "fieldset_entry_in_field" => rt_format!(el.fieldset_entry_in_field, line).unwrap(),
-            "fieldset_entry_in_list" => rt_format!(el.fieldset_entry_in_list, line).unwrap(),
-            "list_item_in_field" => rt_format!(el.list_item_in_field, line).unwrap(),
-            "list_item_in_fieldset" => rt_format!(el.list_item_in_fieldset, line).unwrap(),
+            "fieldset_entry_in_field" => rt_format(&el.fieldset_entry_in_field, &[("line", &line)]),
+            "fieldset_entry_in_list" => rt_format(&el.fieldset_entry_in_list, &[("line", &line)]),
+            "list_item_in_field" => rt_format(&el.list_item_in_field, &[("line", &line)]),
+            "list_item_in_fieldset" => rt_format(&el.list_item_in_fieldset, &[("line", &line)]),
             "missing_element_for_continuation" => {
-                rt_format!(el.missing_element_for_continuation, line).unwrap()
+                rt_format(&el.missing_element_for_continuation, &[("line", &line)])
             }
             "missing_name_for_fieldset_entry" => {
-                rt_format!(el.missing_name_for_fieldset_entry, line).unwrap()
-            }
-            "missing_name_for_list_item" => {
-                rt_format!(el.missing_name_for_list_item, line).unwrap()
+                rt_format(&el.missing_name_for_fieldset_entry, &[("line", &line)])
             }
+            "missing_name_for_list_item" => rt_format(&el.missing_name_for_list_item, &[("line", &line)]),
             "section_hierarchy_layer_skip" => {
-                rt_format!(el.section_hierarchy_layer_skip, line).unwrap()
+                rt_format(&el.section_hierarchy_layer_skip, &[("line", &line)])
             }
-            _ => "Error".to_string(),
+            _ => unknown_key(el_name),
         }
     }
     pub fn msg_with_two_values(
-        el: Analysis,
-        el_name: &'static str,
-        fieldset_name: &'static str,
-        entry_name: &'static str,
+        el: &Analysis,
+        el_name: &str,
+        fieldset_name: &str,
+        entry_name: &str,
     ) -> String {
         match el_name {
-            "duplicate_fieldset_entry_name" => {
-                rt_format!(el.duplicate_fieldset_entry_name, fieldset_name, entry_name).unwrap()
-            }
-            _ => "Error".to_string(),
+            "duplicate_fieldset_entry_name" => rt_format(
+                &el.duplicate_fieldset_entry_name,
+                &[("fieldset", &fieldset_name), ("entry", &entry_name)],
+            ),
+            _ => unknown_key(el_name),
         }
     }
 }
 
 impl Resolution {
-    pub fn msg_with_one_value(el: Resolution, el_name: &'static str, line: i32) -> String {
+    pub fn msg_with_one_value(el: &Resolution, el_name: &str, line: usize) -> String {
         match el_name {
-            "copying_block_into_fieldset" => {
-                rt_format!(el.copying_block_into_fieldset, line).unwrap()
-            }
-            "copying_block_into_list" => rt_format!(el.copying_block_into_list, line).unwrap(),
-            "copying_block_into_section" => {
-                rt_format!(el.copying_block_into_section, line).unwrap()
-            }
-            "copying_field_into_fieldset" => {
-                rt_format!(el.copying_field_into_fieldset, line).unwrap()
-            }
-            "copying_field_into_list" => rt_format!(el.copying_field_into_list, line).unwrap(),
-            "copying_field_into_section" => {
-                rt_format!(el.copying_field_into_section, line).unwrap()
-            }
-            "copying_fieldset_into_field" => {
-                rt_format!(el.copying_fieldset_into_field, line).unwrap()
-            }
-            "copying_fieldset_into_list" => rt_format!(el.copying_field_into_list, line).unwrap(),
+            "copying_block_into_fieldset" => rt_format(&el.copying_block_into_fieldset, &[("line", &line)]),
+            "copying_block_into_list" => rt_format(&el.copying_block_into_list, &[("line", &line)]),
+            "copying_block_into_section" => rt_format(&el.copying_block_into_section, &[("line", &line)]),
+            "copying_field_into_fieldset" => rt_format(&el.copying_field_into_fieldset, &[("line", &line)]),
+            "copying_field_into_list" => rt_format(&el.copying_field_into_list, &[("line", &line)]),
+            "copying_field_into_section" => rt_format(&el.copying_field_into_section, &[("line", &line)]),
+            "copying_fieldset_into_field" => rt_format(&el.copying_fieldset_into_field, &[("line", &line)]),
+            "copying_fieldset_into_list" => rt_format(&el.copying_fieldset_into_list, &[("line", &line)]),
             "copying_fieldset_into_section" => {
-                rt_format!(el.copying_field_into_section, line).unwrap()
-            }
-            "copying_list_into_field" => rt_format!(el.copying_list_into_field, line).unwrap(),
-            "copying_list_into_fieldset" => {
-                rt_format!(el.copying_list_into_fieldset, line).unwrap()
-            }
-            "copying_list_into_section" => rt_format!(el.copying_list_into_section, line).unwrap(),
-            "copying_section_into_empty" => {
-                rt_format!(el.copying_section_into_empty, line).unwrap()
-            }
-            "copying_section_into_field" => {
-                rt_format!(el.copying_section_into_field, line).unwrap()
+                rt_format(&el.copying_fieldset_into_section, &[("line", &line)])
             }
+            "copying_list_into_field" => rt_format(&el.copying_list_into_field, &[("line", &line)]),
+            "copying_list_into_fieldset" => rt_format(&el.copying_list_into_fieldset, &[("line", &line)]),
+            "copying_list_into_section" => rt_format(&el.copying_list_into_section, &[("line", &line)]),
+            "copying_section_into_empty" => rt_format(&el.copying_section_into_empty, &[("line", &line)]),
+            "copying_section_into_field" => rt_format(&el.copying_section_into_field, &[("line", &line)]),
             "copying_section_into_fieldset" => {
-                rt_format!(el.copying_section_into_fieldset, line).unwrap()
+                rt_format(&el.copying_section_into_fieldset, &[("line", &line)])
             }
-            "copying_section_into_list" => rt_format!(el.copying_section_into_list, line).unwrap(),
-            _ => "Error".to_string(),
+            "copying_section_into_list" => rt_format(&el.copying_section_into_list, &[("line", &line)]),
+            _ => unknown_key(el_name),
         }
     }
-    pub fn msg_with_two_values(
-        el: Resolution,
-        el_name: &'static str,
-        line: i32,
-        name: &'static str,
-    ) -> String {
+    pub fn msg_with_two_values(el: &Resolution, el_name: &str, line: usize, name: &str) -> String {
         match el_name {
-            "cyclic_dependency" => rt_format!(el.cyclic_dependency, line, name).unwrap(),
-            "template_not_found" => rt_format!(el.template_not_found, line, name).unwrap(),
+            "cyclic_dependency" => rt_format(&el.cyclic_dependency, &[("line", &line), ("name", &name)]),
+            "template_not_found" => rt_format(&el.template_not_found, &[("line", &line), ("name", &name)]),
             "multiple_templates_found" => {
-                rt_format!(el.multiple_templates_found, line, name).unwrap()
+                rt_format(&el.multiple_templates_found, &[("line", &line), ("name", &name)])
             }
-            _ => "Error".to_string(),
+            _ => unknown_key(el_name),
         }
     }
 }
+
 impl Validation {
-    pub fn msg_with_one_value(el: Validation, el_name: &'static str, name: &'static str) -> String {
+    pub fn msg_with_one_value(el: &Validation, el_name: &str, name: &str) -> String {
         match el_name {
-            "excess_name" => rt_format!(el.excess_name, name).unwrap(),
+            "excess_name" => rt_format(&el.excess_name, &[("name", &name)]),
             "expected_element_got_elements" => {
-                rt_format!(el.expected_element_got_elements, name).unwrap()
-            }
-            "expected_field_got_fields" => rt_format!(el.expected_field_got_fields, name).unwrap(),
-            "expected_field_got_fieldset" => {
-                rt_format!(el.expected_field_got_fieldset, name).unwrap()
-            }
-            "expected_field_got_list" => rt_format!(el.expected_field_got_list, name).unwrap(),
-            "expected_field_got_section" => {
-                rt_format!(el.expected_field_got_section, name).unwrap()
-            }
-            "expected_fields_got_fieldset" => {
-                rt_format!(el.expected_fields_got_fieldset, name).unwrap()
-            }
-            "expected_fields_got_list" => rt_format!(el.expected_fields_got_list, name).unwrap(),
-            "expected_fields_got_section" => {
-                rt_format!(el.expected_field_got_section, name).unwrap()
-            }
-            "expected_fieldset_got_field" => {
-                rt_format!(el.expected_fieldsets_got_field, name).unwrap()
-            }
+                rt_format(&el.expected_element_got_elements, &[("name", &name)])
+            }
+            "expected_field_got_fields" => rt_format(&el.expected_field_got_fields, &[("name", &name)]),
+            "expected_field_got_fieldset" => rt_format(&el.expected_field_got_fieldset, &[("name", &name)]),
+            "expected_field_got_list" => rt_format(&el.expected_field_got_list, &[("name", &name)]),
+            "expected_field_got_section" => rt_format(&el.expected_field_got_section, &[("name", &name)]),
+            "expected_fields_got_fieldset" => rt_format(&el.expected_fields_got_fieldset, &[("name", &name)]),
+            "expected_fields_got_list" => rt_format(&el.expected_fields_got_list, &[("name", &name)]),
+            "expected_fields_got_section" => rt_format(&el.expected_fields_got_section, &[("name", &name)]),
+            "expected_fieldset_got_field" => rt_format(&el.expected_fieldset_got_field, &[("name", &name)]),
             "expected_fieldset_got_fieldsets" => {
-                rt_format!(el.expected_fieldset_got_fieldsets, name).unwrap()
-            }
-            "expected_fieldset_got_list" => {
-                rt_format!(el.expected_fieldset_got_list, name).unwrap()
+                rt_format(&el.expected_fieldset_got_fieldsets, &[("name", &name)])
             }
+            "expected_fieldset_got_list" => rt_format(&el.expected_fieldset_got_list, &[("name", &name)]),
             "expected_fieldset_got_section" => {
-                rt_format!(el.expected_fieldset_got_section, name).unwrap()
-            }
-            "expected_fieldsets_got_field" => {
-                rt_format!(el.expected_fieldsets_got_field, name).unwrap()
-            }
-            "expected_fieldsets_got_list" => {
-                rt_format!(el.expected_fieldsets_got_list, name).unwrap()
+                rt_format(&el.expected_fieldset_got_section, &[("name", &name)])
             }
+            "expected_fieldsets_got_field" => rt_format(&el.expected_fieldsets_got_field, &[("name", &name)]),
+            "expected_fieldsets_got_list" => rt_format(&el.expected_fieldsets_got_list, &[("name", &name)]),
             "expected_fieldsets_got_section" => {
-                rt_format!(el.expected_fieldsets_got_section, name).unwrap()
-            }
-            "expected_list_got_field" => rt_format!(el.expected_list_got_field, name).unwrap(),
-            "expected_list_got_fieldset" => {
-                rt_format!(el.expected_list_got_fieldset, name).unwrap()
-            }
-            "expected_list_got_lists" => rt_format!(el.expected_list_got_lists, name).unwrap(),
-            "expected_list_got_section" => rt_format!(el.expected_list_got_section, name).unwrap(),
-            "expected_lists_got_field" => rt_format!(el.expected_lists_got_field, name).unwrap(),
-            "expected_lists_got_fieldset" => {
-                rt_format!(el.expected_lists_got_fieldset, name).unwrap()
-            }
-            "expected_lists_got_section" => {
-                rt_format!(el.expected_lists_got_section, name).unwrap()
-            }
-            "expected_section_got_empty" => {
-                rt_format!(el.expected_section_got_empty, name).unwrap()
-            }
-            "expected_section_got_field" => {
-                rt_format!(el.expected_section_got_field, name).unwrap()
-            }
+                rt_format(&el.expected_fieldsets_got_section, &[("name", &name)])
+            }
+            "expected_list_got_field" => rt_format(&el.expected_list_got_field, &[("name", &name)]),
+            "expected_list_got_fieldset" => rt_format(&el.expected_list_got_fieldset, &[("name", &name)]),
+            "expected_list_got_lists" => rt_format(&el.expected_list_got_lists, &[("name", &name)]),
+            "expected_list_got_section" => rt_format(&el.expected_list_got_section, &[("name", &name)]),
+            "expected_lists_got_field" => rt_format(&el.expected_lists_got_field, &[("name", &name)]),
+            "expected_lists_got_fieldset" => rt_format(&el.expected_lists_got_fieldset, &[("name", &name)]),
+            "expected_lists_got_section" => rt_format(&el.expected_lists_got_section, &[("name", &name)]),
+            "expected_section_got_empty" => rt_format(&el.expected_section_got_empty, &[("name", &name)]),
+            "expected_section_got_field" => rt_format(&el.expected_section_got_field, &[("name", &name)]),
             "expected_section_got_fieldset" => {
-                rt_format!(el.expected_section_got_fieldset, name).unwrap()
+                rt_format(&el.expected_section_got_fieldset, &[("name", &name)])
             }
-            "expected_section_got_list" => rt_format!(el.expected_section_got_list, name).unwrap(),
+            "expected_section_got_list" => rt_format(&el.expected_section_got_list, &[("name", &name)]),
             "expected_section_got_sections" => {
-                rt_format!(el.expected_section_got_sections, name).unwrap()
-            }
-            "expected_sections_got_empty" => {
-                rt_format!(el.expected_sections_got_empty, name).unwrap()
-            }
-            "expected_sections_got_field" => {
-                rt_format!(el.expected_sections_got_field, name).unwrap()
+                rt_format(&el.expected_section_got_sections, &[("name", &name)])
             }
+            "expected_sections_got_empty" => rt_format(&el.expected_sections_got_empty, &[("name", &name)]),
+            "expected_sections_got_field" => rt_format(&el.expected_sections_got_field, &[("name", &name)]),
             "expected_sections_got_fieldset" => {
-                rt_format!(el.expected_sections_got_fieldset, name).unwrap()
-            }
-            "expected_sections_got_list" => {
-                rt_format!(el.expected_sections_got_list, name).unwrap()
-            }
-            "generic_error" => rt_format!(el.generic_error, name).unwrap(),
-            "missing_element" => rt_format!(el.missing_element, name).unwrap(),
-            "missing_field" => rt_format!(el.missing_field, name).unwrap(),
-            "missing_field_value" => rt_format!(el.missing_field_value, name).unwrap(),
-            "missing_fieldset" => rt_format!(el.missing_fieldset, name).unwrap(),
-            "missing_fieldset_entry" => rt_format!(el.missing_fieldset_entry, name).unwrap(),
-            "missing_fieldset_entry_value" => {
-                rt_format!(el.missing_fieldset_entry_value, name).unwrap()
-            }
-            "missing_list" => rt_format!(el.missing_list, name).unwrap(),
-            "missing_list_item_value" => rt_format!(el.missing_list_item_value, name).unwrap(),
-            "missing_section" => rt_format!(el.missing_section, name).unwrap(),
-            _ => "Error".to_string(),
+                rt_format(&el.expected_sections_got_fieldset, &[("name", &name)])
+            }
+            "expected_sections_got_list" => rt_format(&el.expected_sections_got_list, &[("name", &name)]),
+            "generic_error" => rt_format(&el.generic_error, &[("name", &name)]),
+            "missing_element" => rt_format(&el.missing_element, &[("name", &name)]),
+            "missing_field" => rt_format(&el.missing_field, &[("name", &name)]),
+            "missing_field_value" => rt_format(&el.missing_field_value, &[("name", &name)]),
+            "missing_fieldset" => rt_format(&el.missing_fieldset, &[("name", &name)]),
+            "missing_fieldset_entry" => rt_format(&el.missing_fieldset_entry, &[("name", &name)]),
+            "missing_fieldset_entry_value" => rt_format(&el.missing_fieldset_entry_value, &[("name", &name)]),
+            "missing_list" => rt_format(&el.missing_list, &[("name", &name)]),
+            "missing_list_item_value" => rt_format(&el.missing_list_item_value, &[("name", &name)]),
+            "missing_section" => rt_format(&el.missing_section, &[("name", &name)]),
+            _ => unknown_key(el_name),
         }
     }
+}
+
+impl Validation {
     pub fn msg_with_three_values(
-        el: Validation,
-        el_name: &'static str,
-        name: &'static str,
-        actual: i32,
-        expected: i32,
+        el: &Validation,
+        el_name: &str,
+        name: &str,
+        actual: usize,
+        expected: usize,
     ) -> String {
         match el_name {
             "exact_count_not_met" => {
-                rt_format!(el.exact_count_not_met, name, actual, expected).unwrap()
-            }
-            "max_count_not_met" => {
-                rt_format!(el.max_count_not_met, name, actual, expected).unwrap()
+                rt_format(&el.exact_count_not_met, &[("name", &name), ("actual", &actual), ("expected", &expected)])
             }
-            "min_count_not_met" => {
-                rt_format!(el.min_count_not_met, name, actual, expected).unwrap()
-            }
-            _ => "Error".to_string(),
+            "max_count_not_met" => rt_format(&el.max_count_not_met, &[("name", &name), ("actual", &actual), ("expected", &expected)]),
+            "min_count_not_met" => rt_format(&el.min_count_not_met, &[("name", &name), ("actual", &actual), ("expected", &expected)]),
+            _ => unknown_key(el_name),
         }
     }
 }
 
 impl Loaders {
-    pub fn msg_with_one_value(el: Loaders, el_name: &'static str, name: &'static str) -> String {
+    pub fn msg_with_one_value(el: &Loaders, el_name: &str, name: &str) -> String {
         match el_name {
-            "invalid_boolean" => rt_format!(el.invalid_boolean, name).unwrap(),
-            "invalid_color" => rt_format!(el.invalid_color, name).unwrap(),
-            "invalid_date" => rt_format!(el.invalid_date, name).unwrap(),
-            "invalid_datetime" => rt_format!(el.invalid_datetime, name).unwrap(),
-            "invalid_email" => rt_format!(el.invalid_email, name).unwrap(),
-            "invalid_float" => rt_format!(el.invalid_float, name).unwrap(),
-            "invalid_integer" => rt_format!(el.invalid_integer, name).unwrap(),
-            "invalid_lat_lng" => rt_format!(el.invalid_lat_lng, name).unwrap(),
-            "invalid_url" => rt_format!(el.invalid_url, name).unwrap(),
-            _ => "Error".to_string(),
+            "invalid_boolean" => rt_format(&el.invalid_boolean, &[("name", &name)]),
+            "invalid_color" => rt_format(&el.invalid_color, &[("name", &name)]),
+            "invalid_date" => rt_format(&el.invalid_date, &[("name", &name)]),
+            "invalid_datetime" => rt_format(&el.invalid_datetime, &[("name", &name)]),
+            "invalid_email" => rt_format(&el.invalid_email, &[("name", &name)]),
+            "invalid_float" => rt_format(&el.invalid_float, &[("name", &name)]),
+            "invalid_integer" => rt_format(&el.invalid_integer, &[("name", &name)]),
+            "invalid_lat_lng" => rt_format(&el.invalid_lat_lng, &[("name", &name)]),
+            "invalid_url" => rt_format(&el.invalid_url, &[("name", &name)]),
+            _ => unknown_key(el_name),
         }
     }
-    pub fn masg_with_two_values(
-        el: Loaders,
-        el_name: &'static str,
-        name: &'static str,
-        error: &'static str,
-    ) -> String {
+    pub fn msg_with_two_values(el: &Loaders, el_name: &str, name: &str, error: &str) -> String {
         match el_name {
-            "invalid_json" => rt_format!(el.invalid_json, name, error).unwrap(),
-            _ => "Error".to_string(),
+            "invalid_json" => rt_format(&el.invalid_json, &[("name", &name), ("error", &error)]),
+            _ => unknown_key(el_name),
+        }
+    }
+}
+
+impl Elements {
+    fn template_mut(&mut self, key: &str) -> Option<&mut Cow<'static, str>> {
+        match key {
+            "document" => Some(&mut self.document),
+            "empty" => Some(&mut self.empty),
+            "field" => Some(&mut self.field),
+            "fieldset" => Some(&mut self.fieldset),
+            "fieldset_entry" => Some(&mut self.fieldset_entry),
+            "list" => Some(&mut self.list),
+            "list_item" => Some(&mut self.list_item),
+            "section" => Some(&mut self.section),
+            "value" => Some(&mut self.value),
+            _ => None,
+        }
+    }
+}
+
+impl Tokenization {
+    fn template_mut(&mut self, key: &str) -> Option<&mut Cow<'static, str>> {
+        match key {
+            "invalid_line" => Some(&mut self.invalid_line),
+            "unterminated_block" => Some(&mut self.unterminated_block),
+            "unterminated_escaped_name" => Some(&mut self.unterminated_escaped_name),
+            _ => None,
+        }
+    }
+}
+
+impl Analysis {
+    fn template_mut(&mut self, key: &str) -> Option<&mut Cow<'static, str>> {
+        match key {
+            "duplicate_fieldset_entry_name" => Some(&mut self.duplicate_fieldset_entry_name),
+            "fieldset_entry_in_field" => Some(&mut self.fieldset_entry_in_field),
+            "fieldset_entry_in_list" => Some(&mut self.fieldset_entry_in_list),
+            "list_item_in_field" => Some(&mut self.list_item_in_field),
+            "list_item_in_fieldset" => Some(&mut self.list_item_in_fieldset),
+            "missing_element_for_continuation" => Some(&mut self.missing_element_for_continuation),
+            "missing_name_for_fieldset_entry" => Some(&mut self.missing_name_for_fieldset_entry),
+            "missing_name_for_list_item" => Some(&mut self.missing_name_for_list_item),
+            "section_hierarchy_layer_skip" => Some(&mut self.section_hierarchy_layer_skip),
+            _ => None,
         }
     }
 }
-*/
+
+impl Resolution {
+    fn template_mut(&mut self, key: &str) -> Option<&mut Cow<'static, str>> {
+        match key {
+            "copying_block_into_fieldset" => Some(&mut self.copying_block_into_fieldset),
+            "copying_block_into_list" => Some(&mut self.copying_block_into_list),
+            "copying_block_into_section" => Some(&mut self.copying_block_into_section),
+            "copying_field_into_fieldset" => Some(&mut self.copying_field_into_fieldset),
+            "copying_field_into_list" => Some(&mut self.copying_field_into_list),
+            "copying_field_into_section" => Some(&mut self.copying_field_into_section),
+            "copying_fieldset_into_field" => Some(&mut self.copying_fieldset_into_field),
+            "copying_fieldset_into_list" => Some(&mut self.copying_fieldset_into_list),
+            "copying_fieldset_into_section" => Some(&mut self.copying_fieldset_into_section),
+            "copying_list_into_field" => Some(&mut self.copying_list_into_field),
+            "copying_list_into_fieldset" => Some(&mut self.copying_list_into_fieldset),
+            "copying_list_into_section" => Some(&mut self.copying_list_into_section),
+            "copying_section_into_empty" => Some(&mut self.copying_section_into_empty),
+            "copying_section_into_field" => Some(&mut self.copying_section_into_field),
+            "copying_section_into_fieldset" => Some(&mut self.copying_section_into_fieldset),
+            "copying_section_into_list" => Some(&mut self.copying_section_into_list),
+            "cyclic_dependency" => Some(&mut self.cyclic_dependency),
+            "multiple_templates_found" => Some(&mut self.multiple_templates_found),
+            "template_not_found" => Some(&mut self.template_not_found),
+            _ => None,
+        }
+    }
+}
+
+impl Validation {
+    fn template_mut(&mut self, key: &str) -> Option<&mut Cow<'static, str>> {
+        match key {
+            "exact_count_not_met" => Some(&mut self.exact_count_not_met),
+            "excess_name" => Some(&mut self.excess_name),
+            "expected_element_got_elements" => Some(&mut self.expected_element_got_elements),
+            "expected_field_got_fields" => Some(&mut self.expected_field_got_fields),
+            "expected_field_got_fieldset" => Some(&mut self.expected_field_got_fieldset),
+            "expected_field_got_list" => Some(&mut self.expected_field_got_list),
+            "expected_field_got_section" => Some(&mut self.expected_field_got_section),
+            "expected_fields_got_fieldset" => Some(&mut self.expected_fields_got_fieldset),
+            "expected_fields_got_list" => Some(&mut self.expected_fields_got_list),
+            "expected_fields_got_section" => Some(&mut self.expected_fields_got_section),
+            "expected_fieldset_got_field" => Some(&mut self.expected_fieldset_got_field),
+            "expected_fieldset_got_fieldsets" => Some(&mut self.expected_fieldset_got_fieldsets),
+            "expected_fieldset_got_list" => Some(&mut self.expected_fieldset_got_list),
+            "expected_fieldset_got_section" => Some(&mut self.expected_fieldset_got_section),
+            "expected_fieldsets_got_field" => Some(&mut self.expected_fieldsets_got_field),
+            "expected_fieldsets_got_list" => Some(&mut self.expected_fieldsets_got_list),
+            "expected_fieldsets_got_section" => Some(&mut self.expected_fieldsets_got_section),
+            "expected_list_got_field" => Some(&mut self.expected_list_got_field),
+            "expected_list_got_fieldset" => Some(&mut self.expected_list_got_fieldset),
+            "expected_list_got_lists" => Some(&mut self.expected_list_got_lists),
+            "expected_list_got_section" => Some(&mut self.expected_list_got_section),
+            "expected_lists_got_field" => Some(&mut self.expected_lists_got_field),
+            "expected_lists_got_fieldset" => Some(&mut self.expected_lists_got_fieldset),
+            "expected_lists_got_section" => Some(&mut self.expected_lists_got_section),
+            "expected_section_got_empty" => Some(&mut self.expected_section_got_empty),
+            "expected_section_got_field" => Some(&mut self.expected_section_got_field),
+            "expected_section_got_fieldset" => Some(&mut self.expected_section_got_fieldset),
+            "expected_section_got_list" => Some(&mut self.expected_section_got_list),
+            "expected_section_got_sections" => Some(&mut self.expected_section_got_sections),
+            "expected_sections_got_empty" => Some(&mut self.expected_sections_got_empty),
+            "expected_sections_got_field" => Some(&mut self.expected_sections_got_field),
+            "expected_sections_got_fieldset" => Some(&mut self.expected_sections_got_fieldset),
+            "expected_sections_got_list" => Some(&mut self.expected_sections_got_list),
+            "generic_error" => Some(&mut self.generic_error),
+            "max_count_not_met" => Some(&mut self.max_count_not_met),
+            "min_count_not_met" => Some(&mut self.min_count_not_met),
+            "missing_element" => Some(&mut self.missing_element),
+            "missing_field" => Some(&mut self.missing_field),
+            "missing_field_value" => Some(&mut self.missing_field_value),
+            "missing_fieldset" => Some(&mut self.missing_fieldset),
+            "missing_fieldset_entry" => Some(&mut self.missing_fieldset_entry),
+            "missing_fieldset_entry_value" => Some(&mut self.missing_fieldset_entry_value),
+            "missing_list" => Some(&mut self.missing_list),
+            "missing_list_item_value" => Some(&mut self.missing_list_item_value),
+            "missing_section" => Some(&mut self.missing_section),
+            _ => None,
+        }
+    }
+}
+
+impl Loaders {
+    fn template_mut(&mut self, key: &str) -> Option<&mut Cow<'static, str>> {
+        match key {
+            "invalid_boolean" => Some(&mut self.invalid_boolean),
+            "invalid_color" => Some(&mut self.invalid_color),
+            "invalid_date" => Some(&mut self.invalid_date),
+            "invalid_datetime" => Some(&mut self.invalid_datetime),
+            "invalid_email" => Some(&mut self.invalid_email),
+            "invalid_float" => Some(&mut self.invalid_float),
+            "invalid_integer" => Some(&mut self.invalid_integer),
+            "invalid_json" => Some(&mut self.invalid_json),
+            "invalid_lat_lng" => Some(&mut self.invalid_lat_lng),
+            "invalid_url" => Some(&mut self.invalid_url),
+            _ => None,
+        }
+    }
+}
+
 impl Messages {
-    pub fn get_messages(locale: &'static str) -> Option<Messages> {
+    /// The catalog for exactly `locale`, like `"de"`, if there is one.
+    pub fn get_messages(locale: &str) -> Option<Messages> {
         match locale {
             "en" => Some(get_en_messages()),
+            "de" => Some(get_de_messages()),
+            "es" => Some(get_es_messages()),
+            _ => None,
+        }
+    }
+
+    /// The catalog for `locale`, dropping subtags from the end until one is
+    /// found, so `de-AT` falls back to `de` and anything unknown to English.
+    /// Both `-` and `_` separate subtags, and case is ignored.
+    pub fn for_locale(locale: &str) -> Messages {
+        let locale = locale.to_lowercase().replace('_', "-");
+        let mut tag = locale.as_str();
+        loop {
+            if let Some(messages) = Messages::get_messages(tag) {
+                return messages;
+            }
+            match tag.rfind('-') {
+                Some(end) => tag = &tag[..end],
+                None => return get_en_messages(),
+            }
+        }
+    }
+
+    /// The template of the message `key` in `category`, like `"validation"`
+    /// and `"missing_field"`, if there is such a message.
+    pub(crate) fn template_mut(
+        &mut self,
+        category: &str,
+        key: &str,
+    ) -> Option<&mut Cow<'static, str>> {
+        match category {
+            "elements" => self.elements.template_mut(key),
+            "tokenization" => self.tokenization.template_mut(key),
+            "analysis" => self.analysis.template_mut(key),
+            "resolution" => self.resolution.template_mut(key),
+            "validation" => self.validation.template_mut(key),
+            "loaders" => self.loaders.template_mut(key),
             _ => None,
         }
     }
 }
 
 fn get_en_messages() -> Messages {
-    let msg = Messages {
+    Messages {
         elements: Elements {
-            document: "Document",
-            empty: "Empty Element",
-            field: "Field",
-            fieldset: "Fieldset",
-            fieldset_entry: "Fieldset Entry",
-            list: "List",
-            list_item: "List Item",
-            section: "Section",
-            value: "Value",
+            document: Cow::Borrowed("Document"),
+            empty: Cow::Borrowed("Empty Element"),
+            field: Cow::Borrowed("Field"),
+            fieldset: Cow::Borrowed("Fieldset"),
+            fieldset_entry: Cow::Borrowed("Fieldset Entry"),
+            list: Cow::Borrowed("List"),
+            list_item: Cow::Borrowed("List Item"),
+            section: Cow::Borrowed("Section"),
+            value: Cow::Borrowed("Value"),
         },
 
         tokenization: Tokenization {
-            invalid_line: "Line {} does not follow any specified pattern.",
-            unterminated_block: "The block '{}' starting in line {} is not terminated until the end of the document.",
-            unterminated_escaped_name: "In line {} the name of an element is escaped, but the escape sequence is not terminated until the end of the line."
+            invalid_line: Cow::Borrowed("Line {} does not follow any specified pattern."),
+            unterminated_block: Cow::Borrowed("The block '{}' starting in line {} is not terminated until the end of the document."),
+            unterminated_escaped_name: Cow::Borrowed("In line {} the name of an element is escaped, but the escape sequence is not terminated until the end of the line.")
         },
 
         analysis: Analysis {
-            duplicate_fieldset_entry_name: "The fieldset '{}' contains two entries named '{}'.",
-            fieldset_entry_in_field: "Line {} contains a fieldset entry inside a field.",
-            fieldset_entry_in_list: "Line {} contains a fieldset entry inside a list.",
-            list_item_in_field: "Line {} contains a list item inside a field.",
-            list_item_in_fieldset: "Line {} contains a list item inside a fieldset.",
-            missing_element_for_continuation: "Line {} contains a continuation without any continuable element being specified before.",
-            missing_name_for_fieldset_entry:  "Line {} contains a fieldset entry without a name for a fieldset being specified before.",
-            missing_name_for_list_item: "Line {} contains a list item without a name for a list being specified before.",
-            section_hierarchy_layer_skip: "Line {} starts a section that is more than one level deeper than the current one."
+            duplicate_fieldset_entry_name: Cow::Borrowed("The fieldset '{}' contains two entries named '{}'."),
+            fieldset_entry_in_field: Cow::Borrowed("Line {} contains a fieldset entry inside a field."),
+            fieldset_entry_in_list: Cow::Borrowed("Line {} contains a fieldset entry inside a list."),
+            list_item_in_field: Cow::Borrowed("Line {} contains a list item inside a field."),
+            list_item_in_fieldset: Cow::Borrowed("Line {} contains a list item inside a fieldset."),
+            missing_element_for_continuation: Cow::Borrowed("Line {} contains a continuation without any continuable element being specified before."),
+            missing_name_for_fieldset_entry:  Cow::Borrowed("Line {} contains a fieldset entry without a name for a fieldset being specified before."),
+            missing_name_for_list_item: Cow::Borrowed("Line {} contains a list item without a name for a list being specified before."),
+            section_hierarchy_layer_skip: Cow::Borrowed("Line {} starts a section that is more than one level deeper than the current one.")
         },
 
         resolution: Resolution {
-            copying_block_into_fieldset: "In line {} a block is copied into a fieldset.",
-            copying_block_into_list: "In line {} a block is copied into a list.",
-            copying_block_into_section: "In line {} a block is copied into a section.",
-            copying_field_into_fieldset: "In line {} a field is copied into a fieldset.",
-            copying_field_into_list: "In line {} a field is copied into a list.",
-            copying_field_into_section: "In line {} a field is copied into a section.",
-            copying_fieldset_into_field: "In line {} a fieldset is copied into a field.",
-            copying_fieldset_into_list: "In line {} a fieldset is copied into a list.",
-            copying_fieldset_into_section: "In line {} a fieldset is copied into a section.",
-            copying_list_into_field: "In line {} a list is copied into a field.",
-            copying_list_into_fieldset: "In line {} a list is copied into a fieldset.",
-            copying_list_into_section: "In line {} a list is copied into a section.",
-            copying_section_into_empty: "In line {} a section is copied into an empty element.",
-            copying_section_into_field: "In line {} a section is copied into a field.",
-            copying_section_into_fieldset: "In line {} a section is copied into a fieldset.",
-            copying_section_into_list: "In line {} a section is copied into a list.",
-            cyclic_dependency: "In line {} '{}' is copied into itself.",
-            multiple_templates_found: "In line {} it is not clear which of the elements named '{}' should be copied.",
-            template_not_found: "In line {} the element '{}' should be copied, but it was not found."
+            copying_block_into_fieldset: Cow::Borrowed("In line {} a block is copied into a fieldset."),
+            copying_block_into_list: Cow::Borrowed("In line {} a block is copied into a list."),
+            copying_block_into_section: Cow::Borrowed("In line {} a block is copied into a section."),
+            copying_field_into_fieldset: Cow::Borrowed("In line {} a field is copied into a fieldset."),
+            copying_field_into_list: Cow::Borrowed("In line {} a field is copied into a list."),
+            copying_field_into_section: Cow::Borrowed("In line {} a field is copied into a section."),
+            copying_fieldset_into_field: Cow::Borrowed("In line {} a fieldset is copied into a field."),
+            copying_fieldset_into_list: Cow::Borrowed("In line {} a fieldset is copied into a list."),
+            copying_fieldset_into_section: Cow::Borrowed("In line {} a fieldset is copied into a section."),
+            copying_list_into_field: Cow::Borrowed("In line {} a list is copied into a field."),
+            copying_list_into_fieldset: Cow::Borrowed("In line {} a list is copied into a fieldset."),
+            copying_list_into_section: Cow::Borrowed("In line {} a list is copied into a section."),
+            copying_section_into_empty: Cow::Borrowed("In line {} a section is copied into an empty element."),
+            copying_section_into_field: Cow::Borrowed("In line {} a section is copied into a field."),
+            copying_section_into_fieldset: Cow::Borrowed("In line {} a section is copied into a fieldset."),
+            copying_section_into_list: Cow::Borrowed("In line {} a section is copied into a list."),
+            cyclic_dependency: Cow::Borrowed("In line {} '{}' is copied into itself."),
+            multiple_templates_found: Cow::Borrowed("In line {} it is not clear which of the elements named '{}' should be copied."),
+            template_not_found: Cow::Borrowed("In line {} the element '{}' should be copied, but it was not found.")
         },
 
         validation: Validation {
-            exact_count_not_met:  "The list '{}' contains {actual} items, but must contain exactly {expected} items.",
-            excess_name: "An excess element named '{}' was found, is it possibly a typo?",
-            expected_element_got_elements: "Instead of the expected single element '{}' several elements with this name were found.",
-            expected_field_got_fields: "Instead of the expected single field '{}' several fields with this name were found.",
-            expected_field_got_fieldset: "Instead of the expected field '{}' a fieldset with this name was found.",
-            expected_field_got_list:  "Instead of the expected field '{}' a list with this name was found.",
-            expected_field_got_section: "Instead of the expected field '{}' a section with this name was found.",
-            expected_fields_got_fieldset: "Only fields with the name '{}' were expected, but a fieldset with this name was found.",
-            expected_fields_got_list: "Only fields with the name '{}' were expected, but a list with this name was found.",
-            expected_fields_got_section: "Only fields with the name '{}' were expected, but a section with this name was found.",
-            expected_fieldset_got_field: "Instead of the expected fieldset '{}' a field with this name was found.",
-            expected_fieldset_got_fieldsets: "Instead of the expected single fieldset '{}' several fieldsets with this name were found.",
-            expected_fieldset_got_list: "Instead of the expected fieldset '{}' a list with this name was found.",
-            expected_fieldset_got_section: "Instead of the expected fieldset '{}' a section with this name was found.",
-            expected_fieldsets_got_field: "Only fieldsets with the name '{}' were expected, but a field with this name was found.",
-            expected_fieldsets_got_list: "Only fieldsets with the name '{}' were expected, but a list with this name was found.",
-            expected_fieldsets_got_section: "Only fieldsets with the name '{}' were expected, but a section with this name was found.",
-            expected_list_got_field: "Instead of the expected list '{}' a field with this name was found.",
-            expected_list_got_fieldset: "Instead of the expected list '{}' a fieldset with this name was found.",
-            expected_list_got_lists: "Instead of the expected single list '{}' several lists with this name were found.",
-            expected_list_got_section: "Instead of the expected list '{}' a section with this name was found.",
-            expected_lists_got_field: "Only lists with the name '{}' were expected, but a field with this name was found.",
-            expected_lists_got_fieldset: "Only lists with the name '{}' were expected, but a fieldset with this name was found.",
-            expected_lists_got_section: "Only lists with the name '{}' were expected, but a section with this name was found.",
-            expected_section_got_empty: "Instead of the expected section '{}' an empty element with this name was found.",
-            expected_section_got_field: "Instead of the expected section '{}' a field with this name was found.",
-            expected_section_got_fieldset: "Instead of the expected section '{}' a fieldset with this name was found.",
-            expected_section_got_list: "Instead of the expected section '{}' a list with this name was found.",
-            expected_section_got_sections: "Instead of the expected single section '{}' several sections with this name were found.",
-            expected_sections_got_empty: "Only sections with the name '{}' were expected, but an empty element with this name was found.",
-            expected_sections_got_field: "Only sections with the name '{}' were expected, but a field with this name was found.",
-            expected_sections_got_fieldset: "Only sections with the name '{}' were expected, but a fieldset with this name was found.",
-            expected_sections_got_list: "Only sections with the name '{}' were expected, but a list with this name was found.",
-            generic_error: "There is a problem with the value of the element '{}'.",
-            max_count_not_met: "The list '{}' contains {actual} items, but may only contain a maximum of {} items.",
-            min_count_not_met: "The list '{}' contains {actual} items, but must contain at least {} items.",
-            missing_element: "The element '{}' is missing - in case it has been specified look for typos and also check for correct capitalization.",
-            missing_field: "The field '{}' is missing - in case it has been specified look for typos and also check for correct capitalization.",
-            missing_field_value: "The field '{}' must contain a value.",
-            missing_fieldset: "The fieldset '{}' is missing - in case it has been specified look for typos and also check for correct capitalization.",
-            missing_fieldset_entry: "The fieldset entry '{}' is missing - in case it has been specified look for typos and also check for correct capitalization.",
-            missing_fieldset_entry_value: "The fieldset entry '{}' must contain a value.",
-            missing_list: "The list '{}' is missing - in case it has been specified look for typos and also check for correct capitalization.",
-            missing_list_item_value: "The list '{}' may not contain empty items.",
-            missing_section: "The section '{}' is missing - in case it has been specified look for typos and also check for correct capitalization."
+            exact_count_not_met:  Cow::Borrowed("The list '{}' contains {actual} items, but must contain exactly {expected} items."),
+            excess_name: Cow::Borrowed("An excess element named '{}' was found, is it possibly a typo?"),
+            expected_element_got_elements: Cow::Borrowed("Instead of the expected single element '{}' several elements with this name were found."),
+            expected_field_got_fields: Cow::Borrowed("Instead of the expected single field '{}' several fields with this name were found."),
+            expected_field_got_fieldset: Cow::Borrowed("Instead of the expected field '{}' a fieldset with this name was found."),
+            expected_field_got_list:  Cow::Borrowed("Instead of the expected field '{}' a list with this name was found."),
+            expected_field_got_section: Cow::Borrowed("Instead of the expected field '{}' a section with this name was found."),
+            expected_fields_got_fieldset: Cow::Borrowed("Only fields with the name '{}' were expected, but a fieldset with this name was found."),
+            expected_fields_got_list: Cow::Borrowed("Only fields with the name '{}' were expected, but a list with this name was found."),
+            expected_fields_got_section: Cow::Borrowed("Only fields with the name '{}' were expected, but a section with this name was found."),
+            expected_fieldset_got_field: Cow::Borrowed("Instead of the expected fieldset '{}' a field with this name was found."),
+            expected_fieldset_got_fieldsets: Cow::Borrowed("Instead of the expected single fieldset '{}' several fieldsets with this name were found."),
+            expected_fieldset_got_list: Cow::Borrowed("Instead of the expected fieldset '{}' a list with this name was found."),
+            expected_fieldset_got_section: Cow::Borrowed("Instead of the expected fieldset '{}' a section with this name was found."),
+            expected_fieldsets_got_field: Cow::Borrowed("Only fieldsets with the name '{}' were expected, but a field with this name was found."),
+            expected_fieldsets_got_list: Cow::Borrowed("Only fieldsets with the name '{}' were expected, but a list with this name was found."),
+            expected_fieldsets_got_section: Cow::Borrowed("Only fieldsets with the name '{}' were expected, but a section with this name was found."),
+            expected_list_got_field: Cow::Borrowed("Instead of the expected list '{}' a field with this name was found."),
+            expected_list_got_fieldset: Cow::Borrowed("Instead of the expected list '{}' a fieldset with this name was found."),
+            expected_list_got_lists: Cow::Borrowed("Instead of the expected single list '{}' several lists with this name were found."),
+            expected_list_got_section: Cow::Borrowed("Instead of the expected list '{}' a section with this name was found."),
+            expected_lists_got_field: Cow::Borrowed("Only lists with the name '{}' were expected, but a field with this name was found."),
+            expected_lists_got_fieldset: Cow::Borrowed("Only lists with the name '{}' were expected, but a fieldset with this name was found."),
+            expected_lists_got_section: Cow::Borrowed("Only lists with the name '{}' were expected, but a section with this name was found."),
+            expected_section_got_empty: Cow::Borrowed("Instead of the expected section '{}' an empty element with this name was found."),
+            expected_section_got_field: Cow::Borrowed("Instead of the expected section '{}' a field with this name was found."),
+            expected_section_got_fieldset: Cow::Borrowed("Instead of the expected section '{}' a fieldset with this name was found."),
+            expected_section_got_list: Cow::Borrowed("Instead of the expected section '{}' a list with this name was found."),
+            expected_section_got_sections: Cow::Borrowed("Instead of the expected single section '{}' several sections with this name were found."),
+            expected_sections_got_empty: Cow::Borrowed("Only sections with the name '{}' were expected, but an empty element with this name was found."),
+            expected_sections_got_field: Cow::Borrowed("Only sections with the name '{}' were expected, but a field with this name was found."),
+            expected_sections_got_fieldset: Cow::Borrowed("Only sections with the name '{}' were expected, but a fieldset with this name was found."),
+            expected_sections_got_list: Cow::Borrowed("Only sections with the name '{}' were expected, but a list with this name was found."),
+            generic_error: Cow::Borrowed("There is a problem with the value of the element '{}'."),
+            max_count_not_met: Cow::Borrowed("The list '{}' contains {actual} items, but may only contain a maximum of {} items."),
+            min_count_not_met: Cow::Borrowed("The list '{}' contains {actual} items, but must contain at least {} items."),
+            missing_element: Cow::Borrowed("The element '{}' is missing - in case it has been specified look for typos and also check for correct capitalization."),
+            missing_field: Cow::Borrowed("The field '{}' is missing - in case it has been specified look for typos and also check for correct capitalization."),
+            missing_field_value: Cow::Borrowed("The field '{}' must contain a value."),
+            missing_fieldset: Cow::Borrowed("The fieldset '{}' is missing - in case it has been specified look for typos and also check for correct capitalization."),
+            missing_fieldset_entry: Cow::Borrowed("The fieldset entry '{}' is missing - in case it has been specified look for typos and also check for correct capitalization."),
+            missing_fieldset_entry_value: Cow::Borrowed("The fieldset entry '{}' must contain a value."),
+            missing_list: Cow::Borrowed("The list '{}' is missing - in case it has been specified look for typos and also check for correct capitalization."),
+            missing_list_item_value: Cow::Borrowed("The list '{}' may not contain empty items."),
+            missing_section: Cow::Borrowed("The section '{}' is missing - in case it has been specified look for typos and also check for correct capitalization.")
         },
 
         loaders: Loaders {
-            invalid_boolean: "'{}' must contain a boolean - allowed values are 'true', 'false', 'yes' and 'no'.",
-            invalid_color: "'{}' must contain a color, for instance '#B6D918', '#fff' or '#01b'.",
-            invalid_date: "'{}' must contain a valid date, for instance '1993-11-18'.",
-            invalid_datetime: "'{}' must contain a valid date or date and time, for instance '1961-01-22' or '1989-11-09T19:17Z' (see https://www.w3.org/TR/NOTE-datetime).",
-            invalid_email: "'{}' must contain a valid email address, for instance 'jane.doe@eno-lang.org'.",
-            invalid_float: "'{}' must contain a decimal number, for instance '13.0', '-9.159' or '42'.",
-            invalid_integer: "'{}' must contain an integer, for instance '42' or '-21'.",
-            invalid_json: "'{}' must contain valid JSON - the parser returned: '{}'.",
-            invalid_lat_lng: "'{}' must contain a valid latitude/longitude coordinate pair, for instance '48.2093723, 16.356099'.",
-            invalid_url: "'{}' must contain a valid URL, for instance 'https://eno-lang.org'."
+            invalid_boolean: Cow::Borrowed("'{}' must contain a boolean - allowed values are 'true', 'false', 'yes' and 'no'."),
+            invalid_color: Cow::Borrowed("'{}' must contain a color, for instance '#B6D918', '#fff' or '#01b'."),
+            invalid_date: Cow::Borrowed("'{}' must contain a valid date, for instance '1993-11-18'."),
+            invalid_datetime: Cow::Borrowed("'{}' must contain a valid date or date and time, for instance '1961-01-22' or '1989-11-09T19:17Z' (see https://www.w3.org/TR/NOTE-datetime)."),
+            invalid_email: Cow::Borrowed("'{}' must contain a valid email address, for instance 'jane.doe@eno-lang.org'."),
+            invalid_float: Cow::Borrowed("'{}' must contain a decimal number, for instance '13.0', '-9.159' or '42'."),
+            invalid_integer: Cow::Borrowed("'{}' must contain an integer, for instance '42' or '-21'."),
+            invalid_json: Cow::Borrowed("'{}' must contain valid JSON - the parser returned: '{}'."),
+            invalid_lat_lng: Cow::Borrowed("'{}' must contain a valid latitude/longitude coordinate pair, for instance '48.2093723, 16.356099'."),
+            invalid_url: Cow::Borrowed("'{}' must contain a valid URL, for instance 'https://eno-lang.org'.")
         }
-    };
-    msg
+    }
+}
+
+fn get_de_messages() -> Messages {
+    Messages {
+        elements: Elements {
+            document: Cow::Borrowed("Dokument"),
+            empty: Cow::Borrowed("Leeres Element"),
+            field: Cow::Borrowed("Feld"),
+            fieldset: Cow::Borrowed("Feldset"),
+            fieldset_entry: Cow::Borrowed("Feldset Eintrag"),
+            list: Cow::Borrowed("Liste"),
+            list_item: Cow::Borrowed("Listen Eintrag"),
+            section: Cow::Borrowed("Sektion"),
+            value: Cow::Borrowed("Wert"),
+        },
+
+        tokenization: Tokenization {
+            invalid_line: Cow::Borrowed("Zeile {} folgt keinem der vorgesehenen Muster."),
+            unterminated_block: Cow::Borrowed("Der Block '{}', der in Zeile {} beginnt, wird bis zum Ende des Dokuments nicht abgeschlossen."),
+            unterminated_escaped_name: Cow::Borrowed("In Zeile {} wird der Name eines Elements escaped, die Escape-Sequenz wird jedoch bis zum Ende der Zeile nicht beendet.")
+        },
+
+        analysis: Analysis {
+            duplicate_fieldset_entry_name: Cow::Borrowed("Das Feldset '{}' enthält zwei Einträge namens '{}'."),
+            fieldset_entry_in_field: Cow::Borrowed("Zeile {} enthält einen Feldset Eintrag innerhalb eines Felds."),
+            fieldset_entry_in_list: Cow::Borrowed("Zeile {} enthält einen Feldset Eintrag innerhalb einer Liste."),
+            list_item_in_field: Cow::Borrowed("Zeile {} enthält einen Listen Eintrag innerhalb eines Felds."),
+            list_item_in_fieldset: Cow::Borrowed("Zeile {} enthält einen Listen Eintrag innerhalb eines Feldsets."),
+            missing_element_for_continuation: Cow::Borrowed("Zeile {} enthält eine Fortsetzung, ohne dass davor ein fortsetzbares Element angegeben wurde."),
+            missing_name_for_fieldset_entry: Cow::Borrowed("Zeile {} enthält einen Feldset Eintrag, ohne dass davor der Name eines Feldsets angegeben wurde."),
+            missing_name_for_list_item: Cow::Borrowed("Zeile {} enthält einen Listen Eintrag, ohne dass davor der Name einer Liste angegeben wurde."),
+            section_hierarchy_layer_skip: Cow::Borrowed("Zeile {} beginnt eine Sektion, die mehr als eine Ebene tiefer liegt als die aktuelle.")
+        },
+
+        resolution: Resolution {
+            copying_block_into_fieldset: Cow::Borrowed("In Zeile {} wird ein Block in ein Feldset kopiert."),
+            copying_block_into_list: Cow::Borrowed("In Zeile {} wird ein Block in eine Liste kopiert."),
+            copying_block_into_section: Cow::Borrowed("In Zeile {} wird ein Block in eine Sektion kopiert."),
+            copying_field_into_fieldset: Cow::Borrowed("In Zeile {} wird ein Feld in ein Feldset kopiert."),
+            copying_field_into_list: Cow::Borrowed("In Zeile {} wird ein Feld in eine Liste kopiert."),
+            copying_field_into_section: Cow::Borrowed("In Zeile {} wird ein Feld in eine Sektion kopiert."),
+            copying_fieldset_into_field: Cow::Borrowed("In Zeile {} wird ein Feldset in ein Feld kopiert."),
+            copying_fieldset_into_list: Cow::Borrowed("In Zeile {} wird ein Feldset in eine Liste kopiert."),
+            copying_fieldset_into_section: Cow::Borrowed("In Zeile {} wird ein Feldset in eine Sektion kopiert."),
+            copying_list_into_field: Cow::Borrowed("In Zeile {} wird eine Liste in ein Feld kopiert."),
+            copying_list_into_fieldset: Cow::Borrowed("In Zeile {} wird eine Liste in ein Feldset kopiert."),
+            copying_list_into_section: Cow::Borrowed("In Zeile {} wird eine Liste in eine Sektion kopiert."),
+            copying_section_into_empty: Cow::Borrowed("In Zeile {} wird eine Sektion in ein leeres Element kopiert."),
+            copying_section_into_field: Cow::Borrowed("In Zeile {} wird eine Sektion in ein Feld kopiert."),
+            copying_section_into_fieldset: Cow::Borrowed("In Zeile {} wird eine Sektion in ein Feldset kopiert."),
+            copying_section_into_list: Cow::Borrowed("In Zeile {} wird eine Sektion in eine Liste kopiert."),
+            cyclic_dependency: Cow::Borrowed("In Zeile {} wird '{}' in sich selbst kopiert."),
+            multiple_templates_found: Cow::Borrowed("In Zeile {} ist unklar, welches der Elemente namens '{}' kopiert werden soll."),
+            template_not_found: Cow::Borrowed("In Zeile {} soll das Element '{}' kopiert werden, es wurde jedoch nicht gefunden.")
+        },
+
+        validation: Validation {
+            exact_count_not_met: Cow::Borrowed("Die Liste '{}' enthält {actual} Einträge, muss jedoch genau {expected} Einträge enthalten."),
+            excess_name: Cow::Borrowed("Ein überschüssiges Element namens '{}' wurde gefunden, handelt es sich möglicherweise um einen Tippfehler?"),
+            expected_element_got_elements: Cow::Borrowed("Anstatt dem erwarteten einzelnen Element '{}' wurden mehrere Elemente mit diesem Namen gefunden."),
+            expected_field_got_fields: Cow::Borrowed("Anstatt dem erwarteten einzelnen Feld '{}' wurden mehrere Felder mit diesem Namen gefunden."),
+            expected_field_got_fieldset: Cow::Borrowed("Anstatt dem erwarteten Feld '{}' wurde ein Feldset mit diesem Namen gefunden."),
+            expected_field_got_list: Cow::Borrowed("Anstatt dem erwarteten Feld '{}' wurde eine Liste mit diesem Namen gefunden."),
+            expected_field_got_section: Cow::Borrowed("Anstatt dem erwarteten Feld '{}' wurde eine Sektion mit diesem Namen gefunden."),
+            expected_fields_got_fieldset: Cow::Borrowed("Nur Felder mit dem Namen '{}' wurden erwartet, jedoch wurde ein Feldset mit diesem Namen gefunden."),
+            expected_fields_got_list: Cow::Borrowed("Nur Felder mit dem Namen '{}' wurden erwartet, jedoch wurde eine Liste mit diesem Namen gefunden."),
+            expected_fields_got_section: Cow::Borrowed("Nur Felder mit dem Namen '{}' wurden erwartet, jedoch wurde eine Sektion mit diesem Namen gefunden."),
+            expected_fieldset_got_field: Cow::Borrowed("Anstatt dem erwarteten Feldset '{}' wurde ein Feld mit diesem Namen gefunden."),
+            expected_fieldset_got_fieldsets: Cow::Borrowed("Anstatt dem erwarteten einzelnen Feldset '{}' wurden mehrere Feldsets mit diesem Namen gefunden."),
+            expected_fieldset_got_list: Cow::Borrowed("Anstatt dem erwarteten Feldset '{}' wurde eine Liste mit diesem Namen gefunden."),
+            expected_fieldset_got_section: Cow::Borrowed("Anstatt dem erwarteten Feldset '{}' wurde eine Sektion mit diesem Namen gefunden."),
+            expected_fieldsets_got_field: Cow::Borrowed("Nur Feldsets mit dem Namen '{}' wurden erwartet, jedoch wurde ein Feld mit diesem Namen gefunden."),
+            expected_fieldsets_got_list: Cow::Borrowed("Nur Feldsets mit dem Namen '{}' wurden erwartet, jedoch wurde eine Liste mit diesem Namen gefunden."),
+            expected_fieldsets_got_section: Cow::Borrowed("Nur Feldsets mit dem Namen '{}' wurden erwartet, jedoch wurde eine Sektion mit diesem Namen gefunden."),
+            expected_list_got_field: Cow::Borrowed("Anstatt der erwarteten Liste '{}' wurde ein Feld mit diesem Namen gefunden."),
+            expected_list_got_fieldset: Cow::Borrowed("Anstatt der erwarteten Liste '{}' wurde ein Feldset mit diesem Namen gefunden."),
+            expected_list_got_lists: Cow::Borrowed("Anstatt der erwarteten einzelnen Liste '{}' wurden mehrere Listen mit diesem Namen gefunden."),
+            expected_list_got_section: Cow::Borrowed("Anstatt der erwarteten Liste '{}' wurde eine Sektion mit diesem Namen gefunden."),
+            expected_lists_got_field: Cow::Borrowed("Nur Listen mit dem Namen '{}' wurden erwartet, jedoch wurde ein Feld mit diesem Namen gefunden."),
+            expected_lists_got_fieldset: Cow::Borrowed("Nur Listen mit dem Namen '{}' wurden erwartet, jedoch wurde ein Feldset mit diesem Namen gefunden."),
+            expected_lists_got_section: Cow::Borrowed("Nur Listen mit dem Namen '{}' wurden erwartet, jedoch wurde eine Sektion mit diesem Namen gefunden."),
+            expected_section_got_empty: Cow::Borrowed("Anstatt der erwarteten Sektion '{}' wurde ein leeres Element mit diesem Namen gefunden."),
+            expected_section_got_field: Cow::Borrowed("Anstatt der erwarteten Sektion '{}' wurde ein Feld mit diesem Namen gefunden."),
+            expected_section_got_fieldset: Cow::Borrowed("Anstatt der erwarteten Sektion '{}' wurde ein Feldset mit diesem Namen gefunden."),
+            expected_section_got_list: Cow::Borrowed("Anstatt der erwarteten Sektion '{}' wurde eine Liste mit diesem Namen gefunden."),
+            expected_section_got_sections: Cow::Borrowed("Anstatt der erwarteten einzelnen Sektion '{}' wurden mehrere Sektionen mit diesem Namen gefunden."),
+            expected_sections_got_empty: Cow::Borrowed("Nur Sektionen mit dem Namen '{}' wurden erwartet, jedoch wurde ein leeres Element mit diesem Namen gefunden."),
+            expected_sections_got_field: Cow::Borrowed("Nur Sektionen mit dem Namen '{}' wurden erwartet, jedoch wurde ein Feld mit diesem Namen gefunden."),
+            expected_sections_got_fieldset: Cow::Borrowed("Nur Sektionen mit dem Namen '{}' wurden erwartet, jedoch wurde ein Feldset mit diesem Namen gefunden."),
+            expected_sections_got_list: Cow::Borrowed("Nur Sektionen mit dem Namen '{}' wurden erwartet, jedoch wurde eine Liste mit diesem Namen gefunden."),
+            generic_error: Cow::Borrowed("Mit dem Wert des Elements '{}' gibt es ein Problem."),
+            max_count_not_met: Cow::Borrowed("Die Liste '{}' enthält {actual} Einträge, darf jedoch höchstens {} Einträge enthalten."),
+            min_count_not_met: Cow::Borrowed("Die Liste '{}' enthält {actual} Einträge, muss jedoch mindestens {} Einträge enthalten."),
+            missing_element: Cow::Borrowed("Das Element '{}' fehlt - falls angegeben, eventuell nach Tippfehlern Ausschau halten und auch die Groß- und Kleinschreibung beachten."),
+            missing_field: Cow::Borrowed("Das Feld '{}' fehlt - falls angegeben, eventuell nach Tippfehlern Ausschau halten und auch die Groß- und Kleinschreibung beachten."),
+            missing_field_value: Cow::Borrowed("Das Feld '{}' muss einen Wert enthalten."),
+            missing_fieldset: Cow::Borrowed("Das Feldset '{}' fehlt - falls angegeben, eventuell nach Tippfehlern Ausschau halten und auch die Groß- und Kleinschreibung beachten."),
+            missing_fieldset_entry: Cow::Borrowed("Der Feldset Eintrag '{}' fehlt - falls angegeben, eventuell nach Tippfehlern Ausschau halten und auch die Groß- und Kleinschreibung beachten."),
+            missing_fieldset_entry_value: Cow::Borrowed("Der Feldset Eintrag '{}' muss einen Wert enthalten."),
+            missing_list: Cow::Borrowed("Die Liste '{}' fehlt - falls angegeben, eventuell nach Tippfehlern Ausschau halten und auch die Groß- und Kleinschreibung beachten."),
+            missing_list_item_value: Cow::Borrowed("Die Liste '{}' darf keine leeren Einträge enthalten."),
+            missing_section: Cow::Borrowed("Die Sektion '{}' fehlt - falls angegeben, eventuell nach Tippfehlern Ausschau halten und auch die Groß- und Kleinschreibung beachten.")
+        },
+
+        loaders: Loaders {
+            invalid_boolean: Cow::Borrowed("'{}' muss einen Wahrheitswert enthalten - erlaubte Werte sind 'true', 'false', 'yes' und 'no'."),
+            invalid_color: Cow::Borrowed("'{}' muss eine Farbe enthalten, zum Beispiel '#B6D918', '#fff' oder '#01b'."),
+            invalid_date: Cow::Borrowed("'{}' muss ein gültiges Datum enthalten, zum Beispiel '1993-11-18'."),
+            invalid_datetime: Cow::Borrowed("'{}' muss ein gültiges Datum oder Datum und Uhrzeit enthalten, zum Beispiel '1961-01-22' oder '1989-11-09T19:17Z' (siehe https://www.w3.org/TR/NOTE-datetime)."),
+            invalid_email: Cow::Borrowed("'{}' muss eine gültige E-Mail-Adresse enthalten, zum Beispiel 'jane.doe@eno-lang.org'."),
+            invalid_float: Cow::Borrowed("'{}' muss eine Dezimalzahl enthalten, zum Beispiel '13.0', '-9.159' oder '42'."),
+            invalid_integer: Cow::Borrowed("'{}' muss eine Ganzzahl enthalten, zum Beispiel '42' oder '-21'."),
+            invalid_json: Cow::Borrowed("'{}' muss gültiges JSON enthalten - der Parser meldete: '{}'."),
+            invalid_lat_lng: Cow::Borrowed("'{}' muss ein gültiges Koordinatenpaar aus Breiten- und Längengrad enthalten, zum Beispiel '48.2093723, 16.356099'."),
+            invalid_url: Cow::Borrowed("'{}' muss eine gültige URL enthalten, zum Beispiel 'https://eno-lang.org'.")
+        },
+    }
+}
+
+fn get_es_messages() -> Messages {
+    Messages {
+        elements: Elements {
+            document: Cow::Borrowed("Documento"),
+            empty: Cow::Borrowed("Elemento vacío"),
+            field: Cow::Borrowed("Campo"),
+            fieldset: Cow::Borrowed("Conjunto de campos"),
+            fieldset_entry: Cow::Borrowed("Entrada de conjunto de campos"),
+            list: Cow::Borrowed("Lista"),
+            list_item: Cow::Borrowed("Elemento de lista"),
+            section: Cow::Borrowed("Sección"),
+            value: Cow::Borrowed("Valor"),
+        },
+
+        tokenization: Tokenization {
+            invalid_line: Cow::Borrowed("La línea {} no sigue ninguno de los patrones especificados."),
+            unterminated_block: Cow::Borrowed("El bloque '{}' que comienza en la línea {} no se cierra hasta el final del documento."),
+            unterminated_escaped_name: Cow::Borrowed("En la línea {} el nombre de un elemento está escapado, pero la secuencia de escape no se cierra hasta el final de la línea.")
+        },
+
+        analysis: Analysis {
+            duplicate_fieldset_entry_name: Cow::Borrowed("El conjunto de campos '{}' contiene dos entradas llamadas '{}'."),
+            fieldset_entry_in_field: Cow::Borrowed("La línea {} contiene una entrada de conjunto de campos dentro de un campo."),
+            fieldset_entry_in_list: Cow::Borrowed("La línea {} contiene una entrada de conjunto de campos dentro de una lista."),
+            list_item_in_field: Cow::Borrowed("La línea {} contiene un elemento de lista dentro de un campo."),
+            list_item_in_fieldset: Cow::Borrowed("La línea {} contiene un elemento de lista dentro de un conjunto de campos."),
+            missing_element_for_continuation: Cow::Borrowed("La línea {} contiene una continuación sin que antes se haya especificado un elemento que se pueda continuar."),
+            missing_name_for_fieldset_entry: Cow::Borrowed("La línea {} contiene una entrada de conjunto de campos sin que antes se haya especificado el nombre de un conjunto de campos."),
+            missing_name_for_list_item: Cow::Borrowed("La línea {} contiene un elemento de lista sin que antes se haya especificado el nombre de una lista."),
+            section_hierarchy_layer_skip: Cow::Borrowed("La línea {} comienza una sección que está más de un nivel por debajo de la actual.")
+        },
+
+        resolution: Resolution {
+            copying_block_into_fieldset: Cow::Borrowed("En la línea {} se copia un bloque en un conjunto de campos."),
+            copying_block_into_list: Cow::Borrowed("En la línea {} se copia un bloque en una lista."),
+            copying_block_into_section: Cow::Borrowed("En la línea {} se copia un bloque en una sección."),
+            copying_field_into_fieldset: Cow::Borrowed("En la línea {} se copia un campo en un conjunto de campos."),
+            copying_field_into_list: Cow::Borrowed("En la línea {} se copia un campo en una lista."),
+            copying_field_into_section: Cow::Borrowed("En la línea {} se copia un campo en una sección."),
+            copying_fieldset_into_field: Cow::Borrowed("En la línea {} se copia un conjunto de campos en un campo."),
+            copying_fieldset_into_list: Cow::Borrowed("En la línea {} se copia un conjunto de campos en una lista."),
+            copying_fieldset_into_section: Cow::Borrowed("En la línea {} se copia un conjunto de campos en una sección."),
+            copying_list_into_field: Cow::Borrowed("En la línea {} se copia una lista en un campo."),
+            copying_list_into_fieldset: Cow::Borrowed("En la línea {} se copia una lista en un conjunto de campos."),
+            copying_list_into_section: Cow::Borrowed("En la línea {} se copia una lista en una sección."),
+            copying_section_into_empty: Cow::Borrowed("En la línea {} se copia una sección en un elemento vacío."),
+            copying_section_into_field: Cow::Borrowed("En la línea {} se copia una sección en un campo."),
+            copying_section_into_fieldset: Cow::Borrowed("En la línea {} se copia una sección en un conjunto de campos."),
+            copying_section_into_list: Cow::Borrowed("En la línea {} se copia una sección en una lista."),
+            cyclic_dependency: Cow::Borrowed("En la línea {} '{}' se copia en sí mismo."),
+            multiple_templates_found: Cow::Borrowed("En la línea {} no está claro cuál de los elementos llamados '{}' se debe copiar."),
+            template_not_found: Cow::Borrowed("En la línea {} se debe copiar el elemento '{}', pero no se encontró.")
+        },
+
+        validation: Validation {
+            exact_count_not_met: Cow::Borrowed("La lista '{}' contiene {actual} elementos, pero debe contener exactamente {expected} elementos."),
+            excess_name: Cow::Borrowed("Se encontró un elemento sobrante llamado '{}', ¿es posiblemente un error tipográfico?"),
+            expected_element_got_elements: Cow::Borrowed("En lugar del único elemento esperado '{}' se encontraron varios elementos con este nombre."),
+            expected_field_got_fields: Cow::Borrowed("En lugar del único campo esperado '{}' se encontraron varios campos con este nombre."),
+            expected_field_got_fieldset: Cow::Borrowed("En lugar del campo esperado '{}' se encontró un conjunto de campos con este nombre."),
+            expected_field_got_list: Cow::Borrowed("En lugar del campo esperado '{}' se encontró una lista con este nombre."),
+            expected_field_got_section: Cow::Borrowed("En lugar del campo esperado '{}' se encontró una sección con este nombre."),
+            expected_fields_got_fieldset: Cow::Borrowed("Solo se esperaban campos con el nombre '{}', pero se encontró un conjunto de campos con este nombre."),
+            expected_fields_got_list: Cow::Borrowed("Solo se esperaban campos con el nombre '{}', pero se encontró una lista con este nombre."),
+            expected_fields_got_section: Cow::Borrowed("Solo se esperaban campos con el nombre '{}', pero se encontró una sección con este nombre."),
+            expected_fieldset_got_field: Cow::Borrowed("En lugar del conjunto de campos esperado '{}' se encontró un campo con este nombre."),
+            expected_fieldset_got_fieldsets: Cow::Borrowed("En lugar del único conjunto de campos esperado '{}' se encontraron varios conjuntos de campos con este nombre."),
+            expected_fieldset_got_list: Cow::Borrowed("En lugar del conjunto de campos esperado '{}' se encontró una lista con este nombre."),
+            expected_fieldset_got_section: Cow::Borrowed("En lugar del conjunto de campos esperado '{}' se encontró una sección con este nombre."),
+            expected_fieldsets_got_field: Cow::Borrowed("Solo se esperaban conjuntos de campos con el nombre '{}', pero se encontró un campo con este nombre."),
+            expected_fieldsets_got_list: Cow::Borrowed("Solo se esperaban conjuntos de campos con el nombre '{}', pero se encontró una lista con este nombre."),
+            expected_fieldsets_got_section: Cow::Borrowed("Solo se esperaban conjuntos de campos con el nombre '{}', pero se encontró una sección con este nombre."),
+            expected_list_got_field: Cow::Borrowed("En lugar de la lista esperada '{}' se encontró un campo con este nombre."),
+            expected_list_got_fieldset: Cow::Borrowed("En lugar de la lista esperada '{}' se encontró un conjunto de campos con este nombre."),
+            expected_list_got_lists: Cow::Borrowed("En lugar de la única lista esperada '{}' se encontraron varias listas con este nombre."),
+            expected_list_got_section: Cow::Borrowed("En lugar de la lista esperada '{}' se encontró una sección con este nombre."),
+            expected_lists_got_field: Cow::Borrowed("Solo se esperaban listas con el nombre '{}', pero se encontró un campo con este nombre."),
+            expected_lists_got_fieldset: Cow::Borrowed("Solo se esperaban listas con el nombre '{}', pero se encontró un conjunto de campos con este nombre."),
+            expected_lists_got_section: Cow::Borrowed("Solo se esperaban listas con el nombre '{}', pero se encontró una sección con este nombre."),
+            expected_section_got_empty: Cow::Borrowed("En lugar de la sección esperada '{}' se encontró un elemento vacío con este nombre."),
+            expected_section_got_field: Cow::Borrowed("En lugar de la sección esperada '{}' se encontró un campo con este nombre."),
+            expected_section_got_fieldset: Cow::Borrowed("En lugar de la sección esperada '{}' se encontró un conjunto de campos con este nombre."),
+            expected_section_got_list: Cow::Borrowed("En lugar de la sección esperada '{}' se encontró una lista con este nombre."),
+            expected_section_got_sections: Cow::Borrowed("En lugar de la única sección esperada '{}' se encontraron varias secciones con este nombre."),
+            expected_sections_got_empty: Cow::Borrowed("Solo se esperaban secciones con el nombre '{}', pero se encontró un elemento vacío con este nombre."),
+            expected_sections_got_field: Cow::Borrowed("Solo se esperaban secciones con el nombre '{}', pero se encontró un campo con este nombre."),
+            expected_sections_got_fieldset: Cow::Borrowed("Solo se esperaban secciones con el nombre '{}', pero se encontró un conjunto de campos con este nombre."),
+            expected_sections_got_list: Cow::Borrowed("Solo se esperaban secciones con el nombre '{}', pero se encontró una lista con este nombre."),
+            generic_error: Cow::Borrowed("Hay un problema con el valor del elemento '{}'."),
+            max_count_not_met: Cow::Borrowed("La lista '{}' contiene {actual} elementos, pero solo puede contener un máximo de {} elementos."),
+            min_count_not_met: Cow::Borrowed("La lista '{}' contiene {actual} elementos, pero debe contener al menos {} elementos."),
+            missing_element: Cow::Borrowed("Falta el elemento '{}' - en caso de que se haya especificado, busque errores tipográficos y compruebe también el uso correcto de mayúsculas y minúsculas."),
+            missing_field: Cow::Borrowed("Falta el campo '{}' - en caso de que se haya especificado, busque errores tipográficos y compruebe también el uso correcto de mayúsculas y minúsculas."),
+            missing_field_value: Cow::Borrowed("El campo '{}' debe contener un valor."),
+            missing_fieldset: Cow::Borrowed("Falta el conjunto de campos '{}' - en caso de que se haya especificado, busque errores tipográficos y compruebe también el uso correcto de mayúsculas y minúsculas."),
+            missing_fieldset_entry: Cow::Borrowed("Falta la entrada de conjunto de campos '{}' - en caso de que se haya especificado, busque errores tipográficos y compruebe también el uso correcto de mayúsculas y minúsculas."),
+            missing_fieldset_entry_value: Cow::Borrowed("La entrada de conjunto de campos '{}' debe contener un valor."),
+            missing_list: Cow::Borrowed("Falta la lista '{}' - en caso de que se haya especificado, busque errores tipográficos y compruebe también el uso correcto de mayúsculas y minúsculas."),
+            missing_list_item_value: Cow::Borrowed("La lista '{}' no puede contener elementos vacíos."),
+            missing_section: Cow::Borrowed("Falta la sección '{}' - en caso de que se haya especificado, busque errores tipográficos y compruebe también el uso correcto de mayúsculas y minúsculas.")
+        },
+
+        loaders: Loaders {
+            invalid_boolean: Cow::Borrowed("'{}' debe contener un valor booleano - los valores permitidos son 'true', 'false', 'yes' y 'no'."),
+            invalid_color: Cow::Borrowed("'{}' debe contener un color, por ejemplo '#B6D918', '#fff' o '#01b'."),
+            invalid_date: Cow::Borrowed("'{}' debe contener una fecha válida, por ejemplo '1993-11-18'."),
+            invalid_datetime: Cow::Borrowed("'{}' debe contener una fecha o una fecha y hora válidas, por ejemplo '1961-01-22' o '1989-11-09T19:17Z' (ver https://www.w3.org/TR/NOTE-datetime)."),
+            invalid_email: Cow::Borrowed("'{}' debe contener una dirección de correo electrónico válida, por ejemplo 'jane.doe@eno-lang.org'."),
+            invalid_float: Cow::Borrowed("'{}' debe contener un número decimal, por ejemplo '13.0', '-9.159' o '42'."),
+            invalid_integer: Cow::Borrowed("'{}' debe contener un número entero, por ejemplo '42' o '-21'."),
+            invalid_json: Cow::Borrowed("'{}' debe contener JSON válido - el analizador devolvió: '{}'."),
+            invalid_lat_lng: Cow::Borrowed("'{}' debe contener un par de coordenadas de latitud y longitud válido, por ejemplo '48.2093723, 16.356099'."),
+            invalid_url: Cow::Borrowed("'{}' debe contener una URL válida, por ejemplo 'https://eno-lang.org'.")
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_placeholders_in_order_or_by_name() {
+        let values: &[(&str, &dyn fmt::Display)] = &[("name", &"l"), ("actual", &1), ("expected", &3)];
+        assert_eq!(rt_format("'{}' has {} of {}.", values), "'l' has 1 of 3.");
+        assert_eq!(rt_format("'{}' has {actual} of {}.", values), "'l' has 1 of 3.");
+        assert_eq!(
+            rt_format("{expected} for '{name}', not {actual}.", values),
+            "3 for 'l', not 1."
+        );
+        assert_eq!(rt_format("{count} and {", values), "{count} and {");
+        assert_eq!(placeholders("{expected} for '{}'"), vec!["expected", ""]);
+    }
+
+    #[test]
+    #[should_panic(expected = "there is no message 'no_such_message'")]
+    fn unknown_keys_are_bugs() {
+        let english = Messages::get_messages("en").unwrap();
+        Validation::msg_with_one_value(&english.validation, "no_such_message", "x");
+    }
 }
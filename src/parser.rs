@@ -1,21 +1,34 @@
+use analyzer::Analyzer;
+use document::Document;
+use error::{ParseError, ReadError, TokenizeError};
+use messages::Messages;
+use resolver::Resolver;
 use std::collections::HashMap;
 use std::collections::LinkedList;
+use std::io::{self, BufRead};
 use tokenizer::*;
 
 #[derive(Clone, Debug)]
-pub enum ContextValues {
+pub enum ContextValues<'a> {
     Indexing(usize),
-    Input(&'static str),
-    Instruction(LinkedList<HashMap<&'static str, InstructionValues>>),
+    Input(&'a str),
+    Messages(Box<Messages>),
+    Instruction(LinkedList<Instruction<'a>>),
 }
 
-impl ContextValues {
-    pub fn get_str(&self) -> Option<&'static str> {
+impl<'a> ContextValues<'a> {
+    pub fn get_str(&self) -> Option<&'a str> {
         match self {
             ContextValues::Input(c) => Some(c),
             _ => None,
         }
     }
+    pub fn get_messages(&self) -> Option<&Messages> {
+        match self {
+            ContextValues::Messages(c) => Some(&**c),
+            _ => None,
+        }
+    }
     pub fn get_indexing(&self) -> Option<&usize> {
         match self {
             ContextValues::Indexing(c) => Some(c),
@@ -23,14 +36,14 @@ impl ContextValues {
         }
     }
 
-    pub fn get_instruction(&self) -> Option<&LinkedList<HashMap<&'static str, InstructionValues>>> {
+    pub fn get_instruction(&self) -> Option<&LinkedList<Instruction<'a>>> {
         if let ContextValues::Instruction(c) = &self {
             Some(c)
         } else {
             None
         }
     }
-    pub fn get_instruction_mut(&mut self) -> Option<&mut LinkedList<HashMap<&'static str, InstructionValues>>> {
+    pub fn get_instruction_mut(&mut self) -> Option<&mut LinkedList<Instruction<'a>>> {
         match self {
             ContextValues::Instruction(c) => Some(c),
             _ => None,
@@ -38,15 +51,260 @@ impl ContextValues {
     }
 }
 
-pub fn parse(input: &'static str,  zero_indexing: bool) -> Tokenizer {
+/// Parses `input` into a document that borrows its names from the input.
+pub fn parse(input: &str, zero_indexing: bool) -> Result<Document<'_>, ParseError> {
+    parse_with_locale(input, zero_indexing, "en")
+}
+
+/// Like `parse`, with errors reported in the language of `locale`, see
+/// `Messages::for_locale`. The document reports validation errors in the same
+/// language.
+pub fn parse_with_locale<'a>(
+    input: &'a str,
+    zero_indexing: bool,
+    locale: &str,
+) -> Result<Document<'a>, ParseError> {
+    parse_with_messages(input, zero_indexing, Messages::for_locale(locale))
+}
+
+/// Like `parse`, with errors reported from `messages`, for instance a catalog
+/// loaded by `catalog::from_eno`.
+pub fn parse_with_messages(
+    input: &str,
+    zero_indexing: bool,
+    messages: Messages,
+) -> Result<Document<'_>, ParseError> {
+    let indexing = if zero_indexing { 0 } else { 1 };
     let mut context = HashMap::new();
+    context.insert("Indexing", ContextValues::Indexing(indexing));
+    context.insert("Input", ContextValues::Input(input));
     context.insert(
-        "Indexing",
-        ContextValues::Indexing(if zero_indexing { 0 } else { 1 }),
+        "Messages",
+        ContextValues::Messages(Box::new(messages.clone())),
     );
-    context.insert("Input", ContextValues::Input(input));
-    //println!("{}", context.get("Input").unwrap().getStr().unwrap());
     let mut tokenizer = Tokenizer::new(&mut context);
-    tokenizer.tokenize();
-    tokenizer
+    tokenizer.tokenize()?;
+    let elements = Analyzer::new(&tokenizer.context).analyze()?;
+    let mut document = Document::from_analysis(&elements, input, indexing, messages);
+    Resolver::new(&mut document).resolve()?;
+    Ok(document)
+}
+
+/// Parses `input` into a document that owns all of its data, for input that
+/// does not outlive the parse, like a file read at runtime.
+pub fn parse_owned(input: String, zero_indexing: bool) -> Result<Document<'static>, ParseError> {
+    parse_owned_with_locale(input, zero_indexing, "en")
+}
+
+/// Like `parse_owned`, with messages in the language of `locale`.
+pub fn parse_owned_with_locale(
+    input: String,
+    zero_indexing: bool,
+    locale: &str,
+) -> Result<Document<'static>, ParseError> {
+    parse_with_locale(&input, zero_indexing, locale).map(Document::into_owned)
+}
+
+/// How much input `parse_reader` reads before tokenizing it, so that it
+/// stops reading soon after an invalid line.
+const TOKENIZE_EVERY: usize = 64 * 1024;
+
+/// Reads `reader`, like a file or stdin, and parses it into a document that
+/// owns its data. The input is tokenized while it is read, so that an invalid
+/// line ends the reading, and a block is only looked at again once a line that
+/// may terminate it is read. The document is built once the input ends, as
+/// its elements point into the whole of it. Unlike `read_to_string`, it
+/// reports the line of invalid UTF-8.
+pub fn parse_reader<R: BufRead>(
+    reader: R,
+    zero_indexing: bool,
+) -> Result<Document<'static>, ReadError> {
+    parse_reader_with_locale(reader, zero_indexing, "en")
+}
+
+/// Like `parse_reader`, with messages in the language of `locale`.
+pub fn parse_reader_with_locale<R: BufRead>(
+    mut reader: R,
+    zero_indexing: bool,
+    locale: &str,
+) -> Result<Document<'static>, ReadError> {
+    let indexing = if zero_indexing { 0 } else { 1 };
+    let messages = Messages::for_locale(locale);
+    let mut input = String::new();
+    let mut line = indexing;
+    let mut tokenized = Position::default();
+    let mut in_block = false;
+    loop {
+        let start = input.len();
+        match reader.read_line(&mut input) {
+            Ok(0) => break,
+            Ok(_) => line += 1,
+            Err(ref error) if error.kind() == io::ErrorKind::InvalidData => {
+                return Err(ReadError::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Line {} is not valid UTF-8.", line),
+                )))
+            }
+            Err(error) => return Err(ReadError::Io(error)),
+        }
+        let due = if in_block {
+            input[start..].trim_start().starts_with("--")
+        } else {
+            input.len() - tokenized.byte >= TOKENIZE_EVERY
+        };
+        if due && input.ends_with('\n') {
+            tokenized =
+                tokenize_lines(&input, tokenized, indexing, &messages).map_err(ParseError::from)?;
+            in_block = tokenized.byte < input.len();
+        }
+    }
+    Ok(parse_with_messages(&input, zero_indexing, messages).map(Document::into_owned)?)
+}
+
+/// Tokenizes the lines of `input` from `from` on, up to a block that is not
+/// terminated yet, and returns where to go on from.
+fn tokenize_lines(
+    input: &str,
+    from: Position,
+    indexing: usize,
+    messages: &Messages,
+) -> Result<Position, TokenizeError> {
+    let mut context = HashMap::new();
+    context.insert("Indexing", ContextValues::Indexing(indexing));
+    context.insert("Input", ContextValues::Input(input));
+    context.insert(
+        "Messages",
+        ContextValues::Messages(Box::new(messages.clone())),
+    );
+    let mut tokenizer = Tokenizer::resume(context, from);
+    for instruction in tokenizer.by_ref() {
+        instruction?;
+    }
+    Ok(tokenizer.checkpoint())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use document::Element;
+
+    #[test]
+    fn stops_at_the_first_tokenization_error() {
+        match parse("a: 1\nnot valid\n- x\n", false) {
+            Err(ParseError::Tokenization(details)) => {
+                assert_eq!(details.line, 2);
+                assert_eq!(
+                    details.message,
+                    "Line 2 does not follow any specified pattern."
+                );
+            }
+            result => panic!("{:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parses_borrowed_and_owned_input() {
+        let input = String::from("greeting: hello\n");
+        let borrowed = parse(&input, false).unwrap();
+        let owned = parse_owned(input.clone(), false).unwrap();
+        for document in &[borrowed, owned] {
+            match document.elements()[0] {
+                Element::Field(field) => assert_eq!(field.value(), Some("hello")),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn reports_errors_in_the_language_of_the_locale() {
+        let message = |locale| {
+            parse_with_locale("a: 1\nnot valid\n", false, locale)
+                .unwrap_err()
+                .message()
+                .to_string()
+        };
+        assert_eq!(
+            message("de-AT"),
+            "Zeile 2 folgt keinem der vorgesehenen Muster."
+        );
+        assert_eq!(
+            message("ES"),
+            "La línea 2 no sigue ninguno de los patrones especificados."
+        );
+        assert_eq!(
+            message("fr"),
+            "Line 2 does not follow any specified pattern."
+        );
+        let document = parse_with_locale("", false, "de").unwrap();
+        assert_eq!(
+            document.field("x").unwrap_err().message(),
+            "Das Feld 'x' fehlt - falls angegeben, eventuell nach Tippfehlern Ausschau halten und auch die Groß- und Kleinschreibung beachten."
+        );
+    }
+
+    #[test]
+    fn parses_what_it_reads() {
+        let input: &[u8] = b"greeting: hello\n# section\ncopy < greeting\n";
+        let document = parse_reader(input, false).unwrap();
+        let section = document.section("section").unwrap();
+        assert_eq!(section.field("copy").unwrap().value(), Some("hello"));
+    }
+
+    /// Input that fails when read, to tell whether the reading went on.
+    struct Unreadable;
+
+    impl io::Read for Unreadable {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("read too far"))
+        }
+    }
+
+    /// The line of the error in `input`, which is followed by more valid
+    /// lines than `parse_reader` reads before tokenizing them.
+    fn error_line_read_up_to(input: &str) -> Result<usize, String> {
+        let input = format!("{}{}", input, "a: 1\n".repeat(TOKENIZE_EVERY / 5));
+        let reader = io::BufReader::new(io::Read::chain(input.as_bytes(), Unreadable));
+        match parse_reader(reader, false) {
+            Err(ReadError::Parse(error)) => Ok(error.line()),
+            Err(ReadError::Io(error)) => Err(error.to_string()),
+            Ok(_) => Err("parsed".to_string()),
+        }
+    }
+
+    #[test]
+    fn stops_reading_soon_after_an_invalid_line() {
+        assert_eq!(error_line_read_up_to("a: 1\nnot valid\n"), Ok(2));
+    }
+
+    #[test]
+    fn reads_blocks_up_to_their_terminator() {
+        let mut input = String::from("-- text\n");
+        input.push_str(&"- not an item\n".repeat(TOKENIZE_EVERY / 7));
+        input.push_str("-- text\n");
+        assert_eq!(
+            error_line_read_up_to(&format!("{}not valid\n", input)),
+            Ok(TOKENIZE_EVERY / 7 + 3)
+        );
+
+        let document = parse_reader(input.as_bytes(), false).unwrap();
+        let text = document.field("text").unwrap().value().unwrap();
+        assert_eq!(text.lines().count(), TOKENIZE_EVERY / 7);
+
+        let input = "-- text\nline\n-- other\n";
+        match parse_reader(input.as_bytes(), false) {
+            Err(ReadError::Parse(ParseError::Tokenization(details))) => assert_eq!(details.line, 1),
+            result => panic!("{:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn reports_the_line_with_invalid_utf8() {
+        let input: &[u8] = b"a: 1\nb: \xff\n";
+        match parse_reader(input, false) {
+            Err(ReadError::Io(error)) => {
+                assert_eq!(error.to_string(), "Line 2 is not valid UTF-8.")
+            }
+            result => panic!("{:?}", result.map(|_| ())),
+        }
+    }
 }
@@ -0,0 +1,650 @@
+use document::*;
+use error::ValidationError;
+use loaders::LoaderError;
+use messages::Validation;
+
+/// The kind of element a query asks for. Decides which elements satisfy it
+/// and which Validation message reports a mismatch.
+#[derive(Clone, Copy, PartialEq)]
+enum Expected {
+    Element,
+    Field,
+    Fieldset,
+    List,
+    Section,
+}
+
+impl Expected {
+    fn key(self) -> &'static str {
+        match self {
+            Expected::Element => "element",
+            Expected::Field => "field",
+            Expected::Fieldset => "fieldset",
+            Expected::List => "list",
+            Expected::Section => "section",
+        }
+    }
+
+    /// Blocks are read like fields, and an empty element can stand in for a
+    /// field, fieldset or list that has no value, entries or items.
+    fn accepts(self, element_type: ElementType) -> bool {
+        matches!(
+            (self, element_type),
+            (Expected::Element, _)
+                | (Expected::Field, ElementType::Field)
+                | (Expected::Field, ElementType::Block)
+                | (Expected::Field, ElementType::Empty)
+                | (Expected::Fieldset, ElementType::Fieldset)
+                | (Expected::Fieldset, ElementType::Empty)
+                | (Expected::List, ElementType::List)
+                | (Expected::List, ElementType::Empty)
+                | (Expected::Section, ElementType::Section)
+        )
+    }
+}
+
+pub(crate) fn found_key(element_type: ElementType) -> &'static str {
+    match element_type {
+        ElementType::Field | ElementType::Block => "field",
+        ElementType::Fieldset => "fieldset",
+        ElementType::List => "list",
+        ElementType::Section => "section",
+        ElementType::Empty => "empty",
+        ElementType::FieldsetEntry | ElementType::ListItem => {
+            unreachable!("entries and items only occur inside fieldsets and lists")
+        }
+    }
+}
+
+impl<'d> Section<'d> {
+    /// Looks up the elements named `name` directly inside this section and
+    /// fails unless all of them are of the expected kind.
+    fn lookup(
+        &self,
+        name: &str,
+        expected: Expected,
+        single: bool,
+    ) -> Result<Vec<usize>, ValidationError> {
+        let document = self.document;
+        let ids: Vec<usize> = document.nodes[self.id]
+            .children
+            .iter()
+            .cloned()
+            .filter(|id| document.nodes[*id].name.as_deref() == Some(name))
+            .collect();
+        for id in &ids {
+            let element_type = document.nodes[*id].element_type;
+            if !expected.accepts(element_type) {
+                let el_name = format!(
+                    "expected_{}{}_got_{}",
+                    expected.key(),
+                    if single { "" } else { "s" },
+                    found_key(element_type)
+                );
+                return Err(self.error(&el_name, name, *id));
+            }
+        }
+        if single && ids.len() > 1 {
+            let el_name = format!("expected_{}_got_{}s", expected.key(), expected.key());
+            return Err(self.error(&el_name, name, ids[1]));
+        }
+        for id in &ids {
+            document.nodes[*id].touched.set(true);
+        }
+        Ok(ids)
+    }
+
+    /// Looks up the single element named `name`, which may be missing.
+    fn lookup_optional(
+        &self,
+        name: &str,
+        expected: Expected,
+    ) -> Result<Option<usize>, ValidationError> {
+        Ok(self.lookup(name, expected, true)?.first().cloned())
+    }
+
+    /// Looks up the single element named `name`, which must be present.
+    fn lookup_required(&self, name: &str, expected: Expected) -> Result<usize, ValidationError> {
+        match self.lookup_optional(name, expected)? {
+            Some(id) => Ok(id),
+            None => {
+                let el_name = format!("missing_{}", expected.key());
+                Err(self.error(&el_name, name, self.id))
+            }
+        }
+    }
+
+    fn error(&self, el_name: &str, name: &str, id: usize) -> ValidationError {
+        let message =
+            Validation::msg_with_one_value(&self.document.messages.validation, el_name, name);
+        ValidationError::Validation(self.document.details(message, id))
+    }
+
+    pub fn element(&self, name: &str) -> Result<Element<'d>, ValidationError> {
+        let id = self.lookup_required(name, Expected::Element)?;
+        Ok(self.document.element_at(id))
+    }
+
+    pub fn optional_element(&self, name: &str) -> Result<Option<Element<'d>>, ValidationError> {
+        let id = self.lookup_optional(name, Expected::Element)?;
+        Ok(id.map(|id| self.document.element_at(id)))
+    }
+
+    pub fn field(&self, name: &str) -> Result<Field<'d>, ValidationError> {
+        let id = self.lookup_required(name, Expected::Field)?;
+        Ok(self.field_handle(id))
+    }
+
+    pub fn optional_field(&self, name: &str) -> Result<Option<Field<'d>>, ValidationError> {
+        let id = self.lookup_optional(name, Expected::Field)?;
+        Ok(id.map(|id| self.field_handle(id)))
+    }
+
+    pub fn fields(&self, name: &str) -> Result<Vec<Field<'d>>, ValidationError> {
+        let ids = self.lookup(name, Expected::Field, false)?;
+        Ok(ids.into_iter().map(|id| self.field_handle(id)).collect())
+    }
+
+    pub fn fieldset(&self, name: &str) -> Result<Fieldset<'d>, ValidationError> {
+        let id = self.lookup_required(name, Expected::Fieldset)?;
+        Ok(self.fieldset_handle(id))
+    }
+
+    pub fn optional_fieldset(&self, name: &str) -> Result<Option<Fieldset<'d>>, ValidationError> {
+        let id = self.lookup_optional(name, Expected::Fieldset)?;
+        Ok(id.map(|id| self.fieldset_handle(id)))
+    }
+
+    pub fn fieldsets(&self, name: &str) -> Result<Vec<Fieldset<'d>>, ValidationError> {
+        let ids = self.lookup(name, Expected::Fieldset, false)?;
+        Ok(ids.into_iter().map(|id| self.fieldset_handle(id)).collect())
+    }
+
+    pub fn list(&self, name: &str) -> Result<List<'d>, ValidationError> {
+        let id = self.lookup_required(name, Expected::List)?;
+        Ok(self.list_handle(id))
+    }
+
+    pub fn optional_list(&self, name: &str) -> Result<Option<List<'d>>, ValidationError> {
+        let id = self.lookup_optional(name, Expected::List)?;
+        Ok(id.map(|id| self.list_handle(id)))
+    }
+
+    pub fn lists(&self, name: &str) -> Result<Vec<List<'d>>, ValidationError> {
+        let ids = self.lookup(name, Expected::List, false)?;
+        Ok(ids.into_iter().map(|id| self.list_handle(id)).collect())
+    }
+
+    pub fn section(&self, name: &str) -> Result<Section<'d>, ValidationError> {
+        let id = self.lookup_required(name, Expected::Section)?;
+        Ok(self.section_handle(id))
+    }
+
+    pub fn optional_section(&self, name: &str) -> Result<Option<Section<'d>>, ValidationError> {
+        let id = self.lookup_optional(name, Expected::Section)?;
+        Ok(id.map(|id| self.section_handle(id)))
+    }
+
+    pub fn sections(&self, name: &str) -> Result<Vec<Section<'d>>, ValidationError> {
+        let ids = self.lookup(name, Expected::Section, false)?;
+        Ok(ids.into_iter().map(|id| self.section_handle(id)).collect())
+    }
+
+    /// Fails on the first element in this section, its sections or its
+    /// fieldsets that was never queried or touched. Iterating elements or
+    /// entries, formatting them or selecting them does not count as a query.
+    pub fn assert_all_touched(&self) -> Result<(), ValidationError> {
+        let document = self.document;
+        for id in &document.nodes[self.id].children {
+            let node = &document.nodes[*id];
+            if !node.touched.get() {
+                return Err(self.error("excess_name", node.name.as_deref().unwrap(), *id));
+            }
+            match node.element_type {
+                ElementType::Section => self.section_handle(*id).assert_all_touched()?,
+                ElementType::Fieldset => {
+                    for entry in &node.children {
+                        let node = &document.nodes[*entry];
+                        if !node.touched.get() {
+                            let name = node.name.as_deref().unwrap();
+                            return Err(self.error("excess_name", name, *entry));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn field_handle(&self, id: usize) -> Field<'d> {
+        Field {
+            document: self.document,
+            id,
+        }
+    }
+
+    fn fieldset_handle(&self, id: usize) -> Fieldset<'d> {
+        Fieldset {
+            document: self.document,
+            id,
+        }
+    }
+
+    fn list_handle(&self, id: usize) -> List<'d> {
+        List {
+            document: self.document,
+            id,
+        }
+    }
+
+    fn section_handle(&self, id: usize) -> Section<'d> {
+        Section {
+            document: self.document,
+            id,
+        }
+    }
+}
+
+impl<'d> Fieldset<'d> {
+    pub fn entry(&self, name: &str) -> Result<FieldsetEntry<'d>, ValidationError> {
+        match self.optional_entry(name) {
+            Some(entry) => Ok(entry),
+            None => {
+                let message = Validation::msg_with_one_value(
+                    &self.document.messages.validation,
+                    "missing_fieldset_entry",
+                    name,
+                );
+                Err(ValidationError::Validation(
+                    self.document.details(message, self.id),
+                ))
+            }
+        }
+    }
+
+    /// Entry names are unique within a fieldset, so this never fails.
+    pub fn optional_entry(&self, name: &str) -> Option<FieldsetEntry<'d>> {
+        let entry = self
+            .entries()
+            .into_iter()
+            .find(|entry| entry.name() == name);
+        if let Some(entry) = entry {
+            entry.touch();
+        }
+        entry
+    }
+}
+
+/// Bounds on the number of items in a list, checked by `List::items_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ItemCount {
+    exact: Option<usize>,
+    min: Option<usize>,
+    max: Option<usize>,
+}
+
+impl ItemCount {
+    pub fn new() -> ItemCount {
+        ItemCount::default()
+    }
+
+    pub fn exact_count(mut self, count: usize) -> ItemCount {
+        self.exact = Some(count);
+        self
+    }
+
+    pub fn min_count(mut self, count: usize) -> ItemCount {
+        self.min = Some(count);
+        self
+    }
+
+    pub fn max_count(mut self, count: usize) -> ItemCount {
+        self.max = Some(count);
+        self
+    }
+}
+
+impl<'d> List<'d> {
+    /// The items of the list, failing if their number is out of `count`.
+    pub fn items_with(&self, count: ItemCount) -> Result<Vec<ListItem<'d>>, ValidationError> {
+        let items = self.items();
+        let actual = items.len();
+        let violation = match count {
+            ItemCount {
+                exact: Some(exact), ..
+            } if actual != exact => Some(("exact_count_not_met", exact)),
+            ItemCount { min: Some(min), .. } if actual < min => Some(("min_count_not_met", min)),
+            ItemCount { max: Some(max), .. } if actual > max => Some(("max_count_not_met", max)),
+            _ => None,
+        };
+        match violation {
+            Some((el_name, expected)) => {
+                let message = Validation::msg_with_three_values(
+                    &self.document.messages.validation,
+                    el_name,
+                    self.name(),
+                    actual,
+                    expected,
+                );
+                Err(ValidationError::Validation(
+                    self.document.details(message, self.id),
+                ))
+            }
+            None => Ok(items),
+        }
+    }
+}
+
+impl<'d> Field<'d> {
+    /// The value converted by `loader`, or `None` if the field has no value.
+    pub fn value_with<T, F>(&self, loader: F) -> Result<Option<T>, ValidationError>
+    where
+        F: Fn(&str) -> Result<T, LoaderError>,
+    {
+        load(self.document, self.id, self.name(), loader)
+    }
+
+    pub fn required_value_with<T, F>(&self, loader: F) -> Result<T, ValidationError>
+    where
+        F: Fn(&str) -> Result<T, LoaderError>,
+    {
+        let value = self.value_with(loader)?;
+        require(
+            self.document,
+            self.id,
+            "missing_field_value",
+            self.name(),
+            value,
+        )
+    }
+}
+
+impl<'d> FieldsetEntry<'d> {
+    /// The value converted by `loader`, or `None` if the entry has no value.
+    pub fn value_with<T, F>(&self, loader: F) -> Result<Option<T>, ValidationError>
+    where
+        F: Fn(&str) -> Result<T, LoaderError>,
+    {
+        load(self.document, self.id, self.name(), loader)
+    }
+
+    pub fn required_value_with<T, F>(&self, loader: F) -> Result<T, ValidationError>
+    where
+        F: Fn(&str) -> Result<T, LoaderError>,
+    {
+        let value = self.value_with(loader)?;
+        require(
+            self.document,
+            self.id,
+            "missing_fieldset_entry_value",
+            self.name(),
+            value,
+        )
+    }
+}
+
+/// Items are reported by the name of their list.
+impl<'d> ListItem<'d> {
+    /// The value converted by `loader`, or `None` if the item is empty.
+    pub fn value_with<T, F>(&self, loader: F) -> Result<Option<T>, ValidationError>
+    where
+        F: Fn(&str) -> Result<T, LoaderError>,
+    {
+        load(self.document, self.id, self.parent().name(), loader)
+    }
+
+    pub fn required_value_with<T, F>(&self, loader: F) -> Result<T, ValidationError>
+    where
+        F: Fn(&str) -> Result<T, LoaderError>,
+    {
+        let value = self.value_with(loader)?;
+        require(
+            self.document,
+            self.id,
+            "missing_list_item_value",
+            self.parent().name(),
+            value,
+        )
+    }
+}
+
+fn load<T, F>(
+    document: &Document,
+    id: usize,
+    name: &str,
+    loader: F,
+) -> Result<Option<T>, ValidationError>
+where
+    F: Fn(&str) -> Result<T, LoaderError>,
+{
+    match document.nodes[id].value.as_deref() {
+        Some(value) => loader(value).map(Some).map_err(|error| {
+            let message = error.message(&document.messages.loaders, name);
+            ValidationError::Loaders(document.details(message, id))
+        }),
+        None => Ok(None),
+    }
+}
+
+fn require<T>(
+    document: &Document,
+    id: usize,
+    el_name: &str,
+    name: &str,
+    value: Option<T>,
+) -> Result<T, ValidationError> {
+    value.ok_or_else(|| {
+        let message = Validation::msg_with_one_value(&document.messages.validation, el_name, name);
+        ValidationError::Validation(document.details(message, id))
+    })
+}
+
+/// Queries on the document ask its top level, like a section would.
+impl<'a> Document<'a> {
+    fn root(&self) -> Section<'_> {
+        Section {
+            document: self,
+            id: DOCUMENT,
+        }
+    }
+
+    pub fn element(&self, name: &str) -> Result<Element<'_>, ValidationError> {
+        self.root().element(name)
+    }
+
+    pub fn optional_element(&self, name: &str) -> Result<Option<Element<'_>>, ValidationError> {
+        self.root().optional_element(name)
+    }
+
+    pub fn field(&self, name: &str) -> Result<Field<'_>, ValidationError> {
+        self.root().field(name)
+    }
+
+    pub fn optional_field(&self, name: &str) -> Result<Option<Field<'_>>, ValidationError> {
+        self.root().optional_field(name)
+    }
+
+    pub fn fields(&self, name: &str) -> Result<Vec<Field<'_>>, ValidationError> {
+        self.root().fields(name)
+    }
+
+    pub fn fieldset(&self, name: &str) -> Result<Fieldset<'_>, ValidationError> {
+        self.root().fieldset(name)
+    }
+
+    pub fn optional_fieldset(&self, name: &str) -> Result<Option<Fieldset<'_>>, ValidationError> {
+        self.root().optional_fieldset(name)
+    }
+
+    pub fn fieldsets(&self, name: &str) -> Result<Vec<Fieldset<'_>>, ValidationError> {
+        self.root().fieldsets(name)
+    }
+
+    pub fn list(&self, name: &str) -> Result<List<'_>, ValidationError> {
+        self.root().list(name)
+    }
+
+    pub fn optional_list(&self, name: &str) -> Result<Option<List<'_>>, ValidationError> {
+        self.root().optional_list(name)
+    }
+
+    pub fn lists(&self, name: &str) -> Result<Vec<List<'_>>, ValidationError> {
+        self.root().lists(name)
+    }
+
+    pub fn section(&self, name: &str) -> Result<Section<'_>, ValidationError> {
+        self.root().section(name)
+    }
+
+    pub fn optional_section(&self, name: &str) -> Result<Option<Section<'_>>, ValidationError> {
+        self.root().optional_section(name)
+    }
+
+    pub fn sections(&self, name: &str) -> Result<Vec<Section<'_>>, ValidationError> {
+        self.root().sections(name)
+    }
+
+    pub fn assert_all_touched(&self) -> Result<(), ValidationError> {
+        self.root().assert_all_touched()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use loaders;
+    use parser::parse;
+
+    const INPUT: &str = "title: Notes\nfieldset:\na = 1\nb = 2\n";
+
+    #[test]
+    fn looks_up_elements_in_the_document_and_in_sections() {
+        let document = parse("title: Notes\nfieldset:\na = 1\n# s\nl:\n- x\n", false).unwrap();
+        assert_eq!(document.field("title").unwrap().value(), Some("Notes"));
+        let fieldset = document.fieldset("fieldset").unwrap();
+        assert_eq!(fieldset.entry("a").unwrap().value(), Some("1"));
+        assert!(fieldset.optional_entry("b").is_none());
+        let list = document.section("s").unwrap().list("l").unwrap();
+        assert_eq!(list.items()[0].value(), Some("x"));
+    }
+
+    #[test]
+    fn reports_missing_and_mistyped_elements() {
+        let document = parse("l:\n- x\nf: 1\nf: 2\n# s\n", false).unwrap();
+        let message = |error: ValidationError| error.message().to_string();
+        assert_eq!(
+            message(document.field("missing").unwrap_err()),
+            "The field 'missing' is missing - in case it has been specified look for typos and also check for correct capitalization."
+        );
+        assert_eq!(
+            message(document.field("l").unwrap_err()),
+            "Instead of the expected field 'l' a list with this name was found."
+        );
+        assert_eq!(
+            message(document.field("f").unwrap_err()),
+            "Instead of the expected single field 'f' several fields with this name were found."
+        );
+        assert_eq!(
+            message(document.lists("s").unwrap_err()),
+            "Only lists with the name 's' were expected, but a section with this name was found."
+        );
+        assert!(document.optional_section("missing").unwrap().is_none());
+        assert_eq!(document.fields("f").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn loads_values_and_reports_loader_errors() {
+        let document = parse("n: 42\nx: forty\ne:\n", false).unwrap();
+        let field = document.field("n").unwrap();
+        assert_eq!(field.required_value_with(loaders::integer).unwrap(), 42);
+        let error = document
+            .field("x")
+            .unwrap()
+            .value_with(loaders::integer)
+            .unwrap_err();
+        assert_eq!(
+            error.message(),
+            "'x' must contain an integer, for instance '42' or '-21'."
+        );
+        assert_eq!(error.line(), 2);
+        let empty = document.field("e").unwrap();
+        assert_eq!(empty.value_with(loaders::integer).unwrap(), None);
+        assert_eq!(
+            empty
+                .required_value_with(loaders::integer)
+                .unwrap_err()
+                .message(),
+            "The field 'e' must contain a value."
+        );
+    }
+
+    #[test]
+    fn reports_untouched_elements_in_sections() {
+        let document = parse("a: 1\n# s\nb: 2\nc: 3\n", false).unwrap();
+        document.field("a").unwrap();
+        let section = document.section("s").unwrap();
+        section.field("b").unwrap();
+        let error = document.assert_all_touched().unwrap_err();
+        assert_eq!(
+            error.message(),
+            "An excess element named 'c' was found, is it possibly a typo?"
+        );
+        assert_eq!(error.line(), 4);
+        section.element("c").unwrap();
+        assert!(document.assert_all_touched().is_ok());
+    }
+
+    #[test]
+    fn entries_looked_up_by_name_leave_the_others_untouched() {
+        let document = parse(INPUT, false).unwrap();
+        document.field("title").unwrap();
+        let fieldset = document.fieldset("fieldset").unwrap();
+        assert_eq!(fieldset.entry("a").unwrap().value(), Some("1"));
+        let error = document.assert_all_touched().unwrap_err();
+        assert_eq!(
+            error.message(),
+            "An excess element named 'b' was found, is it possibly a typo?"
+        );
+        assert_eq!(error.line(), 4);
+    }
+
+    #[test]
+    fn checks_the_number_of_items() {
+        let document = parse("l:\n- a\n- b\n", false).unwrap();
+        let list = document.list("l").unwrap();
+        assert_eq!(
+            list.items_with(ItemCount::new().min_count(2))
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            list.items_with(ItemCount::new().exact_count(3))
+                .unwrap_err()
+                .message(),
+            "The list 'l' contains 2 items, but must contain exactly 3 items."
+        );
+        assert_eq!(
+            list.items_with(ItemCount::new().max_count(1))
+                .unwrap_err()
+                .message(),
+            "The list 'l' contains 2 items, but may only contain a maximum of 1 items."
+        );
+    }
+
+    #[test]
+    fn only_lookups_touch_entries() {
+        let document = parse(INPUT, false).unwrap();
+        document.field("title").unwrap();
+        let fieldset = document.fieldset("fieldset").unwrap();
+        assert_eq!(fieldset.entries().len(), 2);
+        assert_eq!(document.select("fieldset/*").unwrap().len(), 2);
+        let _ = format!("{:?}", document);
+        let error = document.assert_all_touched().unwrap_err();
+        assert_eq!(error.line(), 3);
+        fieldset.entry("a").unwrap();
+        fieldset.optional_entry("b").unwrap();
+        assert!(document.assert_all_touched().is_ok());
+    }
+}
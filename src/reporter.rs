@@ -0,0 +1,422 @@
+use error::ErrorDetails;
+use std::cmp;
+use std::fmt::Write;
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar;
+
+/// Renders an error together with the lines of the document it occurred in.
+pub trait Reporter {
+    fn report(&self, details: &ErrorDetails) -> String;
+}
+
+/// Reports errors as plain text, for logs and files:
+///
+/// ```text
+/// Line 3 does not follow any specified pattern.
+///
+///   1 | author: Jane Doe
+///   2 | title: Notes
+/// > 3 | what is this
+///     | ^^^^^^^^^^^^
+///   4 | tags:
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct TextReporter<'a> {
+    input: &'a str,
+    context_lines: usize,
+}
+
+/// Reports errors like `TextReporter`, colored with ANSI escape codes.
+#[derive(Clone, Copy, Debug)]
+pub struct TerminalReporter<'a> {
+    input: &'a str,
+    context_lines: usize,
+}
+
+/// Reports errors as an HTML fragment for embedding in a page. The offending
+/// range is wrapped in a `<span class="eno-highlight">` and the other parts
+/// carry `eno-` classes as well, so the page decides how they look.
+#[derive(Clone, Copy, Debug)]
+pub struct HtmlReporter<'a> {
+    input: &'a str,
+    context_lines: usize,
+}
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+/// The lines around an error, with the byte range of each line that the
+/// error covers.
+struct Snippet<'a> {
+    lines: Vec<(usize, &'a str, Option<Range<usize>>)>,
+    width: usize,
+}
+
+impl<'a> TextReporter<'a> {
+    /// A reporter for errors in `input`, showing two lines of context around
+    /// the offending ones.
+    pub fn new(input: &'a str) -> TextReporter<'a> {
+        TextReporter {
+            input,
+            context_lines: 2,
+        }
+    }
+
+    pub fn context_lines(mut self, context_lines: usize) -> TextReporter<'a> {
+        self.context_lines = context_lines;
+        self
+    }
+}
+
+impl<'a> TerminalReporter<'a> {
+    pub fn new(input: &'a str) -> TerminalReporter<'a> {
+        TerminalReporter {
+            input,
+            context_lines: 2,
+        }
+    }
+
+    pub fn context_lines(mut self, context_lines: usize) -> TerminalReporter<'a> {
+        self.context_lines = context_lines;
+        self
+    }
+}
+
+impl<'a> HtmlReporter<'a> {
+    pub fn new(input: &'a str) -> HtmlReporter<'a> {
+        HtmlReporter {
+            input,
+            context_lines: 2,
+        }
+    }
+
+    pub fn context_lines(mut self, context_lines: usize) -> HtmlReporter<'a> {
+        self.context_lines = context_lines;
+        self
+    }
+}
+
+impl<'a> Reporter for TextReporter<'a> {
+    fn report(&self, details: &ErrorDetails) -> String {
+        text_report(self.input, self.context_lines, details, false)
+    }
+}
+
+impl<'a> Reporter for TerminalReporter<'a> {
+    fn report(&self, details: &ErrorDetails) -> String {
+        text_report(self.input, self.context_lines, details, true)
+    }
+}
+
+impl<'a> Reporter for HtmlReporter<'a> {
+    fn report(&self, details: &ErrorDetails) -> String {
+        let mut report = String::from("<div class=\"eno-report\">\n");
+        let _ = writeln!(
+            report,
+            "<div class=\"eno-message\">{}</div>",
+            escape(&details.message)
+        );
+        if let Some(snippet) = Snippet::new(self.input, self.context_lines, details) {
+            report.push_str("<pre class=\"eno-snippet\">");
+            for (number, line, highlight) in snippet.lines {
+                let _ = write!(
+                    report,
+                    "<div class=\"eno-line{}\"><span class=\"eno-gutter\">{:>width$}</span>",
+                    if highlight.is_some() {
+                        " eno-marked"
+                    } else {
+                        ""
+                    },
+                    number,
+                    width = snippet.width
+                );
+                match highlight {
+                    Some(range) => {
+                        let _ = write!(
+                            report,
+                            "{}<span class=\"eno-highlight\">{}</span>{}",
+                            escape(&line[..range.start]),
+                            escape(&line[range.clone()]),
+                            escape(&line[range.end..])
+                        );
+                    }
+                    None => report.push_str(&escape(line)),
+                }
+                report.push_str("</div>");
+            }
+            report.push_str("</pre>\n");
+        }
+        report.push_str("</div>\n");
+        report
+    }
+}
+
+/// The message of the error followed by the snippet, with carets under the
+/// offending range. Errors about the document as a whole, like a missing
+/// field at its top level, are reported by their message alone.
+fn text_report(input: &str, context_lines: usize, details: &ErrorDetails, colors: bool) -> String {
+    let paint = |report: &mut String, color: &str, text: &str| {
+        if colors {
+            let _ = write!(report, "{}{}{}", color, text, RESET);
+        } else {
+            report.push_str(text);
+        }
+    };
+    let mut report = String::new();
+    paint(&mut report, BOLD, &details.message);
+    report.push('\n');
+    let snippet = match Snippet::new(input, context_lines, details) {
+        Some(snippet) => snippet,
+        None => return report,
+    };
+    report.push('\n');
+    for (number, line, highlight) in snippet.lines {
+        let gutter = format!(
+            "{} {:>width$} | ",
+            if highlight.is_some() { '>' } else { ' ' },
+            number,
+            width = snippet.width
+        );
+        match highlight {
+            Some(range) => {
+                paint(&mut report, RED, &gutter);
+                report.push_str(line);
+                report.push('\n');
+                let carets = cmp::max(columns(&line[range.clone()]), 1);
+                paint(
+                    &mut report,
+                    DIM,
+                    &format!("  {:>width$} | ", "", width = snippet.width),
+                );
+                report.push_str(&indent(&line[..range.start]));
+                paint(&mut report, RED, &"^".repeat(carets));
+            }
+            None => {
+                paint(&mut report, DIM, &gutter);
+                paint(&mut report, DIM, line);
+            }
+        }
+        report.push('\n');
+    }
+    report
+}
+
+impl<'a> Snippet<'a> {
+    /// `None` for errors without a range to point at.
+    fn new(input: &'a str, context_lines: usize, details: &ErrorDetails) -> Option<Snippet<'a>> {
+        if details.range.start >= details.range.end {
+            return None;
+        }
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        for line in input.trim_end_matches('\n').split('\n') {
+            lines.push((offset, line.trim_end_matches('\r')));
+            offset += line.len() + 1;
+        }
+        let start = cmp::min(details.range.start, input.len());
+        let end = cmp::min(details.range.end, input.len());
+        let first = line_of(&lines, start);
+        let last = line_of(&lines, cmp::max(start, end.saturating_sub(1)));
+        // Errors are numbered like the document, which need not start at 0.
+        let indexing = details.line.saturating_sub(first);
+        let shown =
+            first.saturating_sub(context_lines)..cmp::min(last + context_lines + 1, lines.len());
+        let width = (shown.end - 1 + indexing).to_string().len();
+        let lines = shown
+            .map(|number| {
+                let (offset, line) = lines[number];
+                let highlight = if number >= first && number <= last {
+                    let from = cmp::min(cmp::max(start, offset) - offset, line.len());
+                    let to = cmp::max(
+                        cmp::min(end, offset + line.len()).saturating_sub(offset),
+                        from,
+                    );
+                    Some(from..to)
+                } else {
+                    None
+                };
+                (number + indexing, line, highlight)
+            })
+            .collect();
+        Some(Snippet { lines, width })
+    }
+}
+
+/// Blanks as wide as `text` in a terminal, keeping its tabs so they line up
+/// however wide the terminal shows them.
+fn indent(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\t' => "\t".to_string(),
+            c => " ".repeat(c.width().unwrap_or(0)),
+        })
+        .collect()
+}
+
+/// The columns `text` takes up in a terminal, counting a tab as one.
+fn columns(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c {
+            '\t' => 1,
+            c => c.width().unwrap_or(0),
+        })
+        .sum()
+}
+
+/// The number of the line that contains the byte at `index`.
+fn line_of(lines: &[(usize, &str)], index: usize) -> usize {
+    match lines.binary_search_by(|&(offset, _)| offset.cmp(&index)) {
+        Ok(number) => number,
+        Err(number) => number - 1,
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Range;
+
+    const INPUT: &str = "author: Jane Doe\ntitle: Notes\nwhat is this\ntags:\n- a\n- b\n";
+
+    fn details(line: usize, range: Range<usize>) -> ErrorDetails {
+        ErrorDetails {
+            message: format!("Line {} is wrong.", line),
+            line,
+            column: 0,
+            range,
+        }
+    }
+
+    #[test]
+    fn underlines_the_range_with_context_around_it() {
+        let reporter = TextReporter::new(INPUT).context_lines(1);
+        assert_eq!(
+            reporter.report(&details(3, 35..37)),
+            "Line 3 is wrong.\n\
+             \n  2 | title: Notes\
+             \n> 3 | what is this\
+             \n    |      ^^\
+             \n  4 | tags:\n"
+        );
+    }
+
+    #[test]
+    fn marks_each_line_of_a_multi_line_range() {
+        let reporter = TextReporter::new(INPUT).context_lines(0);
+        assert_eq!(
+            reporter.report(&details(2, 17..42)),
+            "Line 2 is wrong.\n\
+             \n> 2 | title: Notes\
+             \n    | ^^^^^^^^^^^^\
+             \n> 3 | what is this\
+             \n    | ^^^^^^^^^^^^\n"
+        );
+    }
+
+    #[test]
+    fn leaves_carriage_returns_out_of_lines_and_carets() {
+        let reporter = TextReporter::new("a: 1\r\nwhat\r\n");
+        assert_eq!(
+            reporter.report(&details(2, 6..11)),
+            "Line 2 is wrong.\n\
+             \n  1 | a: 1\
+             \n> 2 | what\
+             \n    | ^^^^\n"
+        );
+    }
+
+    #[test]
+    fn numbers_lines_like_the_error() {
+        let reporter = TextReporter::new(INPUT).context_lines(0);
+        assert_eq!(
+            reporter.report(&details(2, 30..34)),
+            "Line 2 is wrong.\n\
+             \n> 2 | what is this\
+             \n    | ^^^^\n"
+        );
+    }
+
+    #[test]
+    fn colors_the_report_for_terminals() {
+        let reporter = TerminalReporter::new("bad\n");
+        assert_eq!(
+            reporter.report(&details(1, 0..3)),
+            "\x1b[1mLine 1 is wrong.\x1b[0m\n\
+             \n\x1b[1;31m> 1 | \x1b[0mbad\
+             \n\x1b[2m    | \x1b[0m\x1b[1;31m^^^\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn reports_errors_without_a_range_by_their_message() {
+        let reporter = TextReporter::new(INPUT);
+        assert_eq!(reporter.report(&details(1, 0..0)), "Line 1 is wrong.\n");
+    }
+
+    #[test]
+    fn escapes_html_and_highlights_the_range() {
+        let reporter = HtmlReporter::new("a: <b>\nx & y\n").context_lines(1);
+        let mut details = details(2, 9..10);
+        details.message = "Expected <name> & 'value'.".to_string();
+        assert_eq!(
+            reporter.report(&details),
+            "<div class=\"eno-report\">\n\
+             <div class=\"eno-message\">Expected &lt;name&gt; &amp; &#39;value&#39;.</div>\n\
+             <pre class=\"eno-snippet\">\
+             <div class=\"eno-line\"><span class=\"eno-gutter\">1</span>a: &lt;b&gt;</div>\
+             <div class=\"eno-line eno-marked\"><span class=\"eno-gutter\">2</span>\
+             x <span class=\"eno-highlight\">&amp;</span> y</div>\
+             </pre>\n\
+             </div>\n"
+        );
+    }
+
+    #[test]
+    fn reporters_share_the_trait() {
+        let input = "bad\n";
+        let reporters: Vec<Box<dyn Reporter>> = vec![
+            Box::new(TextReporter::new(input)),
+            Box::new(TerminalReporter::new(input)),
+            Box::new(HtmlReporter::new(input)),
+        ];
+        for reporter in &reporters {
+            assert!(reporter
+                .report(&details(1, 0..3))
+                .contains("Line 1 is wrong."));
+        }
+    }
+
+    #[test]
+    fn lines_carets_up_after_tabs_and_wide_characters() {
+        let reporter = TextReporter::new("\tkey: 日本語 bad\n");
+        assert_eq!(
+            reporter.report(&details(1, 16..19)),
+            "Line 1 is wrong.\n\
+             \n> 1 | \tkey: 日本語 bad\
+             \n    | \t            ^^^\n"
+        );
+        assert_eq!(
+            reporter.report(&details(1, 6..15)),
+            "Line 1 is wrong.\n\
+             \n> 1 | \tkey: 日本語 bad\
+             \n    | \t     ^^^^^^\n"
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use document::{Document, ElementType};
+use error::ParseError;
+use messages::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Unresolved,
+    Resolving,
+    Resolved,
+}
+
+/// Resolves `<` (shallow) and `<<` (deep) copies in place. Templates are
+/// looked up by name among all elements of the document as written, so
+/// elements produced by earlier copies never become templates themselves.
+pub struct Resolver<'d, 'a: 'd> {
+    document: &'d mut Document<'a>,
+    index: HashMap<Cow<'a, str>, Vec<usize>>,
+    states: Vec<State>,
+}
+
+impl<'d, 'a> Resolver<'d, 'a> {
+    pub fn new(document: &'d mut Document<'a>) -> Resolver<'d, 'a> {
+        let mut index = HashMap::new();
+        for (id, node) in document.nodes.iter().enumerate() {
+            match node.element_type {
+                ElementType::FieldsetEntry | ElementType::ListItem => continue,
+                _ => {}
+            }
+            if let Some(ref name) = node.name {
+                index.entry(name.clone()).or_insert_with(Vec::new).push(id);
+            }
+        }
+        let states = vec![State::Unresolved; document.nodes.len()];
+        Resolver {
+            document,
+            index,
+            states,
+        }
+    }
+
+    pub fn resolve(&mut self) -> Result<(), ParseError> {
+        for id in 0..self.states.len() {
+            self.resolve_element(id)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_element(&mut self, id: usize) -> Result<(), ParseError> {
+        // Elements created by copying are clones of already resolved ones.
+        if id >= self.states.len() {
+            return Ok(());
+        }
+        match self.states[id] {
+            State::Resolved => return Ok(()),
+            State::Resolving => return Err(self.error_with_name("cyclic_dependency", id)),
+            State::Unresolved => {}
+        }
+        let template_name = match self.document.nodes[id].template.clone() {
+            Some(template_name) => template_name,
+            None => {
+                self.states[id] = State::Resolved;
+                return Ok(());
+            }
+        };
+        self.states[id] = State::Resolving;
+
+        let template = match self.index.get(&template_name) {
+            Some(candidates) => {
+                let candidates: Vec<usize> =
+                    candidates.iter().cloned().filter(|c| *c != id).collect();
+                match candidates.len() {
+                    // Only the element itself has the name, as in `a < a`.
+                    0 => return Err(self.error_with_name("cyclic_dependency", id)),
+                    1 => candidates[0],
+                    _ => return Err(self.error_with_name("multiple_templates_found", id)),
+                }
+            }
+            None => return Err(self.error_with_name("template_not_found", id)),
+        };
+        self.resolve_tree(template)?;
+        self.copy(template, id)?;
+
+        self.states[id] = State::Resolved;
+        Ok(())
+    }
+
+    fn resolve_tree(&mut self, id: usize) -> Result<(), ParseError> {
+        self.resolve_element(id)?;
+        for child in self.document.nodes[id].children.clone() {
+            self.resolve_tree(child)?;
+        }
+        Ok(())
+    }
+
+    fn copy(&mut self, template: usize, target: usize) -> Result<(), ParseError> {
+        let template_type = self.document.nodes[template].element_type;
+        let target_type = self.document.nodes[target].element_type;
+        match (template_type, target_type) {
+            (ElementType::Section, ElementType::Section) => {
+                for child in self.document.nodes[target].children.clone() {
+                    self.resolve_tree(child)?;
+                }
+                let deep = self.document.nodes[target].deep_copy;
+                self.merge(template, target, deep);
+            }
+            (_, ElementType::Empty) if template_type != ElementType::Section => {
+                let children = self.clone_children(template, target);
+                let value = self.document.nodes[template].value.clone();
+                let node = &mut self.document.nodes[target];
+                node.element_type = template_type;
+                node.value = value;
+                node.children = children;
+            }
+            (ElementType::Field, ElementType::Field)
+            | (ElementType::Block, ElementType::Field)
+            | (ElementType::Empty, ElementType::Field) => {}
+            (ElementType::Fieldset, ElementType::Fieldset) => self.merge_entries(template, target),
+            (ElementType::List, ElementType::List) => self.merge_items(template, target),
+            _ => {
+                // An empty template is copied like a field without a value.
+                let template_key = match template_type {
+                    ElementType::Empty => "field",
+                    _ => type_key(template_type),
+                };
+                let el_name = format!("copying_{}_into_{}", template_key, type_key(target_type));
+                let message = Resolution::msg_with_one_value(
+                    &self.document.messages.resolution,
+                    &el_name,
+                    self.document.line(target),
+                );
+                return Err(self.located(message, target));
+            }
+        }
+        Ok(())
+    }
+
+    /// Prepends the template section's elements to the target section.
+    /// Elements the target redefines by name replace the template's; with a
+    /// deep copy, redefined sections, fieldsets and lists are merged instead.
+    fn merge(&mut self, template: usize, target: usize, deep: bool) {
+        let own = self.document.nodes[target].children.clone();
+        let mut merged = Vec::new();
+        for child in self.document.nodes[template].children.clone() {
+            let name = self.document.nodes[child].name.clone();
+            let overrides: Vec<usize> = own
+                .iter()
+                .cloned()
+                .filter(|o| self.document.nodes[*o].name == name)
+                .collect();
+            if overrides.is_empty() {
+                merged.push(self.clone_tree(child, target));
+                continue;
+            }
+            if deep && overrides.len() == 1 {
+                let child_type = self.document.nodes[child].element_type;
+                match (child_type, self.document.nodes[overrides[0]].element_type) {
+                    (ElementType::Section, ElementType::Section) => {
+                        self.merge(child, overrides[0], true)
+                    }
+                    (ElementType::Fieldset, ElementType::Fieldset) => {
+                        self.merge_entries(child, overrides[0])
+                    }
+                    (ElementType::List, ElementType::List) => self.merge_items(child, overrides[0]),
+                    _ => {}
+                }
+            }
+        }
+        merged.extend(own);
+        self.document.nodes[target].children = merged;
+    }
+
+    fn merge_entries(&mut self, template: usize, target: usize) {
+        let own = self.document.nodes[target].children.clone();
+        let mut merged = Vec::new();
+        for entry in self.document.nodes[template].children.clone() {
+            let name = self.document.nodes[entry].name.clone();
+            if !own.iter().any(|o| self.document.nodes[*o].name == name) {
+                merged.push(self.clone_tree(entry, target));
+            }
+        }
+        merged.extend(own);
+        self.document.nodes[target].children = merged;
+    }
+
+    fn merge_items(&mut self, template: usize, target: usize) {
+        let mut merged = self.clone_children(template, target);
+        merged.extend(self.document.nodes[target].children.iter().cloned());
+        self.document.nodes[target].children = merged;
+    }
+
+    fn clone_children(&mut self, source: usize, parent: usize) -> Vec<usize> {
+        self.document.nodes[source]
+            .children
+            .clone()
+            .into_iter()
+            .map(|child| self.clone_tree(child, parent))
+            .collect()
+    }
+
+    fn clone_tree(&mut self, source: usize, parent: usize) -> usize {
+        let id = self.document.nodes.len();
+        let mut node = self.document.nodes[source].clone();
+        node.parent = Some(parent);
+        node.children = Vec::new();
+        self.document.nodes.push(node);
+        let children = self.clone_children(source, id);
+        self.document.nodes[id].children = children;
+        id
+    }
+
+    fn error_with_name(&self, el_name: &str, id: usize) -> ParseError {
+        let message = Resolution::msg_with_two_values(
+            &self.document.messages.resolution,
+            el_name,
+            self.document.line(id),
+            self.document.nodes[id].template.as_ref().unwrap(),
+        );
+        self.located(message, id)
+    }
+
+    fn located(&self, message: String, id: usize) -> ParseError {
+        ParseError::Resolution(self.document.details(message, id))
+    }
+}
+
+fn type_key(element_type: ElementType) -> &'static str {
+    match element_type {
+        ElementType::Section => "section",
+        ElementType::Field => "field",
+        ElementType::Fieldset => "fieldset",
+        ElementType::FieldsetEntry => "fieldset_entry",
+        ElementType::List => "list",
+        ElementType::ListItem => "list_item",
+        ElementType::Block => "block",
+        ElementType::Empty => "empty",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use document::{Element, Section};
+    use parser::parse;
+
+    fn find<'d>(elements: Vec<Element<'d>>, name: &str) -> Element<'d> {
+        elements
+            .into_iter()
+            .find(|element| element.name() == name)
+            .unwrap()
+    }
+
+    fn section<'d>(elements: Vec<Element<'d>>, name: &str) -> Section<'d> {
+        match find(elements, name) {
+            Element::Section(section) => section,
+            _ => panic!("'{}' is not a section", name),
+        }
+    }
+
+    fn value(elements: Vec<Element<'_>>, name: &str) -> Option<String> {
+        match find(elements, name) {
+            Element::Field(field) => field.value().map(str::to_string),
+            _ => panic!("'{}' is not a field", name),
+        }
+    }
+
+    fn names(section: &Section<'_>) -> Vec<String> {
+        section
+            .elements()
+            .iter()
+            .map(|element| element.name().to_string())
+            .collect()
+    }
+
+    fn items(elements: Vec<Element<'_>>, name: &str) -> Vec<String> {
+        match find(elements, name) {
+            Element::List(list) => list
+                .items()
+                .iter()
+                .map(|item| item.value().unwrap().to_string())
+                .collect(),
+            _ => panic!("'{}' is not a list", name),
+        }
+    }
+
+    macro_rules! template {
+        () => {
+            "# template\na: 1\nb: 2\nlist:\n- x\n## nested\nc: 3\nd: 4\n"
+        };
+    }
+
+    #[test]
+    fn copies_fields_lists_and_fieldsets_into_empty_elements() {
+        let input = "f: value\nl:\n- x\ns:\na = 1\ncf < f\ncl < l\ncs < s\n";
+        let document = parse(input, false).unwrap();
+        assert_eq!(value(document.elements(), "cf"), Some("value".to_string()));
+        assert_eq!(items(document.elements(), "cl"), vec!["x"]);
+        match find(document.elements(), "cs") {
+            Element::Fieldset(fieldset) => {
+                let entries = fieldset.entries();
+                assert_eq!(entries[0].name(), "a");
+                assert_eq!(entries[0].value(), Some("1"));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn a_shallow_copy_replaces_redefined_elements() {
+        let input = concat!(template!(), "# copy < template\nb: own\n## nested\nd: own\n");
+        let document = parse(input, false).unwrap();
+        let copy = section(document.elements(), "copy");
+        assert_eq!(names(&copy), vec!["a", "list", "b", "nested"]);
+        assert_eq!(value(copy.elements(), "b"), Some("own".to_string()));
+        let nested = section(copy.elements(), "nested");
+        assert_eq!(names(&nested), vec!["d"]);
+        // The template itself is left as it was.
+        let template = section(document.elements(), "template");
+        assert_eq!(names(&template), vec!["a", "b", "list", "nested"]);
+    }
+
+    #[test]
+    fn a_deep_copy_merges_redefined_sections_and_lists() {
+        let input = concat!(template!(), "# copy << template\nlist:\n- y\n## nested\nd: own\n");
+        let document = parse(input, false).unwrap();
+        let copy = section(document.elements(), "copy");
+        assert_eq!(names(&copy), vec!["a", "b", "list", "nested"]);
+        assert_eq!(items(copy.elements(), "list"), vec!["x", "y"]);
+        let nested = section(copy.elements(), "nested");
+        assert_eq!(names(&nested), vec!["c", "d"]);
+        assert_eq!(value(nested.elements(), "d"), Some("own".to_string()));
+    }
+
+    #[test]
+    fn copies_of_copies_are_resolved_in_order() {
+        let document = parse("# c < b\n# b < a\n# a\nx: 1\n", false).unwrap();
+        let c = section(document.elements(), "c");
+        assert_eq!(value(c.elements(), "x"), Some("1".to_string()));
+    }
+
+    #[test]
+    fn copying_into_another_type_fails() {
+        assert_eq!(
+            error("l:\n- x\nf < l\na = 1\n"),
+            "In line 3 a list is copied into a fieldset."
+        );
+    }
+
+    fn error(input: &str) -> String {
+        parse(input, false).unwrap_err().message().to_string()
+    }
+
+    #[test]
+    fn copying_an_element_into_itself_is_cyclic() {
+        assert_eq!(error("a < a\n"), "In line 1 'a' is copied into itself.");
+        assert_eq!(error("# a < a\n"), "In line 1 'a' is copied into itself.");
+        assert_eq!(
+            error("a < b\nb < a\n"),
+            "In line 1 'b' is copied into itself."
+        );
+    }
+
+    #[test]
+    fn a_missing_template_is_not_found() {
+        assert_eq!(
+            error("a < b\n"),
+            "In line 1 the element 'b' should be copied, but it was not found."
+        );
+    }
+}
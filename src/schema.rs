@@ -0,0 +1,747 @@
+use document::*;
+use error::{SchemaError, ValidationError};
+use loaders::{self, LoaderError};
+use messages::Validation;
+use parser::parse;
+use query::ItemCount;
+
+/// The expected shape of documents, itself read from an eno document:
+///
+/// ```text
+/// title: field
+/// published: optional field date
+/// tags: optional list min 1 max 5
+/// colors: fieldset color
+/// *: optional field
+///
+/// limits:
+/// memory = integer
+/// cpus = optional float
+///
+/// server: optional section
+///
+/// # server
+/// host: field
+/// port: field integer
+/// ```
+///
+/// Each field declares the element of its name by words:
+///
+/// - `field`, `list`, `fieldset`, `section` or `element` says what it is,
+///   where a field may also be a block or empty element, and `element` is
+///   anything.
+/// - `optional` lets it be missing, or a field or entry be without a value.
+/// - `repeated` lets it occur several times.
+/// - A loader name from `messages::Loaders`, like `integer`, `lat_lng` or
+///   `url`, checks the values of a field, the items of a list, or the entries
+///   of a fieldset.
+/// - `min <n>`, `max <n>` and `count <n>` bound the items of a list.
+///
+/// A section or fieldset in the schema declares what the one of its name
+/// holds. It is required and occurs once, unless a field of the same name
+/// declares it otherwise. The entries of a fieldset in the schema take an
+/// optional `optional` and loader. Without a declaration of its contents, a
+/// section or fieldset may hold anything.
+///
+/// Elements or entries that are not declared are errors, unless there is a
+/// declaration named `*`, which they are then checked against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schema {
+    root: Declarations,
+}
+
+/// The elements of a section, or the entries of a fieldset.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Declarations {
+    named: Vec<Declaration>,
+    /// The declaration named `*`, for elements that are not declared.
+    other: Option<Box<Declaration>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Declaration {
+    name: String,
+    line: usize,
+    kind: Kind,
+    optional: bool,
+    repeated: bool,
+    loader: Option<Loader>,
+    count: ItemCount,
+    /// What a section or fieldset holds, if the schema says.
+    contents: Option<Declarations>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Field,
+    List,
+    Fieldset,
+    Section,
+    Element,
+}
+
+/// The loaders a schema can name, by the names of their messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Loader {
+    Boolean,
+    Color,
+    Date,
+    Datetime,
+    Email,
+    Float,
+    Integer,
+    Json,
+    LatLng,
+    Url,
+}
+
+impl Schema {
+    /// Reads a schema from the eno document `input`.
+    pub fn parse(input: &str) -> Result<Schema, SchemaError> {
+        let document = parse(input, false).map_err(SchemaError::Parse)?;
+        Schema::from_document(&document)
+    }
+
+    /// Reads a schema from a document parsed with other options or messages.
+    pub fn from_document(document: &Document<'_>) -> Result<Schema, SchemaError> {
+        Ok(Schema {
+            root: Declarations::from_elements(document.elements())?,
+        })
+    }
+
+    /// Checks `document` against the schema, failing with every error found,
+    /// in the order of the document.
+    /// The errors carry the messages of `messages::Validation` and
+    /// `messages::Loaders` in the language of the document.
+    pub fn validate<'d>(&self, document: &'d Document<'d>) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let root = Section {
+            document,
+            id: DOCUMENT,
+        };
+        self.root.validate(root, &mut errors);
+        errors.sort_by_key(|error| error.range().start);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Declarations {
+    /// Reads the fields first, so that sections and fieldsets find the
+    /// declarations of their name wherever they are.
+    fn from_elements(elements: Vec<Element<'_>>) -> Result<Declarations, SchemaError> {
+        let mut declarations = Declarations::default();
+        for element in &elements {
+            match element {
+                Element::Field(field) => {
+                    let declaration =
+                        Declaration::parse(field.name(), field.line(), field.value(), false)?;
+                    if declarations.find(field.name()).is_some() {
+                        return Err(invalid(field.name(), field.line(), "is declared twice"));
+                    }
+                    declarations.insert(declaration);
+                }
+                Element::Section(_) | Element::Fieldset(_) => {}
+                element => {
+                    return Err(invalid(
+                        element.name(),
+                        element.line(),
+                        "is neither a field, a fieldset nor a section",
+                    ));
+                }
+            }
+        }
+        for element in elements {
+            let (kind, contents) = match element {
+                Element::Section(section) => (
+                    Kind::Section,
+                    Declarations::from_elements(section.elements())?,
+                ),
+                Element::Fieldset(fieldset) => {
+                    let mut entries = Declarations::default();
+                    for entry in fieldset.entries() {
+                        let declaration =
+                            Declaration::parse(entry.name(), entry.line(), entry.value(), true)?;
+                        entries.insert(declaration);
+                    }
+                    (Kind::Fieldset, entries)
+                }
+                _ => continue,
+            };
+            let (name, line) = (element.name(), element.line());
+            match declarations.find(name) {
+                Some(declaration) if declaration.kind != kind => {
+                    return Err(invalid(
+                        name,
+                        line,
+                        "is declared as another kind of element",
+                    ));
+                }
+                Some(declaration) if declaration.contents.is_some() => {
+                    return Err(invalid(name, line, "has its contents declared twice"));
+                }
+                Some(declaration) => declaration.contents = Some(contents),
+                None => declarations.insert(Declaration {
+                    contents: Some(contents),
+                    ..Declaration::new(name, line, kind)
+                }),
+            }
+        }
+        Ok(declarations)
+    }
+
+    fn find(&mut self, name: &str) -> Option<&mut Declaration> {
+        match name {
+            "*" => self.other.as_deref_mut(),
+            _ => self.named.iter_mut().find(|d| d.name == name),
+        }
+    }
+
+    fn insert(&mut self, declaration: Declaration) {
+        match declaration.name.as_str() {
+            "*" => self.other = Some(Box::new(declaration)),
+            _ => self.named.push(declaration),
+        }
+    }
+
+    fn validate<'d>(&self, section: Section<'d>, errors: &mut Vec<ValidationError>) {
+        for declaration in &self.named {
+            declaration.validate(section, &declaration.name, errors);
+        }
+        let document = section.document;
+        let mut checked: Vec<&str> = self.named.iter().map(|d| d.name.as_str()).collect();
+        for id in &document.nodes[section.id].children {
+            let name = document.nodes[*id].name.as_deref().unwrap();
+            if checked.contains(&name) {
+                continue;
+            }
+            match &self.other {
+                Some(other) => {
+                    other.validate(section, name, errors);
+                    checked.push(name);
+                }
+                None => errors.push(excess(document, name, *id)),
+            }
+        }
+    }
+
+    fn validate_entries<'d>(
+        &self,
+        fieldset: Fieldset<'d>,
+        loader: Option<Loader>,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        for declaration in &self.named {
+            let entry = if declaration.optional {
+                fieldset.optional_entry(&declaration.name)
+            } else {
+                match fieldset.entry(&declaration.name) {
+                    Ok(entry) => Some(entry),
+                    Err(error) => {
+                        errors.push(error);
+                        None
+                    }
+                }
+            };
+            if let Some(entry) = entry {
+                let loader = declaration.loader.or(loader);
+                errors.extend(check_entry(entry, loader, declaration.optional).err());
+            }
+        }
+        for entry in fieldset.entries() {
+            if self.named.iter().any(|d| d.name == entry.name()) {
+                continue;
+            }
+            match &self.other {
+                Some(other) => {
+                    let loader = other.loader.or(loader);
+                    errors.extend(check_entry(entry, loader, other.optional).err());
+                }
+                None => errors.push(excess(fieldset.document, entry.name(), entry.id)),
+            }
+        }
+    }
+}
+
+impl Declaration {
+    fn new(name: &str, line: usize, kind: Kind) -> Declaration {
+        Declaration {
+            name: name.to_string(),
+            line,
+            kind,
+            optional: false,
+            repeated: false,
+            loader: None,
+            count: ItemCount::new(),
+            contents: None,
+        }
+    }
+
+    /// Reads the words of a declaration, which for an `entry` of a fieldset
+    /// say nothing about the kind.
+    fn parse(
+        name: &str,
+        line: usize,
+        words: Option<&str>,
+        entry: bool,
+    ) -> Result<Declaration, SchemaError> {
+        let mut kind = if entry { Some(Kind::Field) } else { None };
+        let mut declaration = Declaration::new(name, line, Kind::Field);
+        let mut words = words.unwrap_or("").split_whitespace();
+        while let Some(word) = words.next() {
+            let found = match word {
+                "field" => Some(Kind::Field),
+                "list" => Some(Kind::List),
+                "fieldset" => Some(Kind::Fieldset),
+                "section" => Some(Kind::Section),
+                "element" => Some(Kind::Element),
+                _ => None,
+            };
+            if let Some(found) = found {
+                if kind.is_some() {
+                    return Err(invalid(name, line, &format!("cannot be a {}", word)));
+                }
+                kind = Some(found);
+                continue;
+            }
+            match word {
+                "optional" => declaration.optional = true,
+                "repeated" if !entry => declaration.repeated = true,
+                "min" | "max" | "count" => {
+                    let count = words
+                        .next()
+                        .and_then(|count| count.parse().ok())
+                        .ok_or_else(|| {
+                            invalid(name, line, &format!("needs a number after '{}'", word))
+                        })?;
+                    declaration.count = match word {
+                        "min" => declaration.count.min_count(count),
+                        "max" => declaration.count.max_count(count),
+                        _ => declaration.count.exact_count(count),
+                    };
+                }
+                _ => match Loader::from_name(word) {
+                    Some(loader) if declaration.loader.is_none() => {
+                        declaration.loader = Some(loader)
+                    }
+                    Some(_) => return Err(invalid(name, line, "names more than one loader")),
+                    None => {
+                        return Err(invalid(
+                            name,
+                            line,
+                            &format!("has the unknown word '{}'", word),
+                        ));
+                    }
+                },
+            }
+        }
+        declaration.kind = kind.ok_or_else(|| {
+            invalid(
+                name,
+                line,
+                "does not say whether it is a field, list, fieldset, section or element",
+            )
+        })?;
+        if declaration.count != ItemCount::new() && declaration.kind != Kind::List {
+            return Err(invalid(
+                name,
+                line,
+                "bounds the items of something else than a list",
+            ));
+        }
+        if declaration.loader.is_some() {
+            if let Kind::Section | Kind::Element = declaration.kind {
+                return Err(invalid(
+                    name,
+                    line,
+                    "names a loader for something without a value",
+                ));
+            }
+        }
+        Ok(declaration)
+    }
+
+    /// Checks the elements called `name` inside `section`, which is the name
+    /// of the declaration unless it is `*`.
+    fn validate<'d>(&self, section: Section<'d>, name: &str, errors: &mut Vec<ValidationError>) {
+        let result = match self.kind {
+            Kind::Field => self
+                .find(
+                    section,
+                    name,
+                    "missing_field",
+                    Section::field,
+                    Section::optional_field,
+                    Section::fields,
+                )
+                .map(|fields| {
+                    for field in fields {
+                        errors.extend(check_field(field, self.loader, self.optional).err());
+                    }
+                }),
+            Kind::List => self
+                .find(
+                    section,
+                    name,
+                    "missing_list",
+                    Section::list,
+                    Section::optional_list,
+                    Section::lists,
+                )
+                .map(|lists| {
+                    for list in lists {
+                        match list.items_with(self.count) {
+                            Ok(items) => {
+                                for item in items {
+                                    errors.extend(check_item(item, self.loader).err());
+                                }
+                            }
+                            Err(error) => errors.push(error),
+                        }
+                    }
+                }),
+            Kind::Fieldset => self
+                .find(
+                    section,
+                    name,
+                    "missing_fieldset",
+                    Section::fieldset,
+                    Section::optional_fieldset,
+                    Section::fieldsets,
+                )
+                .map(|fieldsets| {
+                    for fieldset in fieldsets {
+                        match &self.contents {
+                            Some(entries) => {
+                                entries.validate_entries(fieldset, self.loader, errors)
+                            }
+                            None => {
+                                for entry in fieldset.entries() {
+                                    errors.extend(check_entry(entry, self.loader, true).err());
+                                }
+                            }
+                        }
+                    }
+                }),
+            Kind::Section => self
+                .find(
+                    section,
+                    name,
+                    "missing_section",
+                    Section::section,
+                    Section::optional_section,
+                    Section::sections,
+                )
+                .map(|sections| {
+                    if let Some(contents) = &self.contents {
+                        for section in sections {
+                            contents.validate(section, errors);
+                        }
+                    }
+                }),
+            Kind::Element if self.repeated => {
+                let document = section.document;
+                let mut found = false;
+                for id in &document.nodes[section.id].children {
+                    if document.nodes[*id].name.as_deref() == Some(name) {
+                        document.nodes[*id].touched.set(true);
+                        found = true;
+                    }
+                }
+                if !found && !self.optional {
+                    Err(missing(section, "missing_element", name))
+                } else {
+                    Ok(())
+                }
+            }
+            Kind::Element if self.optional => section.optional_element(name).map(|_| ()),
+            Kind::Element => section.element(name).map(|_| ()),
+        };
+        errors.extend(result.err());
+    }
+
+    /// The elements called `name`, looked up with the query that fits how
+    /// many of them there may be.
+    fn find<'d, T>(
+        &self,
+        section: Section<'d>,
+        name: &str,
+        missing_key: &str,
+        one: fn(&Section<'d>, &str) -> Result<T, ValidationError>,
+        optional: fn(&Section<'d>, &str) -> Result<Option<T>, ValidationError>,
+        repeated: fn(&Section<'d>, &str) -> Result<Vec<T>, ValidationError>,
+    ) -> Result<Vec<T>, ValidationError> {
+        if self.repeated {
+            let found = repeated(&section, name)?;
+            if found.is_empty() && !self.optional {
+                return Err(missing(section, missing_key, name));
+            }
+            Ok(found)
+        } else if self.optional {
+            Ok(optional(&section, name)?.into_iter().collect())
+        } else {
+            Ok(vec![one(&section, name)?])
+        }
+    }
+}
+
+impl Loader {
+    fn from_name(name: &str) -> Option<Loader> {
+        match name {
+            "boolean" => Some(Loader::Boolean),
+            "color" => Some(Loader::Color),
+            "date" => Some(Loader::Date),
+            "datetime" => Some(Loader::Datetime),
+            "email" => Some(Loader::Email),
+            "float" => Some(Loader::Float),
+            "integer" => Some(Loader::Integer),
+            "json" => Some(Loader::Json),
+            "lat_lng" => Some(Loader::LatLng),
+            "url" => Some(Loader::Url),
+            _ => None,
+        }
+    }
+
+    fn check(self, value: &str) -> Result<(), LoaderError> {
+        match self {
+            Loader::Boolean => loaders::boolean(value).map(drop),
+            Loader::Color => loaders::color(value).map(drop),
+            Loader::Date => loaders::date(value).map(drop),
+            Loader::Datetime => loaders::datetime(value).map(drop),
+            Loader::Email => loaders::email(value).map(drop),
+            Loader::Float => loaders::float(value).map(drop),
+            Loader::Integer => loaders::integer(value).map(drop),
+            Loader::Json => loaders::json(value).map(drop),
+            Loader::LatLng => loaders::lat_lng(value).map(drop),
+            Loader::Url => loaders::url(value).map(drop),
+        }
+    }
+}
+
+/// Loads `value` with `loader`, if there is one, to check it.
+fn check(loader: Option<Loader>, value: &str) -> Result<(), LoaderError> {
+    match loader {
+        Some(loader) => loader.check(value),
+        None => Ok(()),
+    }
+}
+
+fn check_field(
+    field: Field<'_>,
+    loader: Option<Loader>,
+    optional: bool,
+) -> Result<(), ValidationError> {
+    if optional {
+        field.value_with(|value| check(loader, value)).map(drop)
+    } else {
+        field.required_value_with(|value| check(loader, value))
+    }
+}
+
+fn check_entry(
+    entry: FieldsetEntry<'_>,
+    loader: Option<Loader>,
+    optional: bool,
+) -> Result<(), ValidationError> {
+    if optional {
+        entry.value_with(|value| check(loader, value)).map(drop)
+    } else {
+        entry.required_value_with(|value| check(loader, value))
+    }
+}
+
+fn check_item(item: ListItem<'_>, loader: Option<Loader>) -> Result<(), ValidationError> {
+    item.required_value_with(|value| check(loader, value))
+}
+
+fn missing(section: Section<'_>, key: &str, name: &str) -> ValidationError {
+    let document = section.document;
+    let message = Validation::msg_with_one_value(&document.messages.validation, key, name);
+    ValidationError::Validation(document.details(message, section.id))
+}
+
+fn excess(document: &Document<'_>, name: &str, id: usize) -> ValidationError {
+    let message =
+        Validation::msg_with_one_value(&document.messages.validation, "excess_name", name);
+    ValidationError::Validation(document.details(message, id))
+}
+
+fn invalid(name: &str, line: usize, problem: &str) -> SchemaError {
+    SchemaError::InvalidDeclaration {
+        name: name.to_string(),
+        line,
+        problem: problem.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = "\
+title: field
+published: optional field date
+tags: optional list min 1 max 2
+colors: fieldset color
+note: repeated optional field
+
+limits:
+memory = integer
+cpus = optional float
+
+# server
+host: field
+port: field integer
+";
+
+    fn errors(schema: &str, input: &str) -> Vec<(usize, String)> {
+        let schema = Schema::parse(schema).unwrap();
+        let document = parse(input, false).unwrap();
+        match schema.validate(&document) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .iter()
+                .map(|error| (error.line(), error.message().to_string()))
+                .collect(),
+        }
+    }
+
+    fn schema_error(schema: &str) -> String {
+        Schema::parse(schema).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn accepts_documents_of_the_declared_shape() {
+        let input = "\
+title: Notes
+published: 2024-02-29
+tags:
+- a
+colors:
+primary = #fff
+note: one
+note: two
+limits:
+memory = 512
+# server
+host: localhost
+port: 80
+";
+        assert_eq!(errors(SCHEMA, input), Vec::new());
+        let minimal = "title:\n| Notes\ncolors:\nlimits:\nmemory = 1\n# server\nhost: a\nport: 1\n";
+        assert_eq!(errors(SCHEMA, minimal), Vec::new());
+    }
+
+    #[test]
+    fn reports_every_error_in_document_order() {
+        let input = "\
+title:
+published: yesterday
+tags:
+- a
+- b
+- c
+colors:
+primary = red
+limits:
+memory = 512
+cpus = many
+extra: 1
+# server
+port: http
+";
+        let found = errors(SCHEMA, input);
+        let lines: Vec<usize> = found.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![1, 2, 3, 8, 11, 12, 13, 14]);
+        assert_eq!(found[0].1, "The field 'title' must contain a value.");
+        assert_eq!(
+            found[2].1,
+            "The list 'tags' contains 3 items, but may only contain a maximum of 2 items."
+        );
+        assert_eq!(
+            found[5].1,
+            "An excess element named 'extra' was found, is it possibly a typo?"
+        );
+        assert_eq!(
+            found[6].1,
+            "The field 'host' is missing - in case it has been specified look for typos and also check for correct capitalization."
+        );
+        assert_eq!(
+            found[7].1,
+            "'port' must contain an integer, for instance '42' or '-21'."
+        );
+    }
+
+    #[test]
+    fn reports_missing_and_mistyped_elements() {
+        let found = errors("a: list\nb: section\n", "a: 1\n");
+        assert_eq!(
+            found,
+            vec![
+                (
+                    1,
+                    "Instead of the expected list 'a' a field with this name was found."
+                        .to_string()
+                ),
+                (
+                    1,
+                    "The section 'b' is missing - in case it has been specified look for typos and also check for correct capitalization."
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_undeclared_elements_against_the_wildcard() {
+        let schema = "id: field integer\n*: optional field integer\n";
+        assert_eq!(errors(schema, "id: 1\nx: 2\ny:\n"), Vec::new());
+        let found = errors(schema, "id: 1\nx: two\n");
+        assert_eq!(
+            found,
+            vec![(
+                2,
+                "'x' must contain an integer, for instance '42' or '-21'.".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_declarations() {
+        assert_eq!(
+            schema_error("a: integer\n"),
+            "The declaration 'a' in line 1 does not say whether it is a field, list, fieldset, section or element."
+        );
+        assert_eq!(
+            schema_error("a: field list\n"),
+            "The declaration 'a' in line 1 cannot be a list."
+        );
+        assert_eq!(
+            schema_error("a: field min 1\n"),
+            "The declaration 'a' in line 1 bounds the items of something else than a list."
+        );
+        assert_eq!(
+            schema_error("a: list min\n"),
+            "The declaration 'a' in line 1 needs a number after 'min'."
+        );
+        assert_eq!(
+            schema_error("a: field shiny\n"),
+            "The declaration 'a' in line 1 has the unknown word 'shiny'."
+        );
+        assert_eq!(
+            schema_error("a: field\na: list\n"),
+            "The declaration 'a' in line 2 is declared twice."
+        );
+        assert_eq!(
+            schema_error("a: field\n# a\n"),
+            "The declaration 'a' in line 2 is declared as another kind of element."
+        );
+        assert_eq!(
+            schema_error("a: section integer\n"),
+            "The declaration 'a' in line 1 names a loader for something without a value."
+        );
+    }
+}
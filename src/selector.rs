@@ -0,0 +1,372 @@
+use document::*;
+use error::SelectorError;
+use std::fmt;
+use std::str::FromStr;
+
+/// A path to elements of a document, written as the names on the way to them
+/// separated by `/`, like `states/active` or `cities expanded/Málaga`:
+///
+/// - A name steps into a section, or into a fieldset for one of its entries.
+/// - `*` stands for any name.
+/// - Hashes, as in `# section` or `## subsection`, match only sections at
+///   that depth.
+/// - `[n]` after a name picks the item of a list, as in `# section/list[2]`.
+///   Items count from zero or one, like the lines of the document.
+/// - A name between backticks, as in `` `a/b`/c `` or `` `*` ``, is taken as
+///   it is, for names with `/`, `[` or surrounding `#`s and spaces. The name
+///   can hold backticks if more of them surround it, like in the document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selector {
+    segments: Vec<Segment>,
+}
+
+/// One step of a selector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Segment {
+    /// The name to match, or `None` for `*`.
+    name: Option<String>,
+    /// The depth of the sections to match, for a segment with hashes.
+    depth: Option<usize>,
+    index: Option<usize>,
+}
+
+/// An element, fieldset entry or list item picked by a selector.
+#[derive(Clone, Copy, Debug)]
+pub enum Selected<'d> {
+    Element(Element<'d>),
+    Entry(FieldsetEntry<'d>),
+    Item(ListItem<'d>),
+}
+
+impl Selector {
+    pub fn parse(selector: &str) -> Result<Selector, SelectorError> {
+        let mut segments = Vec::new();
+        let mut rest = selector;
+        loop {
+            let start = selector.len() - rest.len();
+            let (segment, after) = Segment::parse(rest, start)?;
+            segments.push(segment);
+            match after.chars().next() {
+                None => break,
+                Some('/') => rest = &after[1..],
+                Some(_) => {
+                    return Err(SelectorError::Unexpected(selector.len() - after.len()));
+                }
+            }
+        }
+        Ok(Selector { segments })
+    }
+
+    /// The elements of `document` that the selector reaches, in document order.
+    pub fn select<'d>(&self, document: &'d Document<'d>) -> Vec<Selected<'d>> {
+        self.select_from(document, document.elements())
+    }
+
+    /// Like `select`, starting from the elements of `section`.
+    pub fn select_in<'d>(&self, section: &Section<'d>) -> Vec<Selected<'d>> {
+        self.select_from(section.document, section.elements())
+    }
+
+    fn select_from<'d>(
+        &self,
+        document: &'d Document<'d>,
+        elements: Vec<Element<'d>>,
+    ) -> Vec<Selected<'d>> {
+        let mut selected: Vec<Selected<'d>> = elements.into_iter().map(Selected::Element).collect();
+        for (number, segment) in self.segments.iter().enumerate() {
+            if number > 0 {
+                selected = selected.iter().flat_map(Selected::children).collect();
+            }
+            selected = selected
+                .into_iter()
+                .filter(|candidate| segment.matches(candidate))
+                .filter_map(|candidate| match segment.index {
+                    Some(index) => candidate.item(index.checked_sub(document.indexing)?),
+                    None => Some(candidate),
+                })
+                .collect();
+        }
+        selected
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(selector: &str) -> Result<Selector, SelectorError> {
+        Selector::parse(selector)
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (number, segment) in self.segments.iter().enumerate() {
+            if number > 0 {
+                f.write_str("/")?;
+            }
+            if let Some(depth) = segment.depth {
+                write!(f, "{} ", "#".repeat(depth))?;
+            }
+            match &segment.name {
+                Some(name) if plain(name) => f.write_str(name)?,
+                Some(name) => {
+                    let longest = name.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+                    let quotes = "`".repeat(longest + 1);
+                    write!(f, "{} {} {}", quotes, name, quotes)?;
+                }
+                None => f.write_str("*")?,
+            }
+            if let Some(index) = segment.index {
+                write!(f, "[{}]", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Segment {
+    /// Reads the segment at the start of `input`, which begins at byte
+    /// `start` of the selector, returning it and what follows it.
+    fn parse(input: &str, start: usize) -> Result<(Segment, &str), SelectorError> {
+        let offset = |rest: &str| start + input.len() - rest.len();
+        let hashes = input.len() - input.trim_start_matches('#').len();
+        let depth = if hashes > 0 { Some(hashes) } else { None };
+        let rest = input[hashes..].trim_start();
+        let (name, rest) = if rest.starts_with('`') {
+            let quotes = &rest[..rest.len() - rest.trim_start_matches('`').len()];
+            let content = &rest[quotes.len()..];
+            let end = content
+                .match_indices(quotes)
+                .map(|(index, _)| index)
+                .find(|&index| !content[index + quotes.len()..].starts_with('`'))
+                .ok_or_else(|| SelectorError::UnterminatedEscape(offset(rest)))?;
+            let name = content[..end].trim();
+            if name.is_empty() {
+                return Err(SelectorError::EmptyName(offset(rest)));
+            }
+            (Some(name.to_string()), &content[end + quotes.len()..])
+        } else {
+            let end = rest.find(&['/', '['][..]).unwrap_or(rest.len());
+            match rest[..end].trim() {
+                "" => return Err(SelectorError::EmptyName(offset(rest))),
+                "*" => (None, &rest[end..]),
+                name => (Some(name.to_string()), &rest[end..]),
+            }
+        };
+        let rest = rest.trim_start();
+        let (index, rest) = if let Some(inner) = rest.strip_prefix('[') {
+            let end = inner
+                .find(']')
+                .ok_or_else(|| SelectorError::InvalidIndex(offset(rest)))?;
+            let index = inner[..end]
+                .trim()
+                .parse()
+                .map_err(|_| SelectorError::InvalidIndex(offset(rest)))?;
+            (Some(index), inner[end + 1..].trim_start())
+        } else {
+            (None, rest)
+        };
+        Ok((Segment { name, depth, index }, rest))
+    }
+
+    fn matches(&self, candidate: &Selected<'_>) -> bool {
+        if let Some(depth) = self.depth {
+            match candidate {
+                Selected::Element(Element::Section(section)) if section.depth() == depth => {}
+                _ => return false,
+            }
+        }
+        match (&self.name, candidate.name()) {
+            (None, Some(_)) => true,
+            (Some(name), Some(found)) => name == found,
+            (_, None) => false,
+        }
+    }
+}
+
+/// Whether `name` reads back as itself without backticks.
+fn plain(name: &str) -> bool {
+    name != "*"
+        && !name.starts_with('#')
+        && !name.starts_with('`')
+        && !name.contains(&['/', '['][..])
+        && name.trim() == name
+}
+
+impl<'d> Selected<'d> {
+    /// The name, which list items do not have.
+    pub fn name(&self) -> Option<&'d str> {
+        match self {
+            Selected::Element(element) => Some(element.name()),
+            Selected::Entry(entry) => Some(entry.name()),
+            Selected::Item(_) => None,
+        }
+    }
+
+    pub fn element_type(&self) -> ElementType {
+        match self {
+            Selected::Element(element) => element.element_type(),
+            Selected::Entry(_) => ElementType::FieldsetEntry,
+            Selected::Item(_) => ElementType::ListItem,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        match self {
+            Selected::Element(element) => element.line(),
+            Selected::Entry(entry) => entry.line(),
+            Selected::Item(item) => item.line(),
+        }
+    }
+
+    /// The value of a field, block, fieldset entry or list item.
+    pub fn value(&self) -> Option<&'d str> {
+        match self {
+            Selected::Element(Element::Field(field)) => field.value(),
+            Selected::Element(Element::Block(block)) => block.value(),
+            Selected::Entry(entry) => entry.value(),
+            Selected::Item(item) => item.value(),
+            Selected::Element(_) => None,
+        }
+    }
+
+    /// The elements of a section or the entries of a fieldset, which the
+    /// next segment of a selector picks from.
+    fn children(&self) -> Vec<Selected<'d>> {
+        match self {
+            Selected::Element(Element::Section(section)) => section
+                .elements()
+                .into_iter()
+                .map(Selected::Element)
+                .collect(),
+            Selected::Element(Element::Fieldset(fieldset)) => fieldset
+                .entries()
+                .into_iter()
+                .map(Selected::Entry)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn item(&self, index: usize) -> Option<Selected<'d>> {
+        match self {
+            Selected::Element(Element::List(list)) => {
+                list.items().get(index).map(|item| Selected::Item(*item))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Selecting from the document starts at its top level.
+impl<'a> Document<'a> {
+    /// The elements at `selector`, see `Selector`.
+    pub fn select(&self, selector: &str) -> Result<Vec<Selected<'_>>, SelectorError> {
+        Ok(Selector::parse(selector)?.select(self))
+    }
+}
+
+impl<'d> Section<'d> {
+    /// The elements at `selector`, starting from those of the section.
+    pub fn select(&self, selector: &str) -> Result<Vec<Selected<'d>>, SelectorError> {
+        Ok(Selector::parse(selector)?.select_in(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse;
+
+    const INPUT: &str = "\
+email: root@example.com
+# states
+active: yes
+## active
+email: admin@example.com
+# `cities/expanded`
+list:
+- Málaga
+- Wien
+fieldset:
+a = 1
+b = 2
+";
+
+    fn values(document: &Document<'_>, selector: &str) -> Vec<String> {
+        document
+            .select(selector)
+            .unwrap()
+            .iter()
+            .map(|found| found.value().unwrap_or("-").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn parses_and_displays_selectors() {
+        let selectors = [
+            "a/b",
+            "*/email",
+            "## active/email",
+            "list[2]",
+            "a`b/c",
+            "` x/y `/c",
+            "`` `a` ``",
+        ];
+        for selector in &selectors {
+            assert_eq!(Selector::parse(selector).unwrap().to_string(), *selector);
+        }
+        let selector: Selector = "## s / `x/y` [ 1 ]".parse().unwrap();
+        assert_eq!(selector.to_string(), "## s/` x/y `[1]");
+    }
+
+    #[test]
+    fn reports_malformed_selectors() {
+        assert_eq!(Selector::parse(""), Err(SelectorError::EmptyName(0)));
+        assert_eq!(Selector::parse("a//b"), Err(SelectorError::EmptyName(2)));
+        assert_eq!(
+            Selector::parse("a/`b"),
+            Err(SelectorError::UnterminatedEscape(2))
+        );
+        assert_eq!(Selector::parse("a[x]"), Err(SelectorError::InvalidIndex(1)));
+        assert_eq!(Selector::parse("a[1"), Err(SelectorError::InvalidIndex(1)));
+        assert_eq!(Selector::parse("a[1]b"), Err(SelectorError::Unexpected(4)));
+    }
+
+    #[test]
+    fn selects_by_name_wildcard_and_depth() {
+        let document = parse(INPUT, false).unwrap();
+        assert_eq!(values(&document, "email"), vec!["root@example.com"]);
+        assert_eq!(values(&document, "states/active"), vec!["yes", "-"]);
+        assert_eq!(
+            values(&document, "states/## active/email"),
+            vec!["admin@example.com"]
+        );
+        assert_eq!(values(&document, "*/*/email"), vec!["admin@example.com"]);
+        assert_eq!(values(&document, "## active"), Vec::<String>::new());
+        assert!(document.select("states/missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn selects_items_entries_and_escaped_names() {
+        let document = parse(INPUT, false).unwrap();
+        assert_eq!(
+            values(&document, "`cities/expanded`/list[1]"),
+            vec!["Málaga"]
+        );
+        assert_eq!(
+            values(&document, "`cities/expanded`/list[3]"),
+            Vec::<String>::new()
+        );
+        assert_eq!(values(&document, "*/fieldset/b"), vec!["2"]);
+        let found = document.select("*/fieldset/*").unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].element_type(), ElementType::FieldsetEntry);
+        assert_eq!(found[0].line(), 11);
+
+        let zero = parse(INPUT, true).unwrap();
+        assert_eq!(values(&zero, "*/list[1]"), vec!["Wien"]);
+        let section = zero.section("states").unwrap();
+        assert_eq!(section.select("active").unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,588 @@
+use error::SerializeError;
+use serde::ser::{self, Serialize};
+use serializer::{fits_field, Writer};
+
+/// Writes `value` as a document. Scalars become fields, or blocks if they
+/// span several lines, and `None` and `()` become empty elements. Sequences
+/// of scalars become lists and sequences of structs or maps become sections
+/// of the same name. Structs become sections, as do maps unless all their
+/// values fit a fieldset entry. Names are escaped where the grammar needs it,
+/// but cannot span several lines, and list items cannot have lines with
+/// surrounding whitespace, since no element holds them. Empty strings are written as elements without a value, which `from_str`
+/// reads back as the empty string, though as `None` for an `Option`.
+pub fn to_string<T: ?Sized + Serialize>(value: &T) -> Result<String, SerializeError> {
+    match value.serialize(NodeSerializer)? {
+        Node::Map(entries, _) => {
+            let mut writer = Writer::new();
+            write_entries(&mut writer, &entries, 1)?;
+            Ok(writer.finish())
+        }
+        _ => Err(SerializeError::TopLevel),
+    }
+}
+
+/// A serialized value, before it is laid out as elements.
+enum Node {
+    Value(String),
+    Empty,
+    Seq(Vec<Node>),
+    /// The entries, and whether they come from a struct.
+    Map(Vec<(String, Node)>, bool),
+}
+
+impl Node {
+    fn is_scalar(&self) -> bool {
+        matches!(self, Node::Value(_) | Node::Empty)
+    }
+
+    /// Whether the node can be written as a list item or fieldset entry.
+    fn fits_entry(&self) -> bool {
+        match self {
+            Node::Value(value) => fits_field(value),
+            Node::Empty => true,
+            _ => false,
+        }
+    }
+}
+
+/// Writes the entries that are not sections first, since every element
+/// after a section heading belongs to the section.
+fn write_entries(
+    writer: &mut Writer,
+    entries: &[(String, Node)],
+    depth: usize,
+) -> Result<(), SerializeError> {
+    let mut sections = Vec::new();
+    for (name, node) in entries {
+        match node {
+            Node::Value(value) if value.contains('\n') => writer.block(name, Some(value)),
+            Node::Value(value) => writer.field(name, Some(value)),
+            Node::Empty => writer.empty(name),
+            Node::Seq(items) if items.is_empty() => writer.empty(name),
+            Node::Seq(items) if items.iter().all(Node::is_scalar) => {
+                if !items.iter().all(Node::fits_entry) {
+                    return Err(SerializeError::Unrepresentable(name.clone()));
+                }
+                writer.empty(name);
+                for item in items {
+                    match item {
+                        Node::Value(value) => writer.item(Some(value)),
+                        _ => writer.item(None),
+                    }
+                }
+            }
+            Node::Seq(items) => {
+                for item in items {
+                    match item {
+                        Node::Map(entries, _) => sections.push((name, entries)),
+                        _ => return Err(SerializeError::Sequence(name.clone())),
+                    }
+                }
+            }
+            Node::Map(map, false) if map.is_empty() => writer.empty(name),
+            Node::Map(map, false) if map.iter().all(|(_, value)| value.fits_entry()) => {
+                writer.empty(name);
+                for (key, value) in map {
+                    match value {
+                        Node::Value(value) => writer.entry(key, Some(value)),
+                        _ => writer.entry(key, None),
+                    }
+                }
+            }
+            Node::Map(map, _) => sections.push((name, map)),
+        }
+    }
+    for (name, entries) in sections {
+        writer.section(depth, name);
+        write_entries(writer, entries, depth + 1)?;
+    }
+    Ok(())
+}
+
+struct NodeSerializer;
+
+struct SeqSerializer {
+    items: Vec<Node>,
+    /// The variant the sequence is wrapped in, for tuple variants.
+    variant: Option<&'static str>,
+}
+
+struct MapSerializer {
+    entries: Vec<(String, Node)>,
+    key: Option<String>,
+    is_struct: bool,
+    /// The variant the map is wrapped in, for struct variants.
+    variant: Option<&'static str>,
+}
+
+/// `name` if it can be the name of an element.
+fn element_name(name: String) -> Result<String, SerializeError> {
+    if name.contains('\n') {
+        Err(SerializeError::Key)
+    } else {
+        Ok(name)
+    }
+}
+
+/// Wraps the content of a newtype, tuple or struct variant in a struct with
+/// the variant as its one field, the way serde_json does.
+fn variant(variant: &'static str, content: Node) -> Node {
+    Node::Map(vec![(variant.to_string(), content)], true)
+}
+
+macro_rules! serialize_display {
+    ($($method:ident($ty:ty))*) => {$(
+        fn $method(self, value: $ty) -> Result<Node, SerializeError> {
+            Ok(Node::Value(value.to_string()))
+        }
+    )*};
+}
+
+impl ser::Serializer for NodeSerializer {
+    type Ok = Node;
+    type Error = SerializeError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    serialize_display! {
+        serialize_bool(bool)
+        serialize_i8(i8)
+        serialize_i16(i16)
+        serialize_i32(i32)
+        serialize_i64(i64)
+        serialize_i128(i128)
+        serialize_u8(u8)
+        serialize_u16(u16)
+        serialize_u32(u32)
+        serialize_u64(u64)
+        serialize_u128(u128)
+        serialize_f32(f32)
+        serialize_f64(f64)
+        serialize_char(char)
+        serialize_str(&str)
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Node, SerializeError> {
+        Ok(Node::Seq(
+            value
+                .iter()
+                .map(|byte| Node::Value(byte.to_string()))
+                .collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Node, SerializeError> {
+        Ok(Node::Empty)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Node, SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Node, SerializeError> {
+        Ok(Node::Empty)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Node, SerializeError> {
+        Ok(Node::Empty)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Node, SerializeError> {
+        Ok(Node::Value(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Node, SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Node, SerializeError> {
+        Ok(variant(name, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, SerializeError> {
+        Ok(SeqSerializer {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, SerializeError> {
+        Ok(SeqSerializer {
+            items: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer, SerializeError> {
+        Ok(MapSerializer {
+            entries: Vec::new(),
+            key: None,
+            is_struct: false,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer, SerializeError> {
+        Ok(MapSerializer {
+            entries: Vec::new(),
+            key: None,
+            is_struct: true,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer, SerializeError> {
+        Ok(MapSerializer {
+            entries: Vec::new(),
+            key: None,
+            is_struct: true,
+            variant: Some(variant),
+        })
+    }
+}
+
+impl SeqSerializer {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.items.push(value.serialize(NodeSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Node, SerializeError> {
+        let seq = Node::Seq(self.items);
+        Ok(match self.variant {
+            Some(name) => variant(name, seq),
+            None => seq,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, SerializeError> {
+        self.finish()
+    }
+}
+
+impl MapSerializer {
+    fn finish(self) -> Result<Node, SerializeError> {
+        let map = Node::Map(self.entries, self.is_struct);
+        Ok(match self.variant {
+            Some(name) => variant(name, map),
+            None => map,
+        })
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), SerializeError> {
+        match key.serialize(NodeSerializer)? {
+            Node::Value(key) => {
+                self.key = Some(element_name(key)?);
+                Ok(())
+            }
+            _ => Err(SerializeError::Key),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerializeError> {
+        let key = self.key.take().unwrap();
+        self.entries.push((key, value.serialize(NodeSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Node, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.entries.push((
+            element_name(key.to_string())?,
+            value.serialize(NodeSerializer)?,
+        ));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Node, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.entries.push((
+            element_name(key.to_string())?,
+            value.serialize(NodeSerializer)?,
+        ));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Node, SerializeError> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use de::from_str;
+    use serde_json::{self, json};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn empty_strings_round_trip() {
+        let mut value = BTreeMap::new();
+        value.insert("note".to_string(), String::new());
+        value.insert("title".to_string(), "Notes".to_string());
+        let document = to_string(&value).unwrap();
+        assert_eq!(
+            from_str::<BTreeMap<String, String>>(&document).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn empty_items_and_entries_round_trip() {
+        let mut entries = BTreeMap::new();
+        entries.insert("a".to_string(), String::new());
+        entries.insert("b".to_string(), "b".to_string());
+        let mut value = BTreeMap::new();
+        value.insert("entries".to_string(), entries);
+        let document = to_string(&value).unwrap();
+        assert_eq!(
+            from_str::<BTreeMap<String, BTreeMap<String, String>>>(&document).unwrap(),
+            value
+        );
+
+        let mut value = BTreeMap::new();
+        value.insert("items", vec!["", "x", ""]);
+        let document = to_string(&value).unwrap();
+        let read: BTreeMap<String, Vec<String>> = from_str(&document).unwrap();
+        assert_eq!(read["items"], vec!["", "x", ""]);
+    }
+
+    #[test]
+    fn lays_out_values_as_elements() {
+        let value = json!({
+            "title": "Notes",
+            "body": "two\nlines",
+            "nothing": null,
+            "tags": ["a", "b"],
+            "colors": {"primary": "red", "secondary": "blue"},
+            "server": {"host": "localhost", "ports": {"http": 80}},
+            "users": [{"name": "jane"}, {"name": "joe"}],
+        });
+        assert_eq!(
+            to_string(&value).unwrap(),
+            "-- body\ntwo\nlines\n-- body\ncolors:\nprimary = red\nsecondary = blue\nnothing:\n\
+             tags:\n- a\n- b\ntitle: Notes\n\n# server\nhost: localhost\nports:\nhttp = 80\n\n\
+             # users\nname: jane\n\n# users\nname: joe\n"
+        );
+    }
+
+    #[test]
+    fn round_trips_through_the_deserializer() {
+        let value = json!({
+            "title": "Notes",
+            "body": "  indented\nlines",
+            "tags": ["a", "b"],
+            "colors": {"primary": "red"},
+            "server": {"host": "localhost", "inner": {"deep": "yes"}},
+            "users": [{"name": "jane"}, {"name": "joe"}],
+            "escaped: name": "value",
+        });
+        let document = to_string(&value).unwrap();
+        let read: serde_json::Value = from_str(&document).unwrap();
+        assert_eq!(read, value);
+
+        let mut numbers = BTreeMap::new();
+        numbers.insert("answer".to_string(), 42i64);
+        numbers.insert("negative".to_string(), -21);
+        let read: BTreeMap<String, i64> = from_str(&to_string(&numbers).unwrap()).unwrap();
+        assert_eq!(read, numbers);
+
+        let mut options = BTreeMap::new();
+        options.insert("some", Some(1.5f64));
+        options.insert("none", None);
+        let read: BTreeMap<String, Option<f64>> = from_str(&to_string(&options).unwrap()).unwrap();
+        assert_eq!(read["some"], Some(1.5));
+        assert_eq!(read["none"], None);
+    }
+
+    #[test]
+    fn rejects_what_a_document_cannot_hold() {
+        match to_string(&vec![1, 2]) {
+            Err(SerializeError::TopLevel) => {}
+            result => panic!("{:?}", result),
+        }
+        match to_string(&json!({"mixed": [{"a": "1"}, "b"]})) {
+            Err(SerializeError::Sequence(name)) => assert_eq!(name, "mixed"),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn items_round_trip_or_are_refused() {
+        let mut value = BTreeMap::new();
+        value.insert("items", vec!["two\nlines", "x"]);
+        let read: BTreeMap<String, Vec<String>> = from_str(&to_string(&value).unwrap()).unwrap();
+        assert_eq!(read["items"], vec!["two\nlines", "x"]);
+        for item in &[" padded", "a\n b", "\nleading"] {
+            value.insert("items", vec![item]);
+            match to_string(&value) {
+                Err(SerializeError::Unrepresentable(name)) => assert_eq!(name, "items"),
+                result => panic!("{:?} for {:?}", result, item),
+            }
+        }
+    }
+
+    #[test]
+    fn maps_with_values_an_entry_cannot_hold_become_sections() {
+        let value = json!({"map": {"a": " padded", "b": "a\n b"}});
+        let document = to_string(&value).unwrap();
+        assert!(document.starts_with("# map\n"), "{}", document);
+        let read: serde_json::Value = from_str(&document).unwrap();
+        assert_eq!(read, value);
+    }
+
+    struct Field(&'static str);
+
+    impl Serialize for Field {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeStruct;
+            let mut state = serializer.serialize_struct("Field", 1)?;
+            state.serialize_field(self.0, "value")?;
+            state.end()
+        }
+    }
+
+    #[test]
+    fn refuses_names_with_line_breaks() {
+        match to_string(&json!({"a\nb": 1})) {
+            Err(SerializeError::Key) => {}
+            result => panic!("{:?}", result),
+        }
+        match to_string(&Field("a\nb")) {
+            Err(SerializeError::Key) => {}
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(to_string(&Field("a: b")).unwrap(), "` a: b `: value\n");
+    }
+}
@@ -0,0 +1,246 @@
+use document::*;
+use std::cmp;
+
+/// Writes the document as eno text, with copies written out as resolved and
+/// without their `<` operators. Within a section, the elements that are not
+/// sections come first, since everything below a section heading belongs to
+/// that section.
+pub fn serialize(document: &Document<'_>) -> String {
+    let mut writer = Writer::new();
+    write_elements(&mut writer, &document.elements(), 1);
+    writer.finish()
+}
+
+fn write_elements(writer: &mut Writer, elements: &[Element<'_>], depth: usize) {
+    for element in elements {
+        match element {
+            Element::Section(_) => {}
+            Element::Field(field) => writer.field(field.name(), field.value()),
+            Element::Block(block) => writer.block(block.name(), block.value()),
+            Element::Empty(empty) => writer.empty(empty.name()),
+            Element::List(list) => {
+                writer.empty(list.name());
+                for item in list.items() {
+                    writer.item(item.value());
+                }
+            }
+            Element::Fieldset(fieldset) => {
+                writer.empty(fieldset.name());
+                for entry in fieldset.entries() {
+                    writer.entry(entry.name(), entry.value());
+                }
+            }
+        }
+    }
+    for element in elements {
+        if let Element::Section(section) = element {
+            writer.section(depth, section.name());
+            write_elements(writer, &section.elements(), depth + 1);
+        }
+    }
+}
+
+/// Builds eno text line by line, escaping names and choosing the operators
+/// that let each value be read back unchanged.
+pub(crate) struct Writer {
+    out: String,
+}
+
+impl Writer {
+    pub(crate) fn new() -> Writer {
+        Writer { out: String::new() }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.out
+    }
+
+    /// A field, or a block when the value has a line that a field would
+    /// trim, like one with leading whitespace or an empty first line.
+    pub(crate) fn field(&mut self, name: &str, value: Option<&str>) {
+        match value {
+            Some(value) if !fits_field(value) => self.block(name, Some(value)),
+            Some(value) => {
+                self.name(name);
+                self.out.push(':');
+                self.value(value);
+            }
+            None => self.empty(name),
+        }
+    }
+
+    pub(crate) fn empty(&mut self, name: &str) {
+        self.name(name);
+        self.out.push_str(":\n");
+    }
+
+    pub(crate) fn item(&mut self, value: Option<&str>) {
+        self.out.push('-');
+        self.value(value.unwrap_or(""));
+    }
+
+    pub(crate) fn entry(&mut self, name: &str, value: Option<&str>) {
+        self.name(name);
+        self.out.push_str(" =");
+        self.value(value.unwrap_or(""));
+    }
+
+    /// A block, terminated by the fewest dashes that no line of the content
+    /// could be mistaken for.
+    pub(crate) fn block(&mut self, name: &str, value: Option<&str>) {
+        let operator = "-".repeat(block_dashes(name, value.unwrap_or(""), 2));
+        self.out.push_str(&format!("{} {}\n", operator, name));
+        if let Some(value) = value {
+            self.out.push_str(value);
+            self.out.push('\n');
+        }
+        self.out.push_str(&format!("{} {}\n", operator, name));
+    }
+
+    /// A section heading with `depth` hashes, set apart by an empty line.
+    pub(crate) fn section(&mut self, depth: usize, name: &str) {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out.push_str(&"#".repeat(depth));
+        self.out.push(' ');
+        if section_name_unescaped(name) {
+            self.out.push_str(name);
+        } else {
+            self.out.push_str(&escape(name));
+        }
+        self.out.push('\n');
+    }
+
+    fn name(&mut self, name: &str) {
+        if name_unescaped(name) {
+            self.out.push_str(name);
+        } else {
+            self.out.push_str(&escape(name));
+        }
+    }
+
+    /// The value after its operator, continued with `|` on every further line.
+    fn value(&mut self, value: &str) {
+        for (number, line) in value.split('\n').enumerate() {
+            if number > 0 {
+                self.out.push('|');
+            }
+            if !line.is_empty() {
+                self.out.push(' ');
+                self.out.push_str(line);
+            }
+            self.out.push('\n');
+        }
+    }
+}
+
+/// Whether `value` reads back unchanged from a field continued with `|`.
+pub(crate) fn fits_field(value: &str) -> bool {
+    !value.starts_with('\n') && value.split('\n').all(|line| line.trim() == line)
+}
+
+/// Whether `name` can be written as is, see `NAME_UNESCAPED` in
+/// `grammar_regex`. Names with backticks are escaped even where the grammar
+/// would not need it, so they never read as escape operators.
+pub(crate) fn name_unescaped(name: &str) -> bool {
+    !name.starts_with(|c| ">#-\\|".contains(c))
+        && !name.contains(|c| ":=<`\n".contains(c))
+        && !name.is_empty()
+        && name.trim() == name
+}
+
+/// Whether `name` can be written as is after the hashes of a section, see
+/// `SECTION_NAME_UNESCAPED`.
+fn section_name_unescaped(name: &str) -> bool {
+    !name.contains(|c| "<`\n".contains(c)) && !name.is_empty() && name.trim() == name
+}
+
+/// The number of dashes for a block holding `value`: `preferred` unless a
+/// line of the value would terminate the block early, else the fewest that
+/// none would.
+pub(crate) fn block_dashes(name: &str, value: &str, preferred: usize) -> usize {
+    let taken: Vec<usize> = value
+        .split('\n')
+        .map(str::trim)
+        .filter_map(|line| {
+            let count = line.len() - line.trim_start_matches('-').len();
+            if line[count..].trim_start() == name {
+                Some(count)
+            } else {
+                None
+            }
+        })
+        .collect();
+    if !taken.contains(&preferred) {
+        return preferred;
+    }
+    (2..).find(|dashes| !taken.contains(dashes)).unwrap()
+}
+
+/// `name` between more backticks than it has in a row.
+fn escape(name: &str) -> String {
+    let longest = name.split(|c| c != '`').map(str::len).fold(0, cmp::max);
+    let quotes = "`".repeat(longest + 1);
+    format!("{} {} {}", quotes, name, quotes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse;
+
+    /// Serializes `input`, checking that the output reads back as itself.
+    fn round_trip(input: &str) -> String {
+        let output = serialize(&parse(input, false).unwrap());
+        let again = serialize(&parse(&output, false).unwrap());
+        assert_eq!(again, output, "reading back {:?}", output);
+        output
+    }
+
+    #[test]
+    fn writes_every_kind_of_element() {
+        let input =
+            "f:   v\nl:\n-   a\n-\ne:\nfs:\nx=1\ny =\n-- b\ntext\n-- b\n# s\nc: 1\n## t\nd: 2\n";
+        let output =
+            "f: v\nl:\n- a\n-\ne:\nfs:\nx = 1\ny =\n-- b\ntext\n-- b\n\n# s\nc: 1\n\n## t\nd: 2\n";
+        assert_eq!(round_trip(input), output);
+    }
+
+    #[test]
+    fn writes_copies_resolved_with_sections_last() {
+        let input = "# t\n## n\nx: 1\n# s < t\ny: 2\n";
+        let output = "# t\n\n## n\nx: 1\n\n# s\ny: 2\n\n## n\nx: 1\n";
+        assert_eq!(round_trip(input), output);
+    }
+
+    #[test]
+    fn escapes_names_and_keeps_values_unchanged() {
+        let input =
+            "`a: b`: 1\n`` `x` ``: 2\n-- text\n  indented\n-- text\n--- text\n-- text\n--- text\n";
+        let output = round_trip(input);
+        assert_eq!(
+            output,
+            "` a: b `: 1\n`` `x` ``: 2\n-- text\n  indented\n-- text\n--- text\n-- text\n--- text\n"
+        );
+        let document = parse(&output, false).unwrap();
+        assert_eq!(document.field("a: b").unwrap().value(), Some("1"));
+        assert_eq!(document.field("`x`").unwrap().value(), Some("2"));
+    }
+
+    #[test]
+    fn writes_values_a_field_would_trim_as_blocks() {
+        let mut writer = Writer::new();
+        writer.field("f", Some(" padded"));
+        writer.field("g", Some("two\nlines"));
+        assert_eq!(writer.finish(), "-- f\n padded\n-- f\ng: two\n| lines\n");
+    }
+
+    #[test]
+    fn chooses_dashes_no_line_of_the_block_uses() {
+        assert_eq!(block_dashes("b", "text", 2), 2);
+        assert_eq!(block_dashes("b", "-- b", 2), 3);
+        assert_eq!(block_dashes("b", "-- b\n--- b", 2), 4);
+        assert_eq!(block_dashes("b", "-- c", 2), 2);
+    }
+}
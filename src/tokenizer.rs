@@ -1,89 +1,196 @@
-use env_logger;
+use error::{ErrorDetails, ParseError, TokenizeError};
 use grammar_regex::*;
 use messages::*;
 use parser::ContextValues;
-use onig;
-use regex;
+use std::cmp;
 use std::collections::HashMap;
 use std::collections::LinkedList;
-pub trait Copy: Clone {}
 
 #[derive(Clone, Debug)]
-pub struct Tokenizer {
-    pub input: &'static str,
-    pub context: HashMap<&'static str, ContextValues>,
+pub struct Tokenizer<'a> {
+    pub input: &'a str,
+    pub context: HashMap<&'static str, ContextValues<'a>>,
     pub line: usize,
     pub index: usize,
-    pub instructions: LinkedList<HashMap<&'static str, InstructionValues>>,
+    /// Characters before `index`, which differs from `index` for non-ASCII input.
+    pub char_index: usize,
+    /// Instructions already tokenized but not yet yielded, like the lines
+    /// of a block after its first one.
+    pub instructions: LinkedList<Instruction<'a>>,
+    finished: bool,
+    /// Whether the input is still being read, so that a block without its
+    /// terminator yet ends the instructions rather than failing.
+    partial: bool,
 }
-#[derive(Clone, Debug)]
-pub enum InstructionValues {
-    Index(usize),
-    Line(usize),
-    Type(&'static str),
-    Comment(&'static str),
-    Name(&'static str),
-    Ranges(HashMap<&'static str, RangesValues>),
-    Value(&'static str),
-    Separator(&'static str),
-    Depth(usize),
-    Template(&'static str),
-    DeepCopy(bool),
-    Length(usize),
-    ContentRange(Vec<usize>),
+/// Where the line terminating a block is, as byte offsets into the input.
+struct Terminator {
+    /// The line break before the terminator, which ends the content.
+    line_break: usize,
+    operator: (usize, usize),
+    name: (usize, usize),
+    /// The end of the line, without the line break.
+    end: usize,
 }
-#[derive(Clone, Debug)]
-pub enum RangesValues {
-    Name(Vec<usize>),
-    NameOperator(Vec<usize>),
-    EscapeBeginOperator(Vec<usize>),
-    EscapeEndOperator(Vec<usize>),
-    BlockOperator(Vec<usize>),
-    ItemOperator(Vec<usize>),
-    Value(Vec<usize>),
-    EntryOperator(Vec<usize>),
-    NewlineContinuationOperator(Vec<usize>),
-    LineContinuationOperator(Vec<usize>),
-    Template(Vec<usize>),
-    SectionOperator(Vec<usize>),
-    CopyOperator(Vec<usize>),
-    DeepCopyOperator(Vec<usize>),
-    Comment(Vec<usize>),
-    Content(Vec<usize>),
-    CommentOperator(Vec<usize>),
+
+/// One line of the input as recognized by the tokenizer. Parts the kind of
+/// instruction does not have are `None`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction<'a> {
+    pub kind: InstructionKind,
+    /// Byte offset of the start of the line.
+    pub index: usize,
+    /// Length of the line in bytes, without the line break.
+    pub length: usize,
+    /// 0-based, regardless of the indexing the document is parsed with.
+    pub line: usize,
+    pub name: Option<&'a str>,
+    pub value: Option<&'a str>,
+    pub comment: Option<&'a str>,
+    /// How a continuation joins the value before it: `" "` for `\` and
+    /// `"\n"` for `|`.
+    pub separator: Option<&'a str>,
+    /// The number of `#` of a section, 0 for other instructions.
+    pub depth: usize,
+    pub template: Option<&'a str>,
+    pub deep_copy: bool,
+    pub ranges: Ranges,
+    /// The content of a block, from its first to the end of its last line.
+    pub content_range: Option<Span>,
 }
 
-impl InstructionValues {
-    pub fn get_numeric(&self) -> Option<&usize> {
-        match self {
-            InstructionValues::Index(c) => Some(c),
-            InstructionValues::Line(c) => Some(c),
-            InstructionValues::Depth(c) => Some(c),
-            InstructionValues::Length(c) => Some(c),
-            _ => None,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InstructionKind {
+    EmptyLine,
+    /// A name with a value on the same line, like `name: value`.
+    Field,
+    /// A name without a value, which continuations, list items or fieldset
+    /// entries may follow.
+    Name,
+    ListItem,
+    FieldsetEntry,
+    /// The line opening a block.
+    Block,
+    BlockContent,
+    BlockTerminator,
+    Section,
+    Comment,
+    Continuation,
+    /// A name copying another element, like `name < template`.
+    Copy,
+    /// A line after a tokenization error, which is not tokenized further.
+    Unparsed,
+}
+
+/// A location in the input. All fields are 0-based.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Position {
+    /// Offset in bytes from the start of the input.
+    pub byte: usize,
+    /// Offset in characters from the start of the input.
+    pub char: usize,
+    pub line: usize,
+    /// Offset in characters from the start of the line.
+    pub column: usize,
+}
+
+/// The part of the input from `start` up to, but not including, `end`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Where the parts of an instruction are, for those parts it has.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ranges {
+    pub name: Option<Span>,
+    pub name_operator: Option<Span>,
+    pub escape_begin_operator: Option<Span>,
+    pub escape_end_operator: Option<Span>,
+    pub block_operator: Option<Span>,
+    pub item_operator: Option<Span>,
+    pub value: Option<Span>,
+    pub entry_operator: Option<Span>,
+    pub newline_continuation_operator: Option<Span>,
+    pub line_continuation_operator: Option<Span>,
+    pub template: Option<Span>,
+    pub section_operator: Option<Span>,
+    pub copy_operator: Option<Span>,
+    pub deep_copy_operator: Option<Span>,
+    pub comment: Option<Span>,
+    pub content: Option<Span>,
+    pub comment_operator: Option<Span>,
+}
+
+impl<'a> Instruction<'a> {
+    fn new(kind: InstructionKind, index: usize, line: usize) -> Instruction<'a> {
+        Instruction {
+            kind,
+            index,
+            length: 0,
+            line,
+            name: None,
+            value: None,
+            comment: None,
+            separator: None,
+            depth: 0,
+            template: None,
+            deep_copy: false,
+            ranges: Ranges::default(),
+            content_range: None,
         }
     }
-    pub fn get_str(&self) -> Option<&'static str> {
-        match self {
-            InstructionValues::Type(c) => Some(c),
-            InstructionValues::Comment(c) => Some(c),
-            InstructionValues::Name(c) => Some(c),
-            InstructionValues::Value(c) => Some(c),
-            InstructionValues::Separator(c) => Some(c),
-            InstructionValues::Template(c) => Some(c),
-            _ => None,
-        }
+}
+
+impl Span {
+    pub fn len(&self) -> usize {
+        self.end.byte - self.start.byte
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start.byte == self.end.byte
+    }
+}
+
+impl Ranges {
+    /// The spans of all parts the instruction has.
+    pub fn spans(&self) -> Vec<Span> {
+        [
+            self.name,
+            self.name_operator,
+            self.escape_begin_operator,
+            self.escape_end_operator,
+            self.block_operator,
+            self.item_operator,
+            self.value,
+            self.entry_operator,
+            self.newline_continuation_operator,
+            self.line_continuation_operator,
+            self.template,
+            self.section_operator,
+            self.copy_operator,
+            self.deep_copy_operator,
+            self.comment,
+            self.content,
+            self.comment_operator,
+        ]
+        .iter()
+        .filter_map(|span| *span)
+        .collect()
     }
 }
 
-impl Tokenizer {
-    pub fn new(context: &mut HashMap<&'static str, ContextValues>) -> Tokenizer {
+impl<'a> Tokenizer<'a> {
+    pub fn new(context: &mut HashMap<&'static str, ContextValues<'a>>) -> Tokenizer<'a> {
         let mut k = Tokenizer {
             context: context.clone(),
             input: context.get("Input").unwrap().get_str().unwrap(),
             line: 0,
             index: 0,
+            char_index: 0,
             instructions: LinkedList::new(),
+            finished: false,
+            partial: false,
         };
         k.context.insert(
             "Instructions",
@@ -91,933 +198,474 @@ impl Tokenizer {
         );
         k
     }
-    pub fn tokenize(&mut self) {
-        env_logger::init();
-        info!("starting up");
-        let mut ranges = HashMap::new();
-        let mut block;
-        loop {
-            let reg = onig::Regex::new(get_regex().as_str()).unwrap();
-            let r = reg.find(self.input);
-            let capture = reg.captures(self.input).unwrap();
-            println!("{:?}", capture);
-            println!("{:?}", r);
-            let mut instruction = HashMap::new();
-            if (r.is_none()) || (r.unwrap().0 != self.index) {
-                instruction = self.tokenize_error_context();
-                Tokenization::error_msg("invalid_line", &self.context, &instruction);
+
+    /// A tokenizer for `input` reporting errors in English, for reading the
+    /// instructions one by one without parsing the document.
+    pub fn from_input(input: &'a str, zero_indexing: bool) -> Tokenizer<'a> {
+        let mut context = HashMap::new();
+        context.insert(
+            "Indexing",
+            ContextValues::Indexing(if zero_indexing { 0 } else { 1 }),
+        );
+        context.insert("Input", ContextValues::Input(input));
+        context.insert(
+            "Messages",
+            ContextValues::Messages(Box::new(Messages::for_locale("en"))),
+        );
+        Tokenizer::new(&mut context)
+    }
+
+    /// A tokenizer going on at `from` with input that is still being read
+    /// and ends after a complete line. It stops before a block whose
+    /// terminator is not in the input yet, see `checkpoint`.
+    pub(crate) fn resume(
+        context: HashMap<&'static str, ContextValues<'a>>,
+        from: Position,
+    ) -> Tokenizer<'a> {
+        let input = context.get("Input").unwrap().get_str().unwrap();
+        Tokenizer {
+            input,
+            context,
+            line: from.line,
+            index: from.byte,
+            char_index: from.char,
+            instructions: LinkedList::new(),
+            finished: from.byte >= input.len(),
+            partial: true,
+        }
+    }
+
+    /// Where a resumed tokenizer got to, to resume it there once more input
+    /// is read.
+    pub(crate) fn checkpoint(&self) -> Position {
+        Position {
+            byte: self.index,
+            char: self.char_index,
+            line: self.line,
+            column: 0,
+        }
+    }
+
+    /// Tokenizes the remaining input into `context["Instructions"]`, where
+    /// the analyzer picks the instructions up.
+    pub fn tokenize(&mut self) -> Result<(), ParseError> {
+        let instructions = self.by_ref().collect::<Result<LinkedList<_>, _>>()?;
+        self.context
+            .insert("Instructions", ContextValues::Instruction(instructions));
+        Ok(())
+    }
+
+    /// Tokenizes the next line, or a whole block, into `self.instructions`.
+    fn tokenize_line(&mut self) -> Result<(), TokenizeError> {
+        let mut block = false;
+        let mut instruction = Instruction::new(InstructionKind::EmptyLine, self.index, self.line);
+        let mut ranges = Ranges::default();
+        let capture = match GRAMMAR.captures_at(self.input, self.index) {
+            Some(capture) => capture,
+            None => return Err(self.error("invalid_line", &self.unparsed_line())),
+        };
+        let r = capture.pos(0).unwrap();
+        let span = |group: usize| capture.pos(group).map(|pos| self.span(pos));
+
+        if capture.at(EMPTY_LINE_INDEX).is_some() {
+            instruction.kind = InstructionKind::EmptyLine;
+        } else if capture.at(NAME_OPERATOR_INDEX).is_some() {
+            ranges.name_operator = span(NAME_OPERATOR_INDEX);
+            if let Some(unescaped_name) = capture.at(NAME_UNESCAPED_INDEX) {
+                instruction.name = Some(unescaped_name);
+                ranges.name = span(NAME_UNESCAPED_INDEX);
+            } else {
+                let escaped_name = capture.at(NAME_ESCAPED_INDEX).unwrap();
+                instruction.name = Some(escaped_name);
+                ranges.escape_begin_operator = span(NAME_ESCAPE_BEGIN_OPERATOR_INDEX);
+                ranges.escape_end_operator = span(NAME_ESCAPE_END_OPERATOR_INDEX);
+                ranges.name = span(NAME_ESCAPED_INDEX);
             }
-            instruction.insert("Index", InstructionValues::Index(self.index));
-            instruction.insert("Line", InstructionValues::Index(self.line));
-            block = false;
-
-            if capture.at(EMPTY_LINE_INDEX).is_some() {
-                instruction.insert("Type", InstructionValues::Type("EMPTY_LINE"));
-            } else if capture.at(NAME_OPERATOR_INDEX).is_some() {
-                info!("moved here");
-                let unescaped_name = capture.at(NAME_UNESCAPED_INDEX).unwrap();
-
-                if !(unescaped_name.is_empty()) {
-                    instruction.insert("Name", InstructionValues::Name(unescaped_name));
-                    let name_column =
-                        capture.pos(NAME_UNESCAPED_INDEX).unwrap().0 - self.index;
-                    let name_operator_column =
-                        capture.pos(NAME_OPERATOR_INDEX).unwrap().0 - self.index;
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "Name",
-                                RangesValues::Name(
-                                    (name_column..(name_column + unescaped_name.len())).collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "NameOperator",
-                                RangesValues::NameOperator(
-                                    (name_operator_column..(name_column + 1)).collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                } else {
-                    ranges = HashMap::new();
-                    let escaped_name = capture.at(NAME_ESCAPED_INDEX).unwrap();
-                    instruction.insert("Name", InstructionValues::Name(escaped_name));
-
-                    let escape_operator = capture.at(NAME_ESCAPE_BEGIN_OPERATOR_INDEX).unwrap();
-                    let escape_begin_operator_column = capture
-                        .pos(NAME_ESCAPE_BEGIN_OPERATOR_INDEX)
-                        .unwrap()
-                        .0
-                        - self.index;
-                    let name_column = capture.pos(NAME_ESCAPED_INDEX).unwrap().0 - self.index;
-                    let escape_end_operator_column =
-                        capture.pos(NAME_ESCAPE_END_OPERATOR_INDEX).unwrap().0 - self.index;
-                    let name_operator_column =
-                        capture.pos(NAME_OPERATOR_INDEX).unwrap().0 - self.index;
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "EscapeBeginOperator",
-                                RangesValues::EscapeBeginOperator(
-                                    (escape_begin_operator_column
-                                        ..(escape_begin_operator_column
-                                        + escape_operator.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "EscapeEndOperator",
-                                RangesValues::EscapeEndOperator(
-                                    (escape_end_operator_column
-                                        ..(escape_end_operator_column
-                                        + escape_operator.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "NameOperator",
-                                RangesValues::NameOperator(
-                                    (name_operator_column..(name_operator_column + 1)).collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "Name",
-                                RangesValues::Name(
-                                    (name_column..(name_column + escaped_name.len())).collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                }
-                let value = capture.at(FIELD_VALUE_INDEX).unwrap();
-                if capture.at(FIELD_VALUE_INDEX).is_some() {
-                    instruction.insert("Type", InstructionValues::Type("FIELD"));
-                    instruction.insert("Value", InstructionValues::Value(value));
-
-                    let value_column = capture.pos(FIELD_VALUE_INDEX).unwrap().0 - self.index;
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "Value",
-                                RangesValues::Value(
-                                    (value_column..value_column + value.len()).collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                } else {
-                    instruction.insert("Type", InstructionValues::Type("Name"));
-                }
-            } else if capture.at(LIST_ITEM_OPERATOR_INDEX).is_some() {
-                info!("moved here");
-                instruction.insert("Type", InstructionValues::Type("LIST_ITEM"));
-                let operator_column =
-                    capture.pos(LIST_ITEM_OPERATOR_INDEX).unwrap().0 - self.index;
-                instruction.insert(
-                    "Ranges",
-                    InstructionValues::Ranges({
-                        ranges.insert(
-                            "ItemOperator",
-                            RangesValues::ItemOperator(
-                                (operator_column..(operator_column + 1)).collect(),
-                            ),
-                        );
-                        ranges.clone()
-                    }),
-                );
-                let value = capture.at(LIST_ITEM_VALUE_INDEX).unwrap();
-                instruction.insert("Value", InstructionValues::Value(value));
-                if capture.at(LIST_ITEM_VALUE_INDEX).is_some() {
-                    let value_column =
-                        capture.pos(LIST_ITEM_VALUE_INDEX).unwrap().0 - self.index;
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "Value",
-                                RangesValues::Value(
-                                    (value_column..(value_column + value.len())).collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                }
-            } else if capture.at(FIELDSET_ENTRY_OPERATOR_INDEX).is_some() {
-                info!("moved here");
-                let unescaped_name = capture.at(NAME_UNESCAPED_INDEX).unwrap();
-                if capture.at(NAME_UNESCAPED_INDEX).is_some() {
-                    instruction.insert("Name", InstructionValues::Name(unescaped_name));
-                    let name_column =
-                        capture.pos(NAME_UNESCAPED_INDEX).unwrap().0 - self.index;
-                    let entry_operator_column =
-                        capture.pos(FIELDSET_ENTRY_OPERATOR_INDEX).unwrap().0 - self.index;
-
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "EntryOperator",
-                                RangesValues::EntryOperator(
-                                    (entry_operator_column..(entry_operator_column + 1)).collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "Name",
-                                RangesValues::Name(
-                                    (name_column..(name_column + unescaped_name.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                } else {
-                    let escaped_name = capture.at(NAME_ESCAPED_INDEX).unwrap();
-                    instruction.insert("Name", InstructionValues::Name(escaped_name));
-
-                    let escape_operator = capture.at(NAME_ESCAPE_BEGIN_OPERATOR_INDEX).unwrap();
-                    let escape_begin_operator_column = capture
-                        .pos(NAME_ESCAPE_BEGIN_OPERATOR_INDEX)
-                        .unwrap()
-                        .0
-                        - self.index;
-                    let name_column = capture.pos(NAME_ESCAPED_INDEX).unwrap().0 - self.index;
-                    let escape_end_operator_column =
-                        capture.pos(NAME_ESCAPE_END_OPERATOR_INDEX).unwrap().0 - self.index;
-                    let entry_operator_column =
-                        capture.pos(FIELDSET_ENTRY_OPERATOR_INDEX).unwrap().0 - self.index;
-
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "EscapeBeginOperator",
-                                RangesValues::EscapeBeginOperator(
-                                    (escape_begin_operator_column
-                                        ..(escape_begin_operator_column
-                                        + escape_operator.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "EscapeEndOperator",
-                                RangesValues::EscapeEndOperator(
-                                    (escape_end_operator_column
-                                        ..(escape_end_operator_column
-                                        + escape_operator.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "EntryOperator",
-                                RangesValues::NameOperator(
-                                    (entry_operator_column..(entry_operator_column + 1)).collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "Name",
-                                RangesValues::Name(
-                                    (name_column..(name_column + escaped_name.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                }
-                instruction.insert("Type", InstructionValues::Type("FIELDSET_ENTRY"));
-                let value = capture.at(FIELDSET_ENTRY_VALUE_INDEX).unwrap();
-                instruction.insert("Value", InstructionValues::Value(value));
-                if capture.at(FIELDSET_ENTRY_VALUE_INDEX).is_some() {
-                    let value_column =
-                        capture.pos(FIELDSET_ENTRY_VALUE_INDEX).unwrap().0 - self.index;
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "Value",
-                                RangesValues::Value(
-                                    (value_column..(value_column + value.len())).collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                }
-            } else if capture.at(LINE_CONTINUATION_OPERATOR_INDEX).is_some() {
-                info!("moved here");
-                instruction.insert("Separator", InstructionValues::Separator(" "));
-                instruction.insert("Type", InstructionValues::Type("CONTINUATION"));
-
-                let value = capture.at(LINE_CONTINUATION_VALUE_INDEX).unwrap();
-                instruction.insert("Value", InstructionValues::Value(value));
-
-                let operator_column = capture
-                    .pos(LINE_CONTINUATION_OPERATOR_INDEX)
-                    .unwrap()
-                    .0 - self.index;
-                instruction.insert(
-                    "Ranges",
-                    InstructionValues::Ranges({
-                        ranges.insert(
-                            "LineContinuationOperator",
-                            RangesValues::LineContinuationOperator(
-                                (operator_column..(operator_column + 1)).collect(),
-                            ),
-                        );
-                        ranges.clone()
-                    }),
-                );
-                if capture.at(LINE_CONTINUATION_VALUE_INDEX).is_some() {
-                    let value_column =
-                        capture.pos(LINE_CONTINUATION_VALUE_INDEX).unwrap().0 - self.index;
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "Value",
-                                RangesValues::Value(
-                                    (value_column..(value_column + value.len())).collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                }
-            } else if capture.at(NEWLINE_CONTINUATION_OPERATOR_INDEX).is_some() {
-                info!("moved here");
-                instruction.insert("Separator", InstructionValues::Separator("\n"));
-                instruction.insert("Type", InstructionValues::Type("CONTINUATION"));
-
-                let value = capture.at(NEWLINE_CONTINUATION_VALUE_INDEX).unwrap();
-                instruction.insert("Value", InstructionValues::Value(value));
-
-                let operator_column = capture
-                    .pos(NEWLINE_CONTINUATION_OPERATOR_INDEX)
-                    .unwrap()
-                    .0 - self.index;
-                instruction.insert(
-                    "Ranges",
-                    InstructionValues::Ranges({
-                        ranges.insert(
-                            "NewLineContinuationOperator",
-                            RangesValues::NewlineContinuationOperator(
-                                (operator_column..(operator_column + 1)).collect(),
-                            ),
-                        );
-                        ranges.clone()
-                    }),
-                );
-                if capture.at(NEWLINE_CONTINUATION_VALUE_INDEX).is_some() {
-                    let value_column = capture
-                        .pos(NEWLINE_CONTINUATION_VALUE_INDEX)
-                        .unwrap()
-                        .0 - self.index;
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "Value",
-                                RangesValues::Value(
-                                    (value_column..(value_column + value.len())).collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                }
-            } else if capture.at(SECTION_HASHES_INDEX).is_some() {
-                info!("moved here");
-                let section_operator = capture.at(SECTION_HASHES_INDEX).unwrap();
-                instruction.insert(
-                    "Depth",
-                    InstructionValues::Depth(section_operator.len()),
-                );
-                instruction.insert("Type", InstructionValues::Type("SECTION"));
-                let section_operator_column =
-                    capture.pos(SECTION_HASHES_INDEX).unwrap().0 - self.index;
-                let unescaped_name = capture.at(SECTION_NAME_UNESCAPED_INDEX).unwrap();
-
-                if capture.pos(SECTION_NAME_UNESCAPED_INDEX).is_some() {
-                    instruction.insert("Name", InstructionValues::Name(unescaped_name));
-                    let name_column =
-                        capture.pos(SECTION_NAME_UNESCAPED_INDEX).unwrap().0 - self.index;
-                    let name_end_column = name_column + unescaped_name.len();
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "Name",
-                                RangesValues::LineContinuationOperator(
-                                    (name_column..name_end_column).collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "SectionOperator",
-                                RangesValues::LineContinuationOperator(
-                                    (section_operator_column
-                                        ..(section_operator_column
-                                        + section_operator.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
+            if let Some(value) = capture.at(FIELD_VALUE_INDEX) {
+                instruction.kind = InstructionKind::Field;
+                instruction.value = Some(value);
+                ranges.value = span(FIELD_VALUE_INDEX);
+            } else {
+                instruction.kind = InstructionKind::Name;
+            }
+        } else if capture.at(LIST_ITEM_OPERATOR_INDEX).is_some() {
+            instruction.kind = InstructionKind::ListItem;
+            ranges.item_operator = span(LIST_ITEM_OPERATOR_INDEX);
+            if let Some(value) = capture.at(LIST_ITEM_VALUE_INDEX) {
+                instruction.value = Some(value);
+                ranges.value = span(LIST_ITEM_VALUE_INDEX);
+            }
+        } else if capture.at(FIELDSET_ENTRY_OPERATOR_INDEX).is_some() {
+            ranges.entry_operator = span(FIELDSET_ENTRY_OPERATOR_INDEX);
+            if let Some(unescaped_name) = capture.at(NAME_UNESCAPED_INDEX) {
+                instruction.name = Some(unescaped_name);
+                ranges.name = span(NAME_UNESCAPED_INDEX);
+            } else {
+                let escaped_name = capture.at(NAME_ESCAPED_INDEX).unwrap();
+                instruction.name = Some(escaped_name);
+                ranges.escape_begin_operator = span(NAME_ESCAPE_BEGIN_OPERATOR_INDEX);
+                ranges.escape_end_operator = span(NAME_ESCAPE_END_OPERATOR_INDEX);
+                ranges.name = span(NAME_ESCAPED_INDEX);
+            }
+            instruction.kind = InstructionKind::FieldsetEntry;
+            if let Some(value) = capture.at(FIELDSET_ENTRY_VALUE_INDEX) {
+                instruction.value = Some(value);
+                ranges.value = span(FIELDSET_ENTRY_VALUE_INDEX);
+            }
+        } else if capture.at(LINE_CONTINUATION_OPERATOR_INDEX).is_some() {
+            instruction.separator = Some(" ");
+            instruction.kind = InstructionKind::Continuation;
+            ranges.line_continuation_operator = span(LINE_CONTINUATION_OPERATOR_INDEX);
+            if let Some(value) = capture.at(LINE_CONTINUATION_VALUE_INDEX) {
+                instruction.value = Some(value);
+                ranges.value = span(LINE_CONTINUATION_VALUE_INDEX);
+            }
+        } else if capture.at(NEWLINE_CONTINUATION_OPERATOR_INDEX).is_some() {
+            instruction.separator = Some("\n");
+            instruction.kind = InstructionKind::Continuation;
+            ranges.newline_continuation_operator = span(NEWLINE_CONTINUATION_OPERATOR_INDEX);
+            if let Some(value) = capture.at(NEWLINE_CONTINUATION_VALUE_INDEX) {
+                instruction.value = Some(value);
+                ranges.value = span(NEWLINE_CONTINUATION_VALUE_INDEX);
+            }
+        } else if let Some(section_operator) = capture.at(SECTION_HASHES_INDEX) {
+            instruction.depth = section_operator.len();
+            instruction.kind = InstructionKind::Section;
+            ranges.section_operator = span(SECTION_HASHES_INDEX);
+
+            if let Some(unescaped_name) = capture.at(SECTION_NAME_UNESCAPED_INDEX) {
+                instruction.name = Some(unescaped_name);
+                ranges.name = span(SECTION_NAME_UNESCAPED_INDEX);
+            } else {
+                let escaped_name = capture.at(SECTION_NAME_ESCAPED_INDEX).unwrap();
+                instruction.name = Some(escaped_name);
+                ranges.escape_begin_operator = span(SECTION_NAME_ESCAPE_BEGIN_OPERATOR_INDEX);
+                ranges.escape_end_operator = span(SECTION_NAME_ESCAPE_END_OPERATOR_INDEX);
+                ranges.name = span(SECTION_NAME_ESCAPED_INDEX);
+            }
+
+            if let Some(template) = capture.at(SECTION_TEMPLATE_INDEX) {
+                instruction.template = Some(template);
+                ranges.template = span(SECTION_TEMPLATE_INDEX);
+
+                if capture.at(SECTION_COPY_OPERATOR_INDEX) == Some("<") {
+                    instruction.deep_copy = false;
+                    ranges.copy_operator = span(SECTION_COPY_OPERATOR_INDEX);
                 } else {
-                    let escaped_name = capture.at(SECTION_NAME_ESCAPED_INDEX).unwrap();
-                    instruction.insert("Name", InstructionValues::Name(escaped_name));
-
-                    let escape_operator = capture
-                        .at(SECTION_NAME_ESCAPE_BEGIN_OPERATOR_INDEX)
-                        .unwrap();
-                    let escape_begin_operator_column = capture
-                        .pos(SECTION_NAME_ESCAPE_BEGIN_OPERATOR_INDEX)
-                        .unwrap()
-                        .0
-                        - self.index;
-                    let name_column =
-                        capture.pos(SECTION_NAME_ESCAPED_INDEX).unwrap().0 - self.index;
-                    let escape_end_operator_column = capture
-                        .pos(SECTION_NAME_ESCAPE_END_OPERATOR_INDEX)
-                        .unwrap()
-                        .0 - self.index;
-                    let _name_end_column = escape_end_operator_column + escape_operator.len();
-
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "EscapeBeginOperator",
-                                RangesValues::EscapeBeginOperator(
-                                    (escape_begin_operator_column
-                                        ..(escape_begin_operator_column
-                                        + escape_operator.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "EscapeEndOperator",
-                                RangesValues::EscapeEndOperator(
-                                    (escape_end_operator_column
-                                        ..(escape_end_operator_column
-                                        + escape_operator.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "Name",
-                                RangesValues::Name(
-                                    (name_column..(name_column + escaped_name.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "SectionOperator",
-                                RangesValues::SectionOperator(
-                                    (section_operator_column
-                                        ..(section_operator_column
-                                        + section_operator.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                }
-                let template = capture.at(SECTION_TEMPLATE_INDEX).unwrap();
-
-                if capture.at(SECTION_TEMPLATE_INDEX).is_some() {
-                    instruction.insert("Template", InstructionValues::Template(template));
-
-                    let copy_operator = capture.at(SECTION_COPY_OPERATOR_INDEX).unwrap();
-                    let copy_operator_column =
-                        capture.pos(SECTION_COPY_OPERATOR_INDEX).unwrap().0 - self.index;
-                    let template_column =
-                        capture.pos(SECTION_TEMPLATE_INDEX).unwrap().0 - self.index;
-
-                    if copy_operator == "<" {
-                        instruction.insert("DeepCopy", InstructionValues::DeepCopy(false));
-                        instruction.insert(
-                            "Ranges",
-                            InstructionValues::Ranges({
-                                ranges.insert(
-                                    "CopyOperator",
-                                    RangesValues::CopyOperator(
-                                        (copy_operator_column..(copy_operator_column + 1))
-                                            .collect(),
-                                    ),
-                                );
-                                ranges.clone()
-                            }),
-                        );
-                    } else {
-                        instruction.insert("DeepCopy", InstructionValues::DeepCopy(true));
-                        instruction.insert(
-                            "Ranges",
-                            InstructionValues::Ranges({
-                                ranges.insert(
-                                    "DeepCopyOperator",
-                                    RangesValues::DeepCopyOperator(
-                                        (copy_operator_column..(copy_operator_column + 2))
-                                            .collect(),
-                                    ),
-                                );
-                                ranges.clone()
-                            }),
-                        );
-                    }
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "Template",
-                                RangesValues::Template(
-                                    (template_column..(template_column + template.len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
+                    instruction.deep_copy = true;
+                    ranges.deep_copy_operator = span(SECTION_COPY_OPERATOR_INDEX);
                 }
-            } else if capture.at(BLOCK_DASHES_INDEX).is_some() {
-                info!("moved here");
-                let operator = capture.at(BLOCK_DASHES_INDEX).unwrap();
-                let name = capture.at(BLOCK_NAME_INDEX).unwrap();
-
-                instruction.insert("Name", InstructionValues::Name(name));
-                instruction.insert("Type", InstructionValues::Type("BLOCK"));
-
-                let operator_column = capture.pos(BLOCK_DASHES_INDEX).unwrap().0 - self.index;
-                let name_column = capture.pos(BLOCK_NAME_INDEX).unwrap().0 - self.index;
-                instruction.insert(
-                    "Length",
-                    InstructionValues::Length(r.unwrap().1 - self.index),
-                );
-
-                instruction.insert(
-                    "Ranges",
-                    InstructionValues::Ranges({
-                        ranges.insert(
-                            "Template",
-                            RangesValues::Template(
-                                (operator_column..(operator_column + operator.len()))
-                                    .collect(),
-                            ),
-                        );
-                        ranges.clone()
-                    }),
-                );
-
-                instruction.insert(
-                    "Ranges",
-                    InstructionValues::Ranges({
-                        ranges.insert(
-                            "BlockOperator",
-                            RangesValues::BlockOperator(
-                                (operator_column..(operator_column + operator.len()))
-                                    .collect(),
-                            ),
-                        );
-                        ranges.insert(
-                            "Name",
-                            RangesValues::Name(
-                                (name_column..(name_column + name.len())).collect(),
-                            ),
-                        );
-                        ranges.clone()
-                    }),
-                );
-                self.context
-                    .get_mut("Instructions")
-                    .unwrap()
-                    .get_instruction_mut()
-                    .unwrap()
-                    .push_back(instruction.clone());
-
-                self.index = r.unwrap().1;
-
-                let terminator_str = format!("\\n[^\\S\\n]*({})[^\\S\\n]*({})[^\\S\\n]*(?=\\n|$)",operator, regex::escape(name));
-                let terminator_re = onig::Regex::new(terminator_str.as_str()).unwrap();
-                //TODO start match from self.index
-                let terminator_match = terminator_re.find_at(self.input, self.index).unwrap();
-                let terminator_capture = terminator_re.captures_at(self.input, self.index).unwrap();
-
-                self.index += 1;
-                self.line += 1;
-
-                if terminator_re.find_at(self.input, self.index).is_some() {
-                    self.tokenize_error_context();
-                    Tokenization::error_msg("unterminated_block", &self.context, &instruction);
+            }
+        } else if let Some(operator) = capture.at(BLOCK_DASHES_INDEX) {
+            let name = capture.at(BLOCK_NAME_INDEX).unwrap();
+            let char_index = self.char_index;
+
+            instruction.name = Some(name);
+            instruction.kind = InstructionKind::Block;
+            ranges.block_operator = span(BLOCK_DASHES_INDEX);
+            ranges.name = span(BLOCK_NAME_INDEX);
+            instruction.ranges = ranges.clone();
+            instruction.length = r.1 - self.index;
+            self.advance(r.1);
+
+            let terminator_match = match self.find_terminator(operator, name) {
+                Some(terminator_match) => terminator_match,
+                None if self.partial => {
+                    self.index = instruction.index;
+                    self.char_index = char_index;
+                    self.finished = true;
+                    return Ok(());
                 }
+                None => return Err(self.error("unterminated_block", &instruction)),
+            };
+            let end_of_block_index = terminator_match.line_break;
+
+            self.advance(self.index + 1);
+            self.line += 1;
+
+            if end_of_block_index != self.index - 1 {
+                instruction.content_range = Some(self.span((self.index, end_of_block_index)));
+                self.push_instruction(instruction.clone());
+                loop {
+                    let end_of_line_index = self.input[self.index..end_of_block_index]
+                        .find('\n')
+                        .map(|offset| self.index + offset)
+                        .unwrap_or(end_of_block_index);
+                    let mut content =
+                        Instruction::new(InstructionKind::BlockContent, self.index, self.line);
+                    content.length = end_of_line_index - self.index;
+                    content.ranges.content = Some(self.span((self.index, end_of_line_index)));
+                    self.push_instruction(content);
+
+                    self.advance(end_of_line_index + 1);
+                    self.line += 1;
 
-                let end_of_block_index = terminator_match.0;
-                if end_of_block_index != self.index - 1 {
-                    let mut instr = HashMap::new();
-                    instruction.insert(
-                        "ContentRange",
-                        InstructionValues::ContentRange(
-                            (self.index..(end_of_block_index - 1)).collect(),
-                        ),
-                    );
-                    loop {
-                        instr = HashMap::new();
-                        ranges = HashMap::new();
-                        let end_of_line_index = (&self.input[self.index..end_of_block_index])
-                            .find("\n")
-                            .unwrap();
-                        if (&self.input[self.index..end_of_block_index])
-                            .find("\n")
-                            .is_none()
-                            {
-                                self.instructions.push_back({
-                                    instr.insert("Index", InstructionValues::Index(self.index));
-                                    instr.insert(
-                                        "Length",
-                                        InstructionValues::Length(end_of_block_index - self.index),
-                                    );
-                                    instr.insert("Line", InstructionValues::Line(self.line));
-                                    instr.insert(
-                                        "Rages",
-                                        InstructionValues::Ranges({
-                                            ranges.insert(
-                                                "Content",
-                                                RangesValues::Content(
-                                                    (0..(end_of_block_index - self.index)).collect(),
-                                                ),
-                                            );
-                                            ranges.clone()
-                                        }),
-                                    );
-                                    instr.insert("Type", InstructionValues::Type("BLOCK_CONTENT"));
-                                    instr.clone()
-                                });
-
-                                self.index = end_of_block_index + 1;
-                                self.line += 1;
-
-                                break;
-                            } else {
-                            self.instructions.push_back({
-                                instr.insert("Index", InstructionValues::Index(self.index));
-                                instr.insert(
-                                    "Length",
-                                    InstructionValues::Length(end_of_line_index - self.index),
-                                );
-                                instr.insert("Line", InstructionValues::Line(self.line));
-                                instr.insert(
-                                    "Rages",
-                                    InstructionValues::Ranges({
-                                        ranges.insert(
-                                            "Content",
-                                            RangesValues::Content(
-                                                (0..(end_of_line_index - self.index)).collect(),
-                                            ),
-                                        );
-                                        ranges.clone()
-                                    }),
-                                );
-                                instr.insert("Type", InstructionValues::Type("BLOCK_CONTENT"));
-                                instr.clone()
-                            });
-
-                            self.index = end_of_line_index + 1;
-                            self.line += 1;
-                        }
+                    if end_of_line_index == end_of_block_index {
+                        break;
                     }
                 }
-                ranges = HashMap::new();
-                instruction.insert("Index", InstructionValues::Index(self.index));
-                instruction.insert(
-                    "Length",
-                    InstructionValues::Length(terminator_match.1 - self.index),
-                );
-                instruction.insert("Line", InstructionValues::Line(self.line));
-                instruction.insert(
-                    "Ranges",
-                    InstructionValues::Ranges({
-                        ranges.insert(
-                            "BlockOperator",
-                            RangesValues::BlockOperator(
-                                ((terminator_capture.pos(1).unwrap().0 - self.index)
-                                    ..(terminator_capture.pos(1).unwrap().1 - self.index))
-                                    .collect(),
-                            ),
-                        );
-                        ranges.insert(
-                            "Name",
-                            RangesValues::BlockOperator(
-                                ((terminator_capture.pos(2).unwrap().0 - self.index)
-                                    ..(terminator_capture.pos(2).unwrap().1 - self.index))
-                                    .collect(),
-                            ),
-                        );
-                        ranges.clone()
-                    }),
-                );
-                instruction.insert("Type", InstructionValues::Type("BLOCK_TERMINATOR"));
-
-                self.instructions.push_back(instruction);
-
-                self.index = terminator_match.1 + 1;
-                self.line += 1;
-
-                block = true;
-            } else if capture.at(COMMENT_OPERATOR_INDEX).is_some() {
-                info!("moved here");
-                let comment = capture.at(COMMENT_TEXT_INDEX);
-                let comment_operator_column =
-                    capture.pos(COMMENT_OPERATOR_INDEX).unwrap().0 - self.index;
-
-                instruction.insert("Type", InstructionValues::Type("COMMENT"));
-                instruction.insert(
-                    "Comment",
-                    InstructionValues::Comment(comment.unwrap()),
-                );
-                instruction.insert(
-                    "Ranges",
-                    InstructionValues::Ranges({
-                        ranges.insert(
-                            "CommentOperator",
-                            RangesValues::CommentOperator(
-                                (comment_operator_column..(comment_operator_column + 1)).collect(),
-                            ),
-                        );
-                        ranges.clone()
-                    }),
-                );
-
-                if comment.is_some() {
-                    let comment_column =
-                        capture.pos(COMMENT_TEXT_INDEX).unwrap().0 - self.index;
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "Comment",
-                                RangesValues::Comment(
-                                    (comment_column
-                                        ..(comment_column + comment.unwrap().len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                }
-            } else if capture.at(COPY_OPERATOR_INDEX).is_some() {
-                info!("moved here");
-                let operator = capture.at(COPY_OPERATOR_INDEX);
-                let template = capture.at(TEMPLATE_INDEX);
-                let unescaped_name = capture.at(NAME_UNESCAPED_INDEX);
-
-                if unescaped_name.is_some() {
-                    instruction.insert(
-                        "Name",
-                        InstructionValues::Name(unescaped_name.unwrap()),
-                    );
-
-                    let name_column =
-                        capture.pos(NAME_UNESCAPED_INDEX).unwrap().0 - self.index;
-                    let operator_column =
-                        capture.pos(COPY_OPERATOR_INDEX).unwrap().0 - self.index;
-
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "CopyOperator",
-                                RangesValues::CopyOperator(
-                                    (operator_column
-                                        ..operator_column + operator.unwrap().len())
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "Name",
-                                RangesValues::Name(
-                                    (name_column
-                                        ..name_column + unescaped_name.unwrap().len())
-                                        .collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                } else {
-                    let escaped_name = capture.at(NAME_ESCAPED_INDEX);
-                    instruction.insert(
-                        "Name",
-                        InstructionValues::Name(escaped_name.unwrap()),
-                    );
-
-                    let escape_operator = capture.at(NAME_ESCAPE_BEGIN_OPERATOR_INDEX);
-                    let escape_begin_operator_column = capture
-                        .pos(NAME_ESCAPE_BEGIN_OPERATOR_INDEX)
-                        .unwrap()
-                        .0
-                        - self.index;
-                    let name_column = capture.pos(NAME_ESCAPED_INDEX).unwrap().0 - self.index;
-                    let escape_end_operator_column =
-                        capture.pos(NAME_ESCAPE_END_OPERATOR_INDEX).unwrap().0 - self.index;
-                    let operator_column =
-                        capture.pos(COPY_OPERATOR_INDEX).unwrap().0 - self.index;
-
-                    instruction.insert(
-                        "Ranges",
-                        InstructionValues::Ranges({
-                            ranges.insert(
-                                "CopyOperator",
-                                RangesValues::CopyOperator(
-                                    (operator_column
-                                        ..(operator_column + operator.unwrap().len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "EscapeBeginOperator",
-                                RangesValues::EscapeBeginOperator(
-                                    (escape_begin_operator_column
-                                        ..(escape_begin_operator_column
-                                        + escape_operator.unwrap().len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "EscapeEndOperator",
-                                RangesValues::EscapeEndOperator(
-                                    (escape_end_operator_column
-                                        ..(escape_end_operator_column
-                                        + escape_operator.unwrap().len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.insert(
-                                "Name",
-                                RangesValues::Name(
-                                    (name_column
-                                        ..(name_column + escaped_name.unwrap().len()))
-                                        .collect(),
-                                ),
-                            );
-                            ranges.clone()
-                        }),
-                    );
-                }
+            } else {
+                self.push_instruction(instruction.clone());
+            }
+            let mut terminator =
+                Instruction::new(InstructionKind::BlockTerminator, self.index, self.line);
+            terminator.length = terminator_match.end - self.index;
+            terminator.name = Some(name);
+            terminator.ranges.block_operator = Some(self.span(terminator_match.operator));
+            terminator.ranges.name = Some(self.span(terminator_match.name));
+
+            self.push_instruction(terminator);
+
+            self.advance(terminator_match.end + 1);
+            self.line += 1;
+
+            block = true;
+        } else if capture.at(COMMENT_OPERATOR_INDEX).is_some() {
+            let comment = capture.at(COMMENT_TEXT_INDEX);
+
+            instruction.kind = InstructionKind::Comment;
+            instruction.comment = Some(comment.unwrap_or(""));
+            ranges.comment_operator = span(COMMENT_OPERATOR_INDEX);
+            ranges.comment = span(COMMENT_TEXT_INDEX);
+        } else if let Some(operator) = capture.at(COPY_OPERATOR_INDEX) {
+            let template = capture.at(TEMPLATE_INDEX).unwrap();
+            ranges.copy_operator = span(COPY_OPERATOR_INDEX);
+
+            if let Some(unescaped_name) = capture.at(NAME_UNESCAPED_INDEX) {
+                instruction.name = Some(unescaped_name);
+                ranges.name = span(NAME_UNESCAPED_INDEX);
+            } else {
+                let escaped_name = capture.at(NAME_ESCAPED_INDEX).unwrap();
+                instruction.name = Some(escaped_name);
+                ranges.escape_begin_operator = span(NAME_ESCAPE_BEGIN_OPERATOR_INDEX);
+                ranges.escape_end_operator = span(NAME_ESCAPE_END_OPERATOR_INDEX);
+                ranges.name = span(NAME_ESCAPED_INDEX);
+            }
 
-                instruction.insert(
-                    "Template",
-                    InstructionValues::Template(template.unwrap()),
-                );
-                instruction.insert("Type", InstructionValues::Type("NAME"));
-
-                let template_column = capture.pos(TEMPLATE_INDEX).unwrap().0 - self.index;
-                instruction.insert(
-                    "Ranges",
-                    InstructionValues::Ranges({
-                        ranges.insert(
-                            "Template",
-                            RangesValues::Template(
-                                (template_column
-                                    ..template_column + template.unwrap().len())
-                                    .collect(),
-                            ),
-                        );
-                        ranges.clone()
-                    }),
-                );
-
-                if !(block) {
-                    instruction.insert(
-                        "Length",
-                        InstructionValues::Length(r.unwrap().1 - self.index),
-                    );
-                    self.index = r.unwrap().1 + 1;
-                    self.context
-                        .get_mut("Instructions")
-                        .unwrap()
-                        .get_instruction_mut()
-                        .unwrap()
-                        .push_back(instruction.clone());
-                    self.line += 1;
-                }
+            instruction.template = Some(template);
+            instruction.deep_copy = operator == "<<";
+            instruction.kind = InstructionKind::Copy;
+            ranges.template = span(TEMPLATE_INDEX);
+        }
 
-                let mut instr = HashMap::new();
-                if self.index >= self.input.len() {
-                    if self.input.len() > 0
-                        && self.input.chars().nth(self.input.len() - 1).unwrap() == '\n'
-                        {
-                            self.context
-                                .get_mut("Instructions")
-                                .unwrap()
-                                .get_instruction_mut()
-                                .unwrap()
-                                .push_back({
-                                    instr.insert("Index", InstructionValues::Index(self.input.len()));
-                                    instr.insert("Length", InstructionValues::Length(0));
-                                    instr.insert("Line", InstructionValues::Line(self.line));
-                                    instr.insert("Type", InstructionValues::Type("EMPTY_LINE"));
-                                    instr.clone()
-                                });
-                            break;
-                        }
-                }
+        if !block {
+            instruction.ranges = ranges;
+            instruction.length = r.1 - self.index;
+            self.push_instruction(instruction);
+            self.advance(r.1 + 1);
+            self.line += 1;
+        }
+
+        if self.index >= self.input.len() {
+            if self.input.ends_with('\n') && !self.partial {
+                let empty_line =
+                    Instruction::new(InstructionKind::EmptyLine, self.input.len(), self.line);
+                self.push_instruction(empty_line);
             }
+            self.finished = true;
         }
+        Ok(())
     }
 
-    pub fn tokenize_error_context(&mut self) -> HashMap<&'static str, InstructionValues> {
-        let mut first_instruction = HashMap::new();
-        loop {
-            let end_of_line_index = &self.input[self.index..self.input.len()].find("\n");
-            let mut instruction = HashMap::new();
-            if end_of_line_index.is_none() {
-                instruction.insert("Index", InstructionValues::Index(self.index));
-                instruction.insert(
-                    "Length",
-                    InstructionValues::Length(self.input.len() - self.index),
-                );
-                instruction.insert("Line", InstructionValues::Line(self.line));
-                for (key, val) in self.context.iter_mut() {
-                    if key == &"Instructions" {
-                        val.get_instruction_mut()
-                            .unwrap()
-                            .push_back(instruction.clone());
-                    }
+    /// Finds the line terminating the block `name` opened with the dashes
+    /// `operator`, after the line break at the current index.
+    fn find_terminator(&self, operator: &str, name: &str) -> Option<Terminator> {
+        let mut line_break = self.index;
+        while line_break < self.input.len() {
+            let start = line_break + 1;
+            let end = self.input[start..]
+                .find('\n')
+                .map_or(self.input.len(), |offset| start + offset);
+            let dashes = end - self.input[start..end].trim_start().len();
+            if self.input[dashes..end].starts_with(operator) {
+                let after_dashes = dashes + operator.len();
+                let name_start = end - self.input[after_dashes..end].trim_start().len();
+                if self.input[name_start..end].trim_end() == name {
+                    return Some(Terminator {
+                        line_break,
+                        operator: (dashes, after_dashes),
+                        name: (name_start, name_start + name.len()),
+                        end,
+                    });
                 }
-                if first_instruction.is_empty() {
-                    return instruction.clone();
-                } else {
-                    return first_instruction;
-                }
-            } else {
-                instruction.insert("Index", InstructionValues::Index(self.index));
-                instruction.insert(
-                    "Length",
-                    InstructionValues::Length(end_of_line_index.unwrap() - self.index),
-                );
-                instruction.insert("Line", InstructionValues::Line(self.line));
-                for (key, val) in self.context.iter_mut() {
-                    if key == &"Instructions" {
-                        val.get_instruction_mut().unwrap().push_back(instruction.clone());
-                    }
-                }
-                if first_instruction.is_empty() {
-                    first_instruction = instruction.clone();
+            }
+            line_break = end;
+        }
+        None
+    }
 
-                    self.index = end_of_line_index.unwrap() + 1;
-                    self.line += 1;
-                }
+    fn error(&self, el_name: &'static str, instruction: &Instruction<'a>) -> TokenizeError {
+        let message = Tokenization::error_msg(el_name, &self.context, instruction);
+        let indexing = *self.context.get("Indexing").unwrap().get_indexing().unwrap();
+        TokenizeError(ErrorDetails::from_instruction(message, instruction, indexing))
+    }
+
+    /// The current line as it stands, for reporting it as invalid.
+    fn unparsed_line(&self) -> Instruction<'a> {
+        let end_of_line_index = self.input[self.index..]
+            .find('\n')
+            .map_or(self.input.len(), |offset| self.index + offset);
+        let mut instruction = Instruction::new(InstructionKind::Unparsed, self.index, self.line);
+        instruction.length = end_of_line_index - self.index;
+        instruction
+    }
+
+    /// The span of the byte range `(start, end)`, which begins on the current
+    /// line or one after it.
+    fn span(&self, (start, end): (usize, usize)) -> Span {
+        Span {
+            start: self.position(start),
+            end: self.position(end),
+        }
+    }
+
+    fn position(&self, byte: usize) -> Position {
+        let passed = &self.input[self.index..byte];
+        let char = self.char_index + passed.chars().count();
+        match passed.rfind('\n') {
+            Some(newline) => Position {
+                byte,
+                char,
+                line: self.line + passed.matches('\n').count(),
+                column: passed[newline + 1..].chars().count(),
+            },
+            None => Position {
+                byte,
+                char,
+                line: self.line,
+                column: char - self.char_index,
+            },
+        }
+    }
+
+    /// Moves on to the byte `index`, which may lie past the end of the input.
+    fn advance(&mut self, index: usize) {
+        let end = cmp::min(index, self.input.len());
+        if end > self.index {
+            self.char_index += self.input[self.index..end].chars().count();
+        }
+        self.index = index;
+    }
+
+    fn push_instruction(&mut self, instruction: Instruction<'a>) {
+        self.instructions.push_back(instruction);
+    }
+}
+
+/// Yields the instructions line by line, tokenizing only as far as they are
+/// consumed. After an error the iterator ends.
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Instruction<'a>, TokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.instructions.is_empty() {
+            if self.finished {
+                return None;
+            }
+            if let Err(error) = self.tokenize_line() {
+                self.finished = true;
+                return Some(Err(error));
             }
         }
+        self.instructions.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use error::ParseError;
+    use parser::parse;
+
+    fn kinds(input: &str) -> Vec<InstructionKind> {
+        Tokenizer::from_input(input, false)
+            .map(|instruction| instruction.unwrap().kind)
+            .collect()
+    }
+
+    fn tokenization_error(input: &str) -> String {
+        match parse(input, false) {
+            Err(ParseError::Tokenization(details)) => details.message,
+            result => panic!("{:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn yields_an_instruction_per_line() {
+        use self::InstructionKind::*;
+        let input = "> note\nf: v\n\\ more\nl:\n- x\ns:\na = 1\n\n-- b\ntext\n-- b\n# s < t\nc < f";
+        assert_eq!(
+            kinds(input),
+            vec![
+                Comment,
+                Field,
+                Continuation,
+                Name,
+                ListItem,
+                Name,
+                FieldsetEntry,
+                EmptyLine,
+                Block,
+                BlockContent,
+                BlockTerminator,
+                Section,
+                Copy,
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_each_tokenization_error() {
+        assert_eq!(
+            tokenization_error("f: v\n:x\n"),
+            "Line 2 does not follow any specified pattern."
+        );
+        assert_eq!(
+            tokenization_error("-- b\ntext\n"),
+            "The block 'b' starting in line 1 is not terminated until the end of the document."
+        );
+        assert_eq!(
+            tokenization_error("`name: v\n"),
+            "In line 1 the name of an element is escaped, but the escape sequence is not terminated until the end of the line."
+        );
+    }
+
+    #[test]
+    fn errors_start_at_the_first_span_of_their_line() {
+        let input = "f:\na = 1\n  a = 2\n";
+        let error = parse(input, false).unwrap_err();
+        assert_eq!((error.line(), error.column()), (3, 3));
+        assert_eq!(&input[error.range()], "a = 2");
+    }
+
+    #[test]
+    fn terminates_blocks_on_their_dashes_and_name_only() {
+        let input = "-- b\n--- b\n-- bb\n--b x\n  --  b  \nrest: 1\n";
+        let instructions = Tokenizer::from_input(input, false)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let terminator = &instructions[4];
+        assert_eq!(terminator.kind, InstructionKind::BlockTerminator);
+        assert_eq!((terminator.line, terminator.length), (4, 9));
+        let range = |span: Option<Span>| span.map(|span| &input[span.start.byte..span.end.byte]);
+        assert_eq!(range(terminator.ranges.block_operator), Some("--"));
+        assert_eq!(range(terminator.ranges.name), Some("b"));
+        assert_eq!(
+            range(instructions[0].content_range),
+            Some("--- b\n-- bb\n--b x")
+        );
+        assert_eq!(instructions[5].name, Some("rest"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn yields_instructions_lazily_and_stops_at_an_error() {
+        let mut tokenizer = Tokenizer::from_input("a: 1\n:x\nb: 2\n", false);
+        let first = tokenizer.next().unwrap().unwrap();
+        assert_eq!(
+            (first.kind, first.name, first.value),
+            (InstructionKind::Field, Some("a"), Some("1"))
+        );
+        assert!(tokenizer.next().unwrap().is_err());
+        assert!(tokenizer.next().is_none());
+    }
+}
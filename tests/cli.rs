@@ -0,0 +1,143 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// Runs `eno` with `args` and `input` as its standard input.
+fn eno(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_eno"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // eno may exit on bad arguments before it reads its input, closing the
+    // pipe on the write.
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+/// Writes `text` to a file of this name and returns its path.
+fn file(name: &str, text: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, text).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+fn code(output: &Output) -> i32 {
+    output.status.code().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn check_exits_by_whether_documents_are_valid() {
+    let valid = eno(&["check"], "a: 1\n");
+    assert_eq!((code(&valid), stderr(&valid)), (0, String::new()));
+
+    let invalid = eno(&["check"], "a: 1\nnot valid\n");
+    assert_eq!(code(&invalid), 1);
+    assert!(stderr(&invalid)
+        .starts_with("<stdin>:2:1\nLine 2 does not follow any specified pattern.\n"));
+    assert!(stderr(&invalid).contains("> 2 | not valid\n    | ^^^^^^^^^\n"));
+
+    let unreadable = eno(&["check", "does-not-exist.eno"], "");
+    assert_eq!(code(&unreadable), 2);
+    assert!(stderr(&unreadable).starts_with("eno: cannot read does-not-exist.eno: "));
+}
+
+#[test]
+fn check_reports_what_differs_from_the_schema() {
+    let schema = file("schema.eno", "title: field\n");
+    let output = eno(&["check", "--schema", &schema], "other: x\n");
+    assert_eq!(code(&output), 1);
+    assert!(stderr(&output).contains("The field 'title' is missing"));
+    assert!(stderr(&output).contains("An excess element named 'other' was found"));
+
+    let output = eno(&["check", "--schema", &schema], "title: x\n");
+    assert_eq!(code(&output), 0);
+
+    let invalid_schema = file("invalid-schema.eno", "title: nonsense words\n");
+    let output = eno(&["check", "--schema", &invalid_schema], "title: x\n");
+    assert_eq!(code(&output), 2);
+    assert_eq!(
+        stderr(&output),
+        format!(
+            "eno: {}: The declaration 'title' in line 1 has the unknown word 'nonsense'.\n",
+            invalid_schema
+        )
+    );
+}
+
+#[test]
+fn fmt_prints_checks_and_rewrites_the_layout() {
+    let output = eno(&["fmt"], "  a :  1\n> kept  as is\n");
+    assert_eq!(code(&output), 0);
+    assert_eq!(stdout(&output), "a: 1\n> kept  as is\n");
+
+    let output = eno(&["fmt", "--check"], "  a :  1\n");
+    assert_eq!(
+        (code(&output), stderr(&output)),
+        (1, "<stdin> is not formatted\n".to_string())
+    );
+    assert_eq!(code(&eno(&["fmt", "--check"], "a: 1\n")), 0);
+
+    let path = file("unformatted.eno", "list:\n  -  x\n");
+    assert_eq!(code(&eno(&["fmt", "--write", &path], "")), 0);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "list:\n- x\n");
+
+    assert_eq!(code(&eno(&["fmt", "--check", "--write", &path], "")), 2);
+}
+
+#[test]
+fn convert_keeps_the_document_order() {
+    let input = "zeta: 1\nalpha:\n- x\n-\n# section\nb: 2\nset:\nk = v\n";
+    let output = eno(&["convert", "--to", "json"], input);
+    assert_eq!(code(&output), 0);
+    assert_eq!(
+        stdout(&output),
+        r#"{
+  "zeta": "1",
+  "alpha": [
+    "x",
+    null
+  ],
+  "section": {
+    "b": "2",
+    "set": {
+      "k": "v"
+    }
+  }
+}
+"#
+    );
+
+    let output = eno(&["convert", "--to", "yaml"], input);
+    assert_eq!(
+        (code(&output), stderr(&output)),
+        (
+            2,
+            "eno: cannot convert to 'yaml', only to json\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn query_prints_the_selected_values() {
+    let input = "# server\nhost: example.com\nports:\n- 80\n- 443\n";
+    let output = eno(&["query", "server/ports"], input);
+    assert_eq!(
+        (code(&output), stdout(&output)),
+        (0, "80\n443\n".to_string())
+    );
+
+    let output = eno(&["query", "server/missing"], input);
+    assert_eq!(code(&output), 1);
+}